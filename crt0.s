@@ -59,6 +59,10 @@ __start:
     msr CPSR_c, r0
     ldr sp, =0x3007f00
 
+    @ clear EWRAM for the .ewram section
+    mov r0, #0x01
+    swi 0x010000
+
     @ copy .data section to IWRAM
     ldr r0, =__data_lma     @ source address
     ldr r1, =__data_start   @ destination address
@@ -71,6 +75,15 @@ __start:
     addne r2, #0x04000000
     swine 0xb0000
 
+    @ zero .bss, a BIOS soft reset leaves the last run's values there
+    ldr r0, =__bss_start
+    ldr r1, =__bss_end
+    mov r2, #0
+.Lclear_bss:
+    cmp r0, r1
+    strlo r2, [r0], #4
+    blo .Lclear_bss
+
     @ jump to user code
     ldr r0, =main
     bx r0
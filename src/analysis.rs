@@ -0,0 +1,138 @@
+//! Canvas color statistics
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::Rectangle,
+    style::{PrimitiveStyle, PrimitiveStyleBuilder},
+};
+use gba::vram::bitmap::Mode3;
+
+use crate::{gba_display::GbaDisplay, overlay::Obscured, scheduler::Pass};
+
+/// Buckets for estimating unique colors, 4 bits per channel
+const BUCKETS: usize = 1 << 12;
+
+/// Pixel counts for each palette color, gathered by a read only pass
+#[derive(Clone)]
+pub struct Histogram<const N: usize> {
+    palette: [Bgr555; N],
+    counts: [u32; N],
+    seen: [u32; BUCKETS / 32],
+}
+
+impl<const N: usize> Histogram<N> {
+    pub fn new(palette: [Bgr555; N]) -> Self {
+        Self {
+            palette,
+            counts: [0; N],
+            seen: [0; BUCKETS / 32],
+        }
+    }
+
+    /// Pixels exactly matching each palette color
+    pub fn counts(&self) -> &[u32; N] {
+        &self.counts
+    }
+
+    /// Pixels not matching any palette color
+    pub fn other(&self) -> u32 {
+        let total = (Mode3::WIDTH * Mode3::HEIGHT) as u32;
+        total - self.counts.iter().sum::<u32>()
+    }
+
+    /// Approximate number of distinct colors, comparing only the top 4 bits of each channel
+    pub fn unique(&self) -> u32 {
+        self.seen.iter().map(|word| word.count_ones()).sum()
+    }
+}
+
+impl<const N: usize> Pass for Histogram<N> {
+    fn pixel(&mut self, _point: Point, color: Bgr555) -> Option<Bgr555> {
+        if let Some(i) = self.palette.iter().position(|&entry| entry == color) {
+            self.counts[i] += 1;
+        }
+        let bucket = ((color.r() as usize >> 1) << 8)
+            | ((color.g() as usize >> 1) << 4)
+            | (color.b() as usize >> 1);
+        self.seen[bucket / 32] |= 1 << (bucket % 32);
+        None
+    }
+}
+
+/// Bars in the panel, one per registered palette color
+pub const BARS: usize = 8;
+const BAR_WIDTH: i32 = 6;
+const BAR_HEIGHT: i32 = 32;
+const PANEL_WIDTH: usize = 2 + BARS * (BAR_WIDTH as usize + 2);
+const PANEL_HEIGHT: usize = BAR_HEIGHT as usize + 4;
+const PANEL_ORIGIN: Point = Point::new(
+    (Mode3::WIDTH - PANEL_WIDTH) as i32,
+    (Mode3::HEIGHT - PANEL_HEIGHT) as i32,
+);
+
+/// Bar chart of a histogram drawn over the bottom right corner of the canvas
+///
+/// The pixels underneath are saved and restored when the panel is hidden.
+pub struct HistogramPanel {
+    under: Obscured<{ PANEL_WIDTH * PANEL_HEIGHT }>,
+}
+
+impl HistogramPanel {
+    pub const fn new() -> Self {
+        Self {
+            under: Obscured::new(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.under.is_obscured()
+    }
+
+    /// Draw one bar per palette color, scaled to the most common color
+    pub fn show(
+        &mut self,
+        display: &mut GbaDisplay,
+        histogram: &Histogram<BARS>,
+    ) -> Result<(), Infallible> {
+        let bottom_right =
+            PANEL_ORIGIN + Point::new(PANEL_WIDTH as i32 - 1, PANEL_HEIGHT as i32 - 1);
+        let area = Rectangle::new(PANEL_ORIGIN, bottom_right);
+        if self.under.obscure(display, area).is_err() {
+            return Ok(());
+        }
+        area.into_styled(
+            PrimitiveStyleBuilder::new()
+                .fill_color(Bgr555::new(16, 16, 16))
+                .stroke_color(Bgr555::WHITE)
+                .stroke_width(1)
+                .build(),
+        )
+        .draw(display)?;
+
+        let max = histogram.counts().iter().copied().max().unwrap_or(0).max(1);
+        let bars = histogram.counts().iter().zip(histogram.palette.iter());
+        for (i, (&count, &color)) in bars.enumerate() {
+            let height = (count as u64 * BAR_HEIGHT as u64 / max as u64) as i32;
+            if height == 0 {
+                continue;
+            }
+            let left = PANEL_ORIGIN.x + 2 + i as i32 * (BAR_WIDTH + 2);
+            let bottom = PANEL_ORIGIN.y + PANEL_HEIGHT as i32 - 3;
+            Rectangle::new(
+                Point::new(left, bottom - height + 1),
+                Point::new(left + BAR_WIDTH - 1, bottom),
+            )
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(display)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the canvas under the panel
+    pub fn hide(&mut self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        self.under.reveal(display);
+        Ok(())
+    }
+}
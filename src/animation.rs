@@ -0,0 +1,149 @@
+//! Looping sprite animation from a TGA sprite sheet
+//!
+//! Only the frame on screen is kept in sprite tile memory. Each step decodes
+//! the next frame's cell from the sheet and uploads it over the same tiles,
+//! so a sheet costs one sprite's worth of tiles however many frames it has.
+
+use embedded_graphics::{image::Image, pixelcolor::Bgr555, prelude::*};
+use gba::oam::write_obj_attributes;
+use tinytga::Tga;
+
+use crate::{
+    assets::Asset,
+    charblock::{CharBlock, TileError},
+    color,
+    gba_display::PaletteColor,
+    sprite::{ObjSize, SpriteBuilder, SpriteTiles},
+};
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Tiles of the frame on screen, after the frame step label
+const FIRST_TILE: usize = 130;
+
+/// OAM slot after the frame step label
+const SLOT: usize = 92;
+
+/// Sheet pixels of this color are transparent
+pub const TRANSPARENT_KEY: Bgr555 = Bgr555::new(31, 0, 31);
+
+/// Frames laid out left to right in one row of square cells
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SheetLayout {
+    pub cell: ObjSize,
+    pub frames: usize,
+}
+
+/// A sprite stepping through the frames of a sheet, looping forever
+pub struct Animation {
+    sheet: Tga<'static>,
+    layout: SheetLayout,
+    /// `palette[i]` is object palette slot `i + 1`, see `register_palette`
+    palette: &'static [Bgr555],
+    /// Frames each step stays on screen
+    period: u16,
+    frame: usize,
+    ticks: u16,
+    position: Point,
+    visible: bool,
+}
+
+impl Animation {
+    /// Upload the first frame, `None` if the asset isn't a TGA with every cell of `layout`
+    pub fn new(
+        asset: &'static Asset,
+        layout: SheetLayout,
+        palette: &'static [Bgr555],
+        period: u16,
+    ) -> Option<Self> {
+        let sheet = asset.tga()?;
+        let size = Image::<Tga, Bgr555>::new(&sheet, Point::zero()).size();
+        let cell = layout.cell.pixels();
+        if layout.frames == 0 || size.width < cell * layout.frames as u32 || size.height < cell {
+            return None;
+        }
+        let animation = Self {
+            sheet,
+            layout,
+            palette,
+            period: period.max(1),
+            frame: 0,
+            ticks: 0,
+            position: Point::zero(),
+            visible: false,
+        };
+        animation.upload().ok()?;
+        Some(animation)
+    }
+
+    /// Show the animation with its top left corner at `point`
+    pub fn show(&mut self, point: Point) {
+        self.position = point;
+        self.visible = true;
+        self.write_oam();
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.write_oam();
+    }
+
+    /// Count one frame, uploading the next cell when the step is over
+    pub fn tick(&mut self) -> Result<(), TileError> {
+        if !self.visible {
+            return Ok(());
+        }
+        self.ticks += 1;
+        if self.ticks < self.period {
+            return Ok(());
+        }
+        self.ticks = 0;
+        let next = (self.frame + 1) % self.layout.frames;
+        if next != self.frame {
+            self.frame = next;
+            self.upload()?;
+        }
+        Ok(())
+    }
+
+    /// Decode the current frame's cell into the sprite's tiles
+    fn upload(&self) -> Result<(), TileError> {
+        let side = self.layout.cell.pixels() as i32;
+        let left = side * self.frame as i32;
+        let mut tiles = SpriteTiles::new(self.layout.cell);
+        let image: Image<Tga, Bgr555> = Image::new(&self.sheet, Point::zero());
+        for Pixel(point, color) in &image {
+            if point.x < left || point.x >= left + side || point.y >= side {
+                continue;
+            }
+            if color == TRANSPARENT_KEY {
+                continue;
+            }
+            if let Some(slot) = self.slot(color) {
+                let local = Point::new(point.x - left, point.y);
+                Pixel(local, PaletteColor::new(slot)).draw(&mut tiles)?;
+            }
+        }
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        for (i, &tile) in tiles.used().iter().enumerate() {
+            block.set_tile(FIRST_TILE + i, tile)?;
+        }
+        Ok(())
+    }
+
+    /// Object palette slot of the palette color closest to `color`
+    fn slot(&self, color: Bgr555) -> Option<u8> {
+        let nearest = color::nearest(color, self.palette)?;
+        let index = self.palette.iter().position(|&entry| entry == nearest)?;
+        Some(index as u8 + 1)
+    }
+
+    fn write_oam(&self) {
+        let sprite = SpriteBuilder::new(FIRST_TILE)
+            .size(self.layout.cell)
+            .position(self.position)
+            .hidden(!self.visible)
+            .build();
+        write_obj_attributes(SLOT, sprite);
+    }
+}
@@ -0,0 +1,143 @@
+//! Page through every embedded image full screen
+//!
+//! Opened from the menu, which waits in the canvas snapshot like the
+//! canvas does under the viewer. An image whose header doesn't parse is
+//! shown as a placeholder, so a broken asset is spotted rather than
+//! crashing the browser.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    image::Image,
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::{Line, Rectangle},
+    style::{PrimitiveStyle, TextStyle},
+};
+use gba::vram::bitmap::Mode3;
+use tinytga::Tga;
+
+use crate::{
+    assets::{self, Asset},
+    blit, canvas, fmt,
+    gba_display::GbaDisplay,
+    input::{Input, Key},
+};
+
+const CAPTION_HEIGHT: i32 = 10;
+const PLACEHOLDER: Bgr555 = Bgr555::new(8, 8, 8);
+
+/// Index into `assets::ALL` of the image shown
+pub struct AssetBrowser {
+    index: usize,
+}
+
+impl AssetBrowser {
+    pub const fn new() -> Self {
+        Self { index: 0 }
+    }
+
+    /// Snapshot the screen and show the first image
+    pub fn open(&mut self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        canvas::snapshot_canvas();
+        self.index = 0;
+        self.draw(display)
+    }
+
+    /// Left and right page through the images, returns true once B closes
+    /// the browser and the screen is back as it was
+    pub fn update(&mut self, input: &Input, display: &mut GbaDisplay) -> Result<bool, Infallible> {
+        let count = assets::ALL.len();
+        let previous = self.index;
+        if input.just_pressed(Key::Left) {
+            self.index = (self.index + count - 1) % count;
+        }
+        if input.just_pressed(Key::Right) {
+            self.index = (self.index + 1) % count;
+        }
+        if self.index != previous {
+            self.draw(display)?;
+        }
+        if input.just_pressed(Key::B) {
+            canvas::restore_canvas();
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn draw(&self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        let asset = &assets::ALL[self.index];
+        let size = match asset.tga() {
+            Some(tga) => {
+                blit::draw_fitted(display, &tga)?;
+                Some(Image::<Tga, Bgr555>::new(&tga, Point::zero()).size())
+            }
+            None => {
+                draw_placeholder(display)?;
+                None
+            }
+        };
+        draw_caption(display, asset, size, self.index)
+    }
+}
+
+/// Gray screen crossed out
+fn draw_placeholder(display: &mut GbaDisplay) -> Result<(), Infallible> {
+    display.clear(PLACEHOLDER)?;
+    let (right, bottom) = (Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1);
+    let stroke = PrimitiveStyle::with_stroke(Bgr555::RED, 1);
+    Line::new(Point::zero(), Point::new(right, bottom))
+        .into_styled(stroke)
+        .draw(display)?;
+    Line::new(Point::new(right, 0), Point::new(0, bottom))
+        .into_styled(stroke)
+        .draw(display)
+}
+
+/// "2/2 mascot 64x16" along the bottom, or "can't decode" without a size
+fn draw_caption(
+    display: &mut GbaDisplay,
+    asset: &Asset,
+    size: Option<Size>,
+    index: usize,
+) -> Result<(), Infallible> {
+    let top = Mode3::HEIGHT as i32 - CAPTION_HEIGHT;
+    Rectangle::new(
+        Point::new(0, top),
+        Point::new(Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1),
+    )
+    .into_styled(PrimitiveStyle::with_fill(Bgr555::BLACK))
+    .draw(display)?;
+    let point = Point::new(2, top + 1);
+    let mut buf = [0; 12];
+    let page = fmt::with_number(&mut buf, "", index as u32 + 1, "/").unwrap_or_default();
+    let point = draw_word(display, page, point, Bgr555::WHITE)?;
+    let mut buf = [0; 12];
+    let count = fmt::with_number(&mut buf, "", assets::ALL.len() as u32, " ").unwrap_or_default();
+    let point = draw_word(display, count, point, Bgr555::WHITE)?;
+    let point = draw_word(display, asset.name, point, Bgr555::WHITE)?;
+    let point = point + Point::new(6, 0);
+    match size {
+        Some(size) => {
+            let mut buf = [0; 12];
+            let width = fmt::with_number(&mut buf, "", size.width, "x").unwrap_or_default();
+            let point = draw_word(display, width, point, Bgr555::WHITE)?;
+            let style = TextStyle::new(Font6x8, Bgr555::WHITE);
+            fmt::draw_number(display, size.height, point, style)
+        }
+        None => draw_word(display, "can't decode", point, Bgr555::RED).map(|_| ()),
+    }
+}
+
+/// Draw `text` at `point`, returns where the text after it starts
+fn draw_word(
+    display: &mut GbaDisplay,
+    text: &str,
+    point: Point,
+    color: Bgr555,
+) -> Result<Point, Infallible> {
+    Text::new(text, point)
+        .into_styled(TextStyle::new(Font6x8, color))
+        .draw(display)?;
+    Ok(point + Point::new(6 * text.len() as i32, 0))
+}
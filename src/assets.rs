@@ -0,0 +1,39 @@
+//! Images embedded in the ROM
+//!
+//! TGAs are converted with assets/convert-to-tga.sh so their raw pixels
+//! are already in Bgr555 order.
+
+use tinytga::Tga;
+
+/// An embedded image
+pub struct Asset {
+    pub name: &'static str,
+    pub data: &'static [u8],
+}
+
+impl Asset {
+    /// Parse the TGA header, `None` if the data is malformed
+    pub fn tga(&self) -> Option<Tga<'static>> {
+        Tga::from_slice(self.data).ok()
+    }
+}
+
+/// Default canvas background
+pub const AMY: Asset = Asset {
+    name: "amy",
+    data: include_bytes!("../assets/amy.tga"),
+};
+
+/// Bouncing face, four 16x16 frames in a row for `animation::Animation`
+pub const MASCOT: Asset = Asset {
+    name: "mascot",
+    data: include_bytes!("../assets/mascot.tga"),
+};
+
+/// Every embedded image
+pub static ALL: [Asset; 2] = [AMY, MASCOT];
+
+/// Look up an asset by name
+pub fn find(name: &str) -> Option<&'static Asset> {
+    ALL.iter().find(|asset| asset.name == name)
+}
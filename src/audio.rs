@@ -0,0 +1,130 @@
+//! Direct Sound groundwork, streams a looping tone through FIFO A
+//!
+//! Timer 1 overflows once per sample and FIFO A plays a sample on each
+//! overflow. DMA 1 refills the FIFO from the playing buffer whenever it
+//! runs low. Each of the two buffers holds exactly one frame of samples,
+//! so the vblank interrupt restarts DMA 1 on the buffer it mixed last
+//! frame, then mixes the other one. Timer 0 belongs to `timer`; DMA 0, 2
+//! and 3 and timers 2 and 3 stay free.
+//!
+//! Mixing in the interrupt means the buffer being mixed can never be the
+//! one playing, however late the main loop runs. Mixing has to stay
+//! short.
+//!
+//! The registers are written by address: this is the only user of them,
+//! and half the DMA API takes raw pointers anyway.
+
+#![allow(unsafe_code)]
+
+use gba::{
+    io::timers::{TimerControlSetting, TimerTickRate, TM1CNT_H, TM1CNT_L},
+    sync::Static,
+};
+use voladdress::VolAddress;
+
+use crate::irq;
+
+/// CPU cycles per sample, 280896 cycles per frame divide into whole samples
+const CYCLES_PER_SAMPLE: u16 = 924;
+
+/// Samples played each frame, at about 18157 Hz
+pub const SAMPLES_PER_FRAME: usize = 304;
+
+/// One period of a triangle wave, 567 Hz at the sample rate
+const TONE: [i8; 32] = [
+    -64, -56, -48, -40, -32, -24, -16, -8, 0, 8, 16, 24, 32, 40, 48, 56, 64, 56, 48, 40, 32, 24,
+    16, 8, 0, -8, -16, -24, -32, -40, -48, -56,
+];
+
+/// Full volume DMA sound A on both speakers, clocked by timer 1, FIFO reset,
+/// and the square channels `music` plays on at full volume
+const SOUND_A: u16 = 0x0F06;
+/// Master sound enable
+const SOUND_ON: u16 = 0x0080;
+/// Fixed destination, repeat, 32 bit units, FIFO timing, enabled
+const DMA_FIFO: u16 = 0xB640;
+
+const SOUNDCNT_H: usize = 0x0400_0082;
+const SOUNDCNT_X: usize = 0x0400_0084;
+const FIFO_A: usize = 0x0400_00A0;
+const DMA1SAD: usize = 0x0400_00BC;
+const DMA1DAD: usize = 0x0400_00C0;
+const DMA1CNT_H: usize = 0x0400_00C6;
+
+/// DMA reads words, so each buffer must start word aligned
+#[repr(align(4))]
+struct Buffers([[i8; SAMPLES_PER_FRAME]; 2]);
+
+/// Only `mix` writes, and only the buffer DMA isn't reading
+static mut BUFFERS: Buffers = Buffers([[0; SAMPLES_PER_FRAME]; 2]);
+
+/// Index of the buffer DMA 1 is playing
+static PLAYING: Static<usize> = Static::new(0);
+
+/// Position in `TONE` of the next sample to mix
+static PHASE: Static<usize> = Static::new(0);
+
+/// Mix both buffers and start playback, register `on_vblank` first
+pub fn init() {
+    // a vblank in between would start DMA on a half mixed buffer
+    irq::free(init_masked);
+}
+
+fn init_masked() {
+    mix(0);
+    mix(1);
+    PLAYING.write(0);
+
+    // SAFETY: these are the documented sound registers, written with values
+    // that are valid for them
+    unsafe {
+        VolAddress::<u16>::new(SOUNDCNT_X).write(SOUND_ON);
+        VolAddress::<u16>::new(SOUNDCNT_H).write(SOUND_A);
+    }
+    TM1CNT_L.write(0u16.wrapping_sub(CYCLES_PER_SAMPLE)); // reload value
+    TM1CNT_H.write(
+        TimerControlSetting::new()
+            .with_tick_rate(TimerTickRate::CPU1)
+            .with_enabled(true),
+    );
+    start_dma(0);
+}
+
+/// Vblank interrupt handler, switches playback to the other buffer and
+/// mixes the next frame into the one that stopped
+///
+/// Runs in the interrupt so the switch lands on the frame boundary
+/// whatever the main loop is doing.
+pub fn on_vblank() {
+    let next = 1 - PLAYING.read();
+    PLAYING.write(next);
+    start_dma(next);
+    mix(1 - next);
+}
+
+/// Mix the next frame of samples into buffer `idle`
+///
+/// Only call with interrupts masked, on the buffer DMA isn't reading.
+fn mix(idle: usize) {
+    let mut phase = PHASE.read();
+    // SAFETY: DMA only reads the playing buffer, which `idle` isn't, and
+    // interrupts are masked so nothing else touches the buffers
+    let buffer = unsafe { &mut BUFFERS.0[idle] };
+    for sample in buffer.iter_mut() {
+        *sample = TONE[phase];
+        phase = (phase + 1) % TONE.len();
+    }
+    PHASE.write(phase);
+}
+
+fn start_dma(buffer: usize) {
+    // SAFETY: the source is a static buffer that outlives the transfer and
+    // the destination is the FIFO, DMA is stopped before it is repointed
+    unsafe {
+        let control = VolAddress::<u16>::new(DMA1CNT_H);
+        control.write(0);
+        VolAddress::<usize>::new(DMA1SAD).write(BUFFERS.0[buffer].as_ptr() as usize);
+        VolAddress::<usize>::new(DMA1DAD).write(FIFO_A);
+        control.write(DMA_FIFO);
+    }
+}
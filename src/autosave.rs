@@ -0,0 +1,48 @@
+/// Decides when the canvas should be written to SRAM
+///
+/// Saves are skipped while the canvas is unchanged, since SRAM has limited
+/// write endurance.
+pub struct Autosave {
+    interval: Option<u32>,
+    changed_at: Option<u32>,
+}
+
+impl Autosave {
+    /// Save `interval` seconds after the first unsaved change, `None` disables autosave
+    pub const fn new(interval: Option<u32>) -> Self {
+        Self {
+            interval,
+            changed_at: None,
+        }
+    }
+
+    /// Record that the canvas changed at `now` seconds
+    pub fn mark_dirty(&mut self, now: u32) {
+        if self.changed_at.is_none() {
+            self.changed_at = Some(now);
+        }
+    }
+
+    /// Check if a save is due at `now` seconds
+    pub fn is_due(&self, now: u32) -> bool {
+        match (self.interval, self.changed_at) {
+            (Some(interval), Some(changed_at)) => now.wrapping_sub(changed_at) >= interval,
+            _ => false,
+        }
+    }
+
+    /// Check if the canvas changed since it was last saved
+    pub fn is_dirty(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    /// Record that the canvas was saved
+    pub fn mark_saved(&mut self) {
+        self.changed_at = None;
+    }
+
+    /// Record that saving failed at `now` seconds, retrying an interval later
+    pub fn mark_failed(&mut self, now: u32) {
+        self.changed_at = Some(now);
+    }
+}
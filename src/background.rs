@@ -0,0 +1,26 @@
+use core::convert::Infallible;
+use embedded_graphics::{image::Image, pixelcolor::Bgr555, prelude::*};
+use gba::vram::bitmap::Mode3;
+use tinytga::Tga;
+
+use crate::{assets, gba_display::GbaDisplay};
+
+/// Uncompressed, top left origin, 16 bit Bgr555 TGA the size of the display
+const TGA: &[u8] = assets::AMY.data;
+
+/// Length of the fixed TGA header, not counting the image id
+const HEADER_LEN: usize = 18;
+
+/// Draw the background image over the whole display
+pub fn draw(display: &mut GbaDisplay) -> Result<(), Infallible> {
+    let tga = Tga::from_slice(TGA).unwrap();
+    let image: Image<Tga, Bgr555> = Image::new(&tga, Point::zero());
+    image.draw(display)?;
+    Ok(())
+}
+
+/// Raw Bgr555 value of the background image at `x`, `y`
+pub fn pixel(x: usize, y: usize) -> u16 {
+    let offset = HEADER_LEN + TGA[0] as usize + 2 * (x + y * Mode3::WIDTH);
+    u16::from_le_bytes([TGA[offset], TGA[offset + 1]])
+}
@@ -0,0 +1,75 @@
+//! Draw images with placement and scaling
+
+use core::convert::Infallible;
+use embedded_graphics::{image::Image, pixelcolor::Bgr555, prelude::*};
+use tinytga::Tga;
+
+use crate::gba_display::GbaDisplay;
+
+/// How an image covers the display
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Placement {
+    /// Centered and shrunk to fit, see `draw_fitted`
+    Fit,
+    /// Repeated across the display, see `tile_image`
+    Tile,
+}
+
+/// Draw `tga` over the whole display with `placement`
+pub fn draw(display: &mut GbaDisplay, tga: &Tga, placement: Placement) -> Result<(), Infallible> {
+    match placement {
+        Placement::Fit => draw_fitted(display, tga),
+        Placement::Tile => tile_image(display, tga),
+    }
+}
+
+/// Draw `tga` centered on a black display, shrinking it to fit if it's too large
+///
+/// Shrinking keeps the aspect ratio and drops source pixels (nearest neighbor).
+pub fn draw_fitted(display: &mut GbaDisplay, tga: &Tga) -> Result<(), Infallible> {
+    let image: Image<Tga, Bgr555> = Image::new(tga, Point::zero());
+    let size = image.size();
+    let screen = display.size();
+
+    // scale by num / den using the tighter axis, never enlarging
+    let (num, den) = if screen.width * size.height < screen.height * size.width {
+        (screen.width, size.width)
+    } else {
+        (screen.height, size.height)
+    };
+    let (num, den) = if num >= den { (1, 1) } else { (num, den) };
+    let scaled = Size::new(size.width * num / den, size.height * num / den);
+    let offset = Point::new(
+        ((screen.width - scaled.width) / 2) as i32,
+        ((screen.height - scaled.height) / 2) as i32,
+    );
+
+    display.clear(Bgr555::BLACK)?;
+    for Pixel(point, color) in &image {
+        let x = point.x as u32 * num / den;
+        let y = point.y as u32 * num / den;
+        Pixel(offset + Point::new(x as i32, y as i32), color).draw(display)?;
+    }
+    Ok(())
+}
+
+/// Fill the display with copies of `tga` from the top left, clipping the
+/// partial copies at the right and bottom edges
+pub fn tile_image(display: &mut GbaDisplay, tga: &Tga) -> Result<(), Infallible> {
+    let image: Image<Tga, Bgr555> = Image::new(tga, Point::zero());
+    let size = image.size();
+    let screen = display.size();
+    if size.width == 0 || size.height == 0 {
+        return display.clear(Bgr555::BLACK);
+    }
+
+    // each source pixel is decoded once and written to every copy
+    for Pixel(point, color) in &image {
+        for y in (point.y as u32..screen.height).step_by(size.height as usize) {
+            for x in (point.x as u32..screen.width).step_by(size.width as usize) {
+                Pixel(Point::new(x as i32, y as i32), color).draw(display)?;
+            }
+        }
+    }
+    Ok(())
+}
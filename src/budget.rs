@@ -0,0 +1,68 @@
+//! Per frame work budget for long running operations
+//!
+//! Writing VRAM outside of vblank tears, so resumable work checks
+//! `Budget::exhausted` and pauses until the next frame.
+
+use gba::{io::display::VCOUNT, vram::bitmap::Mode3};
+
+/// When a frame's budget runs out
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BudgetLimit {
+    /// After a fixed number of pixels, roughly a read plus a write each
+    Pixels(usize),
+    /// Once the display leaves vblank and starts drawing line 0
+    VBlank,
+}
+
+/// Work spent so far this frame
+pub struct Budget {
+    limit: BudgetLimit,
+    spent: usize,
+}
+
+impl Budget {
+    /// Start a fresh budget, call once per frame right after vblank
+    pub fn start(limit: BudgetLimit) -> Self {
+        Self { limit, spent: 0 }
+    }
+
+    /// Record `pixels` worth of work
+    pub fn spend(&mut self, pixels: usize) {
+        self.spent += pixels;
+    }
+
+    /// Check if work should pause until the next frame
+    pub fn exhausted(&self) -> bool {
+        match self.limit {
+            BudgetLimit::Pixels(pixels) => self.spent >= pixels,
+            BudgetLimit::VBlank => (VCOUNT.read() as usize) < Mode3::HEIGHT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_budget_runs_out_at_its_limit() {
+        let mut budget = Budget::start(BudgetLimit::Pixels(100));
+        assert!(!budget.exhausted());
+        budget.spend(99);
+        assert!(!budget.exhausted());
+        budget.spend(1);
+        assert!(budget.exhausted());
+    }
+
+    #[test]
+    fn overspending_stays_exhausted() {
+        let mut budget = Budget::start(BudgetLimit::Pixels(10));
+        budget.spend(25);
+        assert!(budget.exhausted());
+    }
+
+    #[test]
+    fn zero_budget_starts_exhausted() {
+        assert!(Budget::start(BudgetLimit::Pixels(0)).exhausted());
+    }
+}
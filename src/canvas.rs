@@ -0,0 +1,135 @@
+//! Full copy of the canvas kept in EWRAM
+//!
+//! A Mode3 canvas is 75KB, more than all of IWRAM, so the copy lives in
+//! the `.ewram` section, which crt0 clears at boot. Use it to show another
+//! full screen image over the canvas without losing the painting, or as
+//! the unchanging source of a pass that reads neighboring pixels.
+
+use core::ops::Range;
+use gba::{
+    sync::{Mutex, Static},
+    vram::bitmap::Mode3,
+    Color,
+};
+
+const PIXELS: usize = Mode3::WIDTH * Mode3::HEIGHT;
+
+/// Canvas pixels in row order
+struct Pixels([u16; PIXELS]);
+
+impl Pixels {
+    /// Copy every pixel `read` gives for `x`, `y`
+    fn capture(&mut self, read: impl Fn(usize, usize) -> u16) {
+        for (i, pixel) in self.0.iter_mut().enumerate() {
+            *pixel = read(i % Mode3::WIDTH, i / Mode3::WIDTH);
+        }
+    }
+
+    /// Hand every pixel back to `write` with its `x`, `y`
+    fn restore(&self, mut write: impl FnMut(usize, usize, u16)) {
+        for (i, &pixel) in self.0.iter().enumerate() {
+            write(i % Mode3::WIDTH, i / Mode3::WIDTH, pixel);
+        }
+    }
+}
+
+#[link_section = ".ewram"]
+static SNAPSHOT: Mutex<Pixels> = Mutex::new(Pixels([0; PIXELS]));
+
+/// Whether `SNAPSHOT` holds a canvas that hasn't been restored yet
+static TAKEN: Static<bool> = Static::new(false);
+
+/// Copy every canvas pixel into the snapshot, replacing any earlier one
+pub fn snapshot_canvas() {
+    SNAPSHOT
+        .lock()
+        .capture(|x, y| Mode3::read(x, y).unwrap_or(Color(0)).0);
+    TAKEN.write(true);
+}
+
+/// Snapshot pixel at `x`, `y`, `None` off screen or without a snapshot
+pub fn snapshot_pixel(x: i32, y: i32) -> Option<Color> {
+    let inside = x >= 0 && y >= 0 && x < Mode3::WIDTH as i32 && y < Mode3::HEIGHT as i32;
+    if !TAKEN.read() || !inside {
+        return None;
+    }
+    Some(Color(
+        SNAPSHOT.lock().0[x as usize + y as usize * Mode3::WIDTH],
+    ))
+}
+
+/// Forget the snapshot without touching the display
+pub fn discard_snapshot() {
+    TAKEN.write(false);
+}
+
+/// Put the snapshot's `columns` back on the display and forget it, returns
+/// false if there is none
+pub fn restore_columns(columns: Range<usize>) -> bool {
+    if !TAKEN.read() {
+        return false;
+    }
+    let snapshot = SNAPSHOT.lock();
+    for y in 0..Mode3::HEIGHT {
+        for x in columns.clone() {
+            if let Some(&pixel) = snapshot.0.get(x + y * Mode3::WIDTH) {
+                Mode3::write(x, y, Color(pixel));
+            }
+        }
+    }
+    TAKEN.write(false);
+    true
+}
+
+/// Put the snapshot back on the display exactly, returns false if there is none
+pub fn restore_canvas() -> bool {
+    if !TAKEN.read() {
+        return false;
+    }
+    SNAPSHOT
+        .lock()
+        .restore(|x, y, pixel| Mode3::write(x, y, Color(pixel)));
+    TAKEN.write(false);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_is_exact() {
+        // values spread over all 16 bits, bit 15 included
+        let pattern = |x: usize, y: usize| (x * 7919 + y * 104_729) as u16;
+        let mut snapshot = Box::new(Pixels([0; PIXELS]));
+        snapshot.capture(pattern);
+
+        let mut canvas = vec![0; PIXELS];
+        snapshot.restore(|x, y, pixel| canvas[x + y * Mode3::WIDTH] = pixel);
+        for (i, &pixel) in canvas.iter().enumerate() {
+            assert_eq!(
+                pixel,
+                pattern(i % Mode3::WIDTH, i / Mode3::WIDTH),
+                "pixel {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn capture_reads_every_pixel_once_in_row_order() {
+        let mut next = 0;
+        let mut snapshot = Box::new(Pixels([0; PIXELS]));
+        let reads = core::cell::Cell::new(0);
+        snapshot.capture(|x, y| {
+            reads.set(reads.get() + 1);
+            (x + y * Mode3::WIDTH) as u16
+        });
+        assert_eq!(reads.get(), PIXELS);
+        snapshot.restore(|x, y, pixel| {
+            assert_eq!((x, y), (next % Mode3::WIDTH, next / Mode3::WIDTH));
+            assert_eq!(pixel as usize, next);
+            next += 1;
+        });
+    }
+}
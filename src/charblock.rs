@@ -0,0 +1,95 @@
+//! Bounds checked access to 8bpp tiles in VRAM character blocks
+
+use core::convert::Infallible;
+use gba::vram::{get_8bpp_character_block, Tile8bpp};
+
+/// Character blocks in VRAM, 0 to 3 for backgrounds and 4 and 5 for sprites
+pub const BLOCKS: usize = 6;
+
+/// 8bpp tiles in one 16KB character block
+pub const TILES_8BPP: usize = 256;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TileError {
+    /// There is no character block with this number
+    Block(usize),
+    /// The tile index is past the end of the block
+    Index(usize),
+}
+
+impl From<Infallible> for TileError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// One character block viewed as 8bpp tiles
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CharBlock {
+    block: usize,
+}
+
+impl CharBlock {
+    pub fn new(block: usize) -> Result<Self, TileError> {
+        if block < BLOCKS {
+            Ok(Self { block })
+        } else {
+            Err(TileError::Block(block))
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        TILES_8BPP
+    }
+
+    pub fn set_tile(&self, index: usize, tile: Tile8bpp) -> Result<(), TileError> {
+        self.check(index)?;
+        get_8bpp_character_block(self.block)
+            .index(index)
+            .write(tile);
+        Ok(())
+    }
+
+    pub fn get_tile(&self, index: usize) -> Result<Tile8bpp, TileError> {
+        self.check(index)?;
+        Ok(get_8bpp_character_block(self.block).index(index).read())
+    }
+
+    fn check(&self, index: usize) -> Result<(), TileError> {
+        if index < self.capacity() {
+            Ok(())
+        } else {
+            Err(TileError::Index(index))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_past_vram_are_rejected() {
+        assert!(CharBlock::new(BLOCKS - 1).is_ok());
+        assert_eq!(CharBlock::new(BLOCKS), Err(TileError::Block(BLOCKS)));
+    }
+
+    #[test]
+    fn last_8bpp_tile_is_in_bounds() {
+        let block = CharBlock::new(4).unwrap();
+        assert_eq!(block.check(0), Ok(()));
+        assert_eq!(block.check(TILES_8BPP - 1), Ok(()));
+        assert_eq!(block.check(TILES_8BPP), Err(TileError::Index(TILES_8BPP)));
+    }
+
+    #[test]
+    fn out_of_range_tiles_fail_before_touching_vram() {
+        let block = CharBlock::new(5).unwrap();
+        let index = TILES_8BPP;
+        assert_eq!(block.get_tile(index).err(), Some(TileError::Index(index)));
+        assert_eq!(
+            block.set_tile(index, Tile8bpp([0; 16])),
+            Err(TileError::Index(index))
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Fletcher-16 checksum of saved data, catches torn writes and bit rot
+
+/// Running Fletcher-16 over bytes pushed one at a time
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Fletcher16 {
+    a: u16,
+    b: u16,
+}
+
+impl Fletcher16 {
+    pub const fn new() -> Self {
+        Self { a: 0, b: 0 }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.a = (self.a + byte as u16) % 255;
+        self.b = (self.b + self.a) % 255;
+    }
+
+    pub fn value(&self) -> u16 {
+        self.b << 8 | self.a
+    }
+}
+
+/// Fletcher-16 of all of `bytes`
+pub fn fletcher16(bytes: &[u8]) -> u16 {
+    let mut sum = Fletcher16::new();
+    bytes.iter().for_each(|&byte| sum.push(byte));
+    sum.value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_value() {
+        // worked example from the Fletcher's checksum definition
+        assert_eq!(fletcher16(b"abcde"), 0xC8F0);
+    }
+
+    #[test]
+    fn running_sum_matches_the_whole() {
+        let mut sum = Fletcher16::new();
+        b"abcde".iter().for_each(|&byte| sum.push(byte));
+        assert_eq!(sum.value(), fletcher16(b"abcde"));
+    }
+
+    #[test]
+    fn flipped_bit_is_detected() {
+        let bytes = *b"canvas payload";
+        for i in 0..bytes.len() {
+            for bit in 0..8 {
+                let mut flipped = bytes;
+                flipped[i] ^= 1 << bit;
+                assert_ne!(
+                    fletcher16(&flipped),
+                    fletcher16(&bytes),
+                    "byte {} bit {}",
+                    i,
+                    bit
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn swapped_bytes_are_detected() {
+        assert_ne!(fletcher16(&[1, 2]), fletcher16(&[2, 1]));
+    }
+}
@@ -0,0 +1,74 @@
+use embedded_graphics::{drawable::Pixel, geometry::Size, pixelcolor::Bgr555, prelude::*};
+
+use crate::gba_display::GbaDisplay;
+
+/// Largest region that can be copied, costs 2 bytes of stack per pixel
+pub const MAX_WIDTH: u32 = 48;
+pub const MAX_HEIGHT: u32 = 48;
+
+/// Size `copy` would copy for the region spanning corners `a` and `b`
+pub fn copy_size(a: Point, b: Point) -> Size {
+    Size::new(
+        (((a.x - b.x).abs() + 1) as u32).min(MAX_WIDTH),
+        (((a.y - b.y).abs() + 1) as u32).min(MAX_HEIGHT),
+    )
+}
+
+/// Rectangular region of the canvas copied into RAM
+pub struct Clipboard {
+    pixels: [Bgr555; (MAX_WIDTH * MAX_HEIGHT) as usize],
+    size: Size,
+}
+
+impl Clipboard {
+    pub const fn new() -> Self {
+        Self {
+            pixels: [Bgr555::BLACK; (MAX_WIDTH * MAX_HEIGHT) as usize],
+            size: Size::zero(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == Size::zero()
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn clear(&mut self) {
+        self.size = Size::zero();
+    }
+
+    /// Copy the region spanning corners `a` and `b`
+    ///
+    /// Regions larger than `MAX_WIDTH` by `MAX_HEIGHT` are cropped, keeping
+    /// the top left. Returns the size actually copied.
+    pub fn copy(&mut self, display: &GbaDisplay, a: Point, b: Point) -> Size {
+        let top_left = Point::new(a.x.min(b.x), a.y.min(b.y));
+        self.size = copy_size(a, b);
+
+        for y in 0..self.size.height {
+            for x in 0..self.size.width {
+                let point = top_left + Point::new(x as i32, y as i32);
+                self.pixels[(x + y * self.size.width) as usize] =
+                    display.get_pixel(point).unwrap_or(Bgr555::BLACK);
+            }
+        }
+        self.size
+    }
+
+    /// Pixels of the copied region with its top left corner at `origin`
+    ///
+    /// Pixels can fall off screen, draw targets are expected to clip them.
+    pub fn pixels(&self, origin: Point) -> impl Iterator<Item = Pixel<Bgr555>> + '_ {
+        let width = self.size.width;
+        self.pixels[..(self.size.width * self.size.height) as usize]
+            .iter()
+            .enumerate()
+            .map(move |(i, &color)| {
+                let offset = Point::new((i as u32 % width) as i32, (i as u32 / width) as i32);
+                Pixel(origin + offset, color)
+            })
+    }
+}
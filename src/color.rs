@@ -0,0 +1,372 @@
+//! Bgr555 color math
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+/// Blend from `a` to `b` by `amount` sixteenths, the same steps as the hardware blender
+pub fn blend(a: Bgr555, b: Bgr555, amount: u8) -> Bgr555 {
+    let amount = amount.min(16) as u16;
+    let mix = |a: u8, b: u8| ((a as u16 * (16 - amount) + b as u16 * amount) / 16) as u8;
+    Bgr555::new(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
+
+/// How a painted color combines with the canvas pixel under it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// Replace the canvas pixel
+    Normal,
+    /// Add channels, saturating at full intensity
+    Additive,
+    /// Subtract the painted channels from the canvas, saturating at zero
+    Subtractive,
+    /// Multiply channels as fractions of full intensity, always darkens
+    Multiply,
+}
+
+impl BlendMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Self::Normal => Self::Additive,
+            Self::Additive => Self::Subtractive,
+            Self::Subtractive => Self::Multiply,
+            Self::Multiply => Self::Normal,
+        }
+    }
+
+    /// Result of painting `color` over `canvas`
+    pub fn apply(self, canvas: Bgr555, color: Bgr555) -> Bgr555 {
+        let mix = |f: fn(u8, u8) -> u8| {
+            Bgr555::new(
+                f(canvas.r(), color.r()),
+                f(canvas.g(), color.g()),
+                f(canvas.b(), color.b()),
+            )
+        };
+        match self {
+            Self::Normal => color,
+            Self::Additive => mix(|a, b| (a + b).min(31)),
+            Self::Subtractive => mix(|a, b| a.saturating_sub(b)),
+            // 31 * 31 / 31 keeps white as the identity
+            Self::Multiply => mix(|a, b| ((a as u16 * b as u16 + 15) / 31) as u8),
+        }
+    }
+}
+
+/// Entry of `palette` closest to `color` by squared distance in 5 bit channel space,
+/// the first of any ties
+pub fn nearest(color: Bgr555, palette: &[Bgr555]) -> Option<Bgr555> {
+    let distance = |entry: &Bgr555| {
+        let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        channel(color.r(), entry.r())
+            + channel(color.g(), entry.g())
+            + channel(color.b(), entry.b())
+    };
+    palette.iter().copied().min_by_key(distance)
+}
+
+/// Channel wise mean of `colors`, rounded to nearest, `None` if there are none
+pub fn average(colors: impl IntoIterator<Item = Bgr555>) -> Option<Bgr555> {
+    let (mut r, mut g, mut b, mut n) = (0u32, 0u32, 0u32, 0u32);
+    for color in colors {
+        r += color.r() as u32;
+        g += color.g() as u32;
+        b += color.b() as u32;
+        n += 1;
+    }
+    if n == 0 {
+        return None;
+    }
+    let mean = |sum: u32| ((sum + n / 2) / n) as u8;
+    Some(Bgr555::new(mean(r), mean(g), mean(b)))
+}
+
+/// Sum of absolute channel differences, 0 for equal colors
+pub fn difference(a: Bgr555, b: Bgr555) -> u32 {
+    let channel = |a: u8, b: u8| (a as i32 - b as i32).abs() as u32;
+    channel(a.r(), b.r()) + channel(a.g(), b.g()) + channel(a.b(), b.b())
+}
+
+/// Perceived brightness from 0 to 31, green counts most and blue least
+pub fn brightness(color: Bgr555) -> u8 {
+    ((3 * color.r() as u32 + 6 * color.g() as u32 + color.b() as u32 + 5) / 10) as u8
+}
+
+/// Black to outline over light `background`, white over dark
+pub fn contrasting(background: Bgr555) -> Bgr555 {
+    if brightness(background) >= 16 {
+        Bgr555::BLACK
+    } else {
+        Bgr555::WHITE
+    }
+}
+
+/// Flip every channel, inverting twice gives the original color
+pub fn invert(color: Bgr555) -> Bgr555 {
+    Bgr555::new(31 - color.r(), 31 - color.g(), 31 - color.b())
+}
+
+/// Shift `color` warmer by `delta` steps of red and away from blue, or
+/// cooler for a negative `delta`
+///
+/// Channels clamp at 0 and 31. A clamped channel loses the difference, so
+/// warming and then cooling by the same amount doesn't give saturated
+/// colors back, and repeated shifts flatten them toward the extremes.
+pub fn adjust_tint(color: Bgr555, delta: i8) -> Bgr555 {
+    let shift = |channel: u8, delta: i32| (channel as i32 + delta).max(0).min(31) as u8;
+    Bgr555::new(
+        shift(color.r(), delta as i32),
+        color.g(),
+        shift(color.b(), -(delta as i32)),
+    )
+}
+
+/// 4x4 ordered dither thresholds in sixteenths
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Dither threshold for the pixel at `point`, repeating every 4 pixels
+pub fn bayer(point: Point) -> u8 {
+    BAYER[(point.y & 3) as usize][(point.x & 3) as usize]
+}
+
+/// Like `blend`, but the fraction lost when rounding to 5 bits is dithered by position
+///
+/// Neighboring pixels round up or down in a fixed pattern so areas average
+/// to the exact blend. This trades a fine grain for much less visible
+/// banding in gradients and blended fills.
+pub fn blend_dithered(a: Bgr555, b: Bgr555, amount: u8, point: Point) -> Bgr555 {
+    let amount = amount.min(16) as u16;
+    let threshold = bayer(point) as u16;
+    let mix =
+        |a: u8, b: u8| ((a as u16 * (16 - amount) + b as u16 * amount + threshold) / 16) as u8;
+    Bgr555::new(mix(a.r(), b.r()), mix(a.g(), b.g()), mix(a.b(), b.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(level: u8) -> Bgr555 {
+        Bgr555::new(level, level, level)
+    }
+
+    #[test]
+    fn additive_saturates_at_full_intensity() {
+        let apply = |a, b| BlendMode::Additive.apply(gray(a), gray(b));
+        assert_eq!(apply(31, 31), gray(31));
+        assert_eq!(apply(16, 16), gray(31));
+        assert_eq!(apply(15, 16), gray(31));
+        assert_eq!(apply(15, 15), gray(30));
+        assert_eq!(apply(0, 0), gray(0));
+    }
+
+    #[test]
+    fn subtractive_saturates_at_zero() {
+        let apply = |a, b| BlendMode::Subtractive.apply(gray(a), gray(b));
+        assert_eq!(apply(0, 31), gray(0));
+        assert_eq!(apply(15, 16), gray(0));
+        assert_eq!(apply(16, 15), gray(1));
+        assert_eq!(apply(31, 0), gray(31));
+    }
+
+    #[test]
+    fn multiply_keeps_white_as_identity_and_black_as_zero() {
+        for level in 0..=31 {
+            assert_eq!(
+                BlendMode::Multiply.apply(gray(level), gray(31)),
+                gray(level)
+            );
+            assert_eq!(BlendMode::Multiply.apply(gray(level), gray(0)), gray(0));
+        }
+        assert_eq!(BlendMode::Multiply.apply(gray(1), gray(1)), gray(0));
+    }
+
+    #[test]
+    fn channels_blend_independently() {
+        let canvas = Bgr555::new(31, 0, 20);
+        let color = Bgr555::new(5, 31, 20);
+        assert_eq!(BlendMode::Normal.apply(canvas, color), color);
+        assert_eq!(
+            BlendMode::Additive.apply(canvas, color),
+            Bgr555::new(31, 31, 31)
+        );
+        assert_eq!(
+            BlendMode::Subtractive.apply(canvas, color),
+            Bgr555::new(26, 0, 0)
+        );
+    }
+
+    #[test]
+    fn next_visits_every_mode() {
+        let mut mode = BlendMode::Normal;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            seen.push(mode);
+            mode = mode.next();
+        }
+        assert_eq!(mode, BlendMode::Normal);
+        for m in [
+            BlendMode::Additive,
+            BlendMode::Subtractive,
+            BlendMode::Multiply,
+        ]
+        .iter()
+        {
+            assert!(seen.contains(m));
+        }
+    }
+
+    #[test]
+    fn nearest_picks_the_closest_entry() {
+        let palette = [Bgr555::BLACK, Bgr555::RED, Bgr555::WHITE, Bgr555::BLUE];
+        assert_eq!(nearest(Bgr555::new(3, 2, 1), &palette), Some(Bgr555::BLACK));
+        assert_eq!(nearest(Bgr555::new(28, 4, 2), &palette), Some(Bgr555::RED));
+        assert_eq!(
+            nearest(Bgr555::new(25, 25, 25), &palette),
+            Some(Bgr555::WHITE)
+        );
+        assert_eq!(nearest(Bgr555::new(0, 3, 30), &palette), Some(Bgr555::BLUE));
+    }
+
+    #[test]
+    fn nearest_exact_match_wins() {
+        let palette = [gray(10), gray(11), gray(12)];
+        assert_eq!(nearest(gray(11), &palette), Some(gray(11)));
+    }
+
+    #[test]
+    fn nearest_uses_squared_distance() {
+        // off by 3 in one channel is 9, off by 2 in three channels is 12
+        let one_channel = Bgr555::new(13, 10, 10);
+        let spread = Bgr555::new(12, 12, 12);
+        assert_eq!(nearest(gray(10), &[spread, one_channel]), Some(one_channel));
+    }
+
+    #[test]
+    fn nearest_ties_go_to_the_first() {
+        let palette = [gray(8), gray(12)];
+        assert_eq!(nearest(gray(10), &palette), Some(gray(8)));
+    }
+
+    #[test]
+    fn nearest_of_nothing_is_none() {
+        assert_eq!(nearest(Bgr555::WHITE, &[]), None);
+    }
+
+    #[test]
+    fn warming_raises_red_and_lowers_blue() {
+        let color = Bgr555::new(10, 12, 20);
+        assert_eq!(adjust_tint(color, 3), Bgr555::new(13, 12, 17));
+        assert_eq!(adjust_tint(color, -3), Bgr555::new(7, 12, 23));
+        assert_eq!(adjust_tint(color, 0), color);
+    }
+
+    #[test]
+    fn tint_clamps_at_saturation() {
+        assert_eq!(adjust_tint(Bgr555::new(30, 5, 1), 4), Bgr555::new(31, 5, 0));
+        assert_eq!(
+            adjust_tint(Bgr555::new(1, 5, 30), -4),
+            Bgr555::new(0, 5, 31)
+        );
+        assert_eq!(adjust_tint(Bgr555::WHITE, i8::MAX), Bgr555::new(31, 31, 0));
+        assert_eq!(adjust_tint(Bgr555::WHITE, i8::MIN), Bgr555::new(0, 31, 31));
+    }
+
+    #[test]
+    fn clamped_tint_does_not_come_back() {
+        let color = Bgr555::new(29, 16, 2);
+        let round_trip = adjust_tint(adjust_tint(color, 5), -5);
+        assert_eq!(round_trip, Bgr555::new(26, 16, 5));
+        // unclamped channels come back exactly
+        let color = Bgr555::new(16, 16, 16);
+        assert_eq!(adjust_tint(adjust_tint(color, 5), -5), color);
+    }
+
+    #[test]
+    fn brightness_of_gray_is_its_level() {
+        for level in 0..32 {
+            assert_eq!(brightness(gray(level)), level);
+        }
+    }
+
+    #[test]
+    fn green_looks_brighter_than_red_then_blue() {
+        assert_eq!(brightness(Bgr555::GREEN), 19);
+        assert_eq!(brightness(Bgr555::RED), 9);
+        assert_eq!(brightness(Bgr555::BLUE), 3);
+    }
+
+    #[test]
+    fn outline_flips_at_half_brightness() {
+        assert_eq!(contrasting(gray(15)), Bgr555::WHITE);
+        assert_eq!(contrasting(gray(16)), Bgr555::BLACK);
+        assert_eq!(contrasting(Bgr555::BLACK), Bgr555::WHITE);
+        assert_eq!(contrasting(Bgr555::WHITE), Bgr555::BLACK);
+    }
+
+    #[test]
+    fn bright_green_gets_a_black_outline_but_blue_a_white_one() {
+        assert_eq!(contrasting(Bgr555::GREEN), Bgr555::BLACK);
+        assert_eq!(contrasting(Bgr555::BLUE), Bgr555::WHITE);
+    }
+
+    /// The 16 pixels of the dither tile at `origin`
+    fn tile(origin: Point) -> impl Iterator<Item = Point> {
+        (0..16).map(move |i| origin + Point::new(i % 4, i / 4))
+    }
+
+    #[test]
+    fn bayer_repeats_every_four_pixels() {
+        for point in tile(Point::zero()) {
+            let threshold = bayer(point);
+            assert_eq!(bayer(point + Point::new(4, 0)), threshold);
+            assert_eq!(bayer(point + Point::new(0, 4)), threshold);
+            assert_eq!(bayer(point + Point::new(236, 156)), threshold);
+        }
+    }
+
+    #[test]
+    fn bayer_wraps_negative_coordinates() {
+        assert_eq!(bayer(Point::new(-1, 0)), bayer(Point::new(3, 0)));
+        assert_eq!(bayer(Point::new(0, -1)), bayer(Point::new(0, 3)));
+        assert_eq!(bayer(Point::new(-4, -8)), bayer(Point::zero()));
+    }
+
+    #[test]
+    fn each_threshold_appears_once_per_tile() {
+        for &origin in [Point::zero(), Point::new(2, 1), Point::new(-3, -5)].iter() {
+            let mut seen = [0; 16];
+            for point in tile(origin) {
+                seen[bayer(point) as usize] += 1;
+            }
+            assert_eq!(seen, [1; 16], "{:?}", origin);
+        }
+    }
+
+    #[test]
+    fn dithered_tile_averages_to_the_exact_blend() {
+        let (a, b) = (Bgr555::new(3, 20, 31), Bgr555::new(10, 0, 16));
+        for amount in 0..=16 {
+            let (mut r, mut g, mut b_sum) = (0, 0, 0);
+            for point in tile(Point::new(8, 4)) {
+                let color = blend_dithered(a, b, amount, point);
+                r += color.r() as u32;
+                g += color.g() as u32;
+                b_sum += color.b() as u32;
+            }
+            // sixteen pixels sum to sixteen times the unrounded blend
+            let exact = |a: u8, b: u8| a as u32 * (16 - amount as u32) + b as u32 * amount as u32;
+            assert_eq!(r, exact(a.r(), b.r()), "amount {}", amount);
+            assert_eq!(g, exact(a.g(), b.g()), "amount {}", amount);
+            assert_eq!(b_sum, exact(a.b(), b.b()), "amount {}", amount);
+        }
+    }
+
+    #[test]
+    fn dithering_an_exact_blend_changes_nothing() {
+        let (a, b) = (gray(4), gray(20));
+        // 4 * 8 + 20 * 8 is a multiple of 16
+        for point in tile(Point::zero()) {
+            assert_eq!(blend_dithered(a, b, 8, point), blend(a, b, 8));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Darken part of the canvas with the blend hardware
+//!
+//! Window 0 covers the region and only there is the canvas layer a target
+//! of the brightness decrease effect. Sprites are left out of the effect,
+//! so a panel drawn with sprites over the region stays at full brightness
+//! and no canvas pixel is touched or needs backing up.
+//!
+//! The safe area marker uses window 1 the other way around: the canvas is
+//! darkened outside it, between its edges and the screen's. Window 0 has
+//! priority where they overlap. There's one brightness setting, so when
+//! both are shown the stronger amount is used for both.
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use gba::{
+    io::{
+        color_blend::{BrightnessSetting, ColorEffectSetting, ColorSpecialEffect, BLDCNT, BLDY},
+        display::DISPCNT,
+        window::{
+            InsideWindowSetting, OutsideWindowSetting, WIN0H, WIN0V, WIN1H, WIN1V, WININ, WINOUT,
+        },
+    },
+    sync::Static,
+    vram::bitmap::Mode3,
+};
+
+/// Strongest darkening, the canvas turns black
+pub const MAX_AMOUNT: u8 = 16;
+
+/// Region darkened inside and its amount
+static REGION: Static<Option<(Rectangle, u8)>> = Static::new(None);
+
+/// Safe area left alone and the amount outside it
+static SAFE_AREA: Static<Option<(Rectangle, u8)>> = Static::new(None);
+
+/// Brightness decrease of the canvas layer only
+pub fn effect_setting() -> ColorEffectSetting {
+    ColorEffectSetting::new()
+        .with_bg2_1st_target_pixel(true)
+        .with_color_special_effect(ColorSpecialEffect::BrightnessDecrease)
+}
+
+/// `BLDY` value darkening by `amount` sixteenths, at most `MAX_AMOUNT`
+pub fn brightness_setting(amount: u8) -> BrightnessSetting {
+    BrightnessSetting::new().with_y(amount.min(MAX_AMOUNT) as u16)
+}
+
+/// Darken the canvas inside `rect`, corners inclusive, by `amount`
+/// sixteenths, replacing any region dimmed before
+pub fn dim_region(rect: &Rectangle, amount: u8) {
+    REGION.write(Some((*rect, amount)));
+    apply();
+}
+
+/// Show the whole canvas at full brightness again, except outside the safe area
+pub fn clear() {
+    REGION.write(None);
+    apply();
+}
+
+/// Darken the canvas by `amount` sixteenths outside the safe area `inset`
+/// pixels in from each screen edge, `None` shows it all again
+pub fn mark_safe_area(inset: Option<u8>, amount: u8) {
+    SAFE_AREA.write(inset.map(|inset| {
+        let inset = inset as i32;
+        let rect = Rectangle::new(
+            Point::new(inset, inset),
+            Point::new(
+                Mode3::WIDTH as i32 - 1 - inset,
+                Mode3::HEIGHT as i32 - 1 - inset,
+            ),
+        );
+        (rect, amount)
+    }));
+    apply();
+}
+
+/// Program the windows and blender for the region and safe area
+fn apply() {
+    let region = REGION.read();
+    let safe_area = SAFE_AREA.read();
+    if let Some((rect, _)) = region {
+        let (horizontal, vertical) = bounds(&rect);
+        WIN0H.write(horizontal);
+        WIN0V.write(vertical);
+    }
+    if let Some((rect, _)) = safe_area {
+        let (horizontal, vertical) = bounds(&rect);
+        WIN1H.write(horizontal);
+        WIN1V.write(vertical);
+    }
+    WININ.write(
+        InsideWindowSetting::new()
+            .with_win0_bg2(true)
+            .with_win0_obj(true)
+            .with_win0_color_special(true)
+            .with_win1_bg2(true)
+            .with_win1_obj(true),
+    );
+    WINOUT.write(
+        OutsideWindowSetting::new()
+            .with_outside_bg2(true)
+            .with_outside_obj(true)
+            .with_outside_color_special(safe_area.is_some()),
+    );
+    let amount = region
+        .iter()
+        .chain(safe_area.iter())
+        .map(|&(_, amount)| amount)
+        .max();
+    BLDCNT.write(match amount {
+        Some(_) => effect_setting(),
+        None => ColorEffectSetting::new(),
+    });
+    BLDY.write(brightness_setting(amount.unwrap_or(0)));
+    DISPCNT.write(
+        DISPCNT
+            .read()
+            .with_win0(region.is_some())
+            .with_win1(safe_area.is_some()),
+    );
+}
+
+/// Window edge registers for `rect`, corners inclusive, clamped to the screen
+fn bounds(rect: &Rectangle) -> (u16, u16) {
+    let left = rect.top_left.x.max(0).min(Mode3::WIDTH as i32) as u16;
+    let top = rect.top_left.y.max(0).min(Mode3::HEIGHT as i32) as u16;
+    // the window's right and bottom edges are exclusive
+    let right = (rect.bottom_right.x + 1).max(0).min(Mode3::WIDTH as i32) as u16;
+    let bottom = (rect.bottom_right.y + 1).max(0).min(Mode3::HEIGHT as i32) as u16;
+    (left << 8 | right, top << 8 | bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_canvas_layer_is_darkened() {
+        let setting = effect_setting();
+        assert!(setting.bg2_1st_target_pixel());
+        assert!(!setting.bg0_1st_target_pixel());
+        assert!(!setting.bg1_1st_target_pixel());
+        assert!(!setting.bg3_1st_target_pixel());
+        assert!(!setting.obj_1st_target_pixel());
+        assert!(!setting.backdrop_1st_target_pixel());
+        assert!(matches!(
+            setting.color_special_effect(),
+            ColorSpecialEffect::BrightnessDecrease
+        ));
+    }
+
+    #[test]
+    fn brightness_is_the_amount_in_sixteenths() {
+        assert_eq!(brightness_setting(0).y(), 0);
+        assert_eq!(brightness_setting(8).y(), 8);
+        assert_eq!(brightness_setting(MAX_AMOUNT).y(), 16);
+    }
+
+    #[test]
+    fn brightness_above_the_max_is_clamped() {
+        assert_eq!(brightness_setting(17).y(), 16);
+        assert_eq!(brightness_setting(u8::MAX).y(), 16);
+    }
+}
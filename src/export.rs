@@ -0,0 +1,258 @@
+//! Export the canvas as a TGA file over the debug log
+//!
+//! The file is logged as lines of `PREFIX` followed by hex bytes. Joining
+//! the hex of every line in order and decoding it gives an uncompressed
+//! 16 bit TGA that image viewers open directly. Assets keep Bgr555 channel
+//! order so tinytga can blit them raw, but the export swaps red and blue
+//! into the standard TGA order.
+//!
+//! Only one row of the file is buffered at a time, the whole file would
+//! take 75KB.
+//!
+//! A sprite sheet animation is logged as a manifest line, then for each
+//! frame a frame line followed by that frame's TGA lines, then an end line:
+//!
+//! ```text
+//! ANIM 4 8 16x16
+//! FRAME 0
+//! TGA 000002...
+//! FRAME 1
+//! TGA 000002...
+//! ANIM END
+//! ```
+//!
+//! The manifest gives the frame count, how many 60Hz frames each one is
+//! shown for and the cell size. Transparent pixels keep the sheet's
+//! `TRANSPARENT_KEY` color for the host to key out when assembling a GIF.
+
+use core::str;
+use embedded_graphics::{image::Image, pixelcolor::Bgr555, prelude::*};
+use gba::{debug, vram::bitmap::Mode3, Color};
+use tinytga::Tga;
+
+use crate::{animation::SheetLayout, assets::Asset};
+
+/// Starts every export line, so host scripts can pick them out of the log
+pub const PREFIX: &str = "TGA ";
+
+/// Starts the manifest line of an animation export
+pub const MANIFEST_PREFIX: &str = "ANIM ";
+
+/// Starts the line before each frame of an animation export, then the frame index
+pub const FRAME_PREFIX: &str = "FRAME ";
+
+/// Last line of an animation export
+pub const END: &str = "ANIM END";
+
+pub const HEADER_LEN: usize = 18;
+
+/// TGA 2.0 footer: no extension or developer area, then the signature
+pub const FOOTER: [u8; 26] = *b"\0\0\0\0\0\0\0\0TRUEVISION-XFILE.\0";
+
+/// Bytes per log line, twice as many hex digits stay under the log line limit
+const LINE_BYTES: usize = 32;
+
+/// Uncompressed true color, no color map, 16 bits per pixel, top left origin
+pub fn header() -> [u8; HEADER_LEN] {
+    header_sized(Mode3::WIDTH, Mode3::HEIGHT)
+}
+
+/// `header` of an image `width` by `height` pixels
+fn header_sized(width: usize, height: usize) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[2] = 2; // uncompressed true color
+    header[12..14].copy_from_slice(&(width as u16).to_le_bytes());
+    header[14..16].copy_from_slice(&(height as u16).to_le_bytes());
+    header[16] = 16; // bits per pixel
+    header[17] = 0x20; // rows start at the top
+    header
+}
+
+/// TGA 16 bit pixel (x, red, green, blue from the top bit) of a Bgr555 pixel
+pub fn to_tga_pixel(color: Color) -> u16 {
+    let (r, g, b) = (
+        color.0 & 0x1F,
+        (color.0 >> 5) & 0x1F,
+        (color.0 >> 10) & 0x1F,
+    );
+    r << 10 | g << 5 | b
+}
+
+/// Log the whole canvas as a TGA file
+pub fn export_canvas() {
+    debug!(
+        "Export {}x{} TGA, {} bytes",
+        Mode3::WIDTH,
+        Mode3::HEIGHT,
+        file_len()
+    );
+    write_tga(Mode3::WIDTH, Mode3::HEIGHT, |x, y| {
+        Mode3::read(x, y).unwrap_or(Color(0))
+    });
+    debug!("Export done");
+}
+
+/// Largest cell side, the biggest sprite
+const MAX_CELL: usize = 32;
+
+/// Logs the frames of a sprite sheet animation, one frame per `step` so no
+/// single frame of the main loop floods the log
+pub struct AnimationExport {
+    sheet: Tga<'static>,
+    layout: SheetLayout,
+    frame: usize,
+}
+
+impl AnimationExport {
+    /// Log the manifest, `None` if the asset isn't a TGA with every cell
+    /// of `layout`
+    pub fn new(asset: &'static Asset, layout: SheetLayout, period: u16) -> Option<Self> {
+        let sheet = asset.tga()?;
+        let size = Image::<Tga, Bgr555>::new(&sheet, Point::zero()).size();
+        let cell = layout.cell.pixels();
+        if layout.frames == 0 || size.width < cell * layout.frames as u32 || size.height < cell {
+            return None;
+        }
+        debug!(
+            "{}{} {} {}x{}",
+            MANIFEST_PREFIX, layout.frames, period, cell, cell
+        );
+        Some(Self {
+            sheet,
+            layout,
+            frame: 0,
+        })
+    }
+
+    /// Log the next frame, returns true once the last one and the end line are out
+    pub fn step(&mut self) -> bool {
+        let side = self.layout.cell.pixels() as usize;
+        let left = (side * self.frame) as i32;
+        // the sheet decodes in file order, which may run bottom up
+        let mut cell = [Color(0); MAX_CELL * MAX_CELL];
+        let image: Image<Tga, Bgr555> = Image::new(&self.sheet, Point::zero());
+        for Pixel(point, color) in &image {
+            let x = point.x - left;
+            if (0..side as i32).contains(&x) && (0..side as i32).contains(&point.y) {
+                cell[point.y as usize * side + x as usize] = Color(color.into_storage());
+            }
+        }
+        debug!("{}{}", FRAME_PREFIX, self.frame);
+        write_tga(side, side, |x, y| cell[y * side + x]);
+        self.frame += 1;
+        let done = self.frame == self.layout.frames;
+        if done {
+            debug!("{}", END);
+        }
+        done
+    }
+}
+
+/// Log a `width` by `height` TGA file of the pixels `pixel` gives in row order
+fn write_tga(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Color) {
+    let mut lines = HexLines::new();
+    for &byte in header_sized(width, height).iter() {
+        lines.push(byte);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            for &byte in to_tga_pixel(pixel(x, y)).to_le_bytes().iter() {
+                lines.push(byte);
+            }
+        }
+    }
+    for &byte in FOOTER.iter() {
+        lines.push(byte);
+    }
+    lines.flush();
+}
+
+fn file_len() -> usize {
+    HEADER_LEN + 2 * Mode3::WIDTH * Mode3::HEIGHT + FOOTER.len()
+}
+
+/// Collects bytes and logs them as a line of hex whenever a line fills
+struct HexLines {
+    digits: [u8; 2 * LINE_BYTES],
+    len: usize,
+}
+
+impl HexLines {
+    fn new() -> Self {
+        Self {
+            digits: [0; 2 * LINE_BYTES],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        self.digits[self.len] = HEX[(byte >> 4) as usize];
+        self.digits[self.len + 1] = HEX[(byte & 0xF) as usize];
+        self.len += 2;
+        if self.len == self.digits.len() {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.len > 0 {
+            let hex = str::from_utf8(&self.digits[..self.len]).unwrap_or_default();
+            debug!("{}{}", PREFIX, hex);
+            self.len = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_matches_the_tga_spec() {
+        let header = header();
+        assert_eq!(header[0], 0, "no image id");
+        assert_eq!(header[1], 0, "no color map");
+        assert_eq!(header[2], 2, "uncompressed true color");
+        assert_eq!(header[3..8], [0; 5], "empty color map spec");
+        assert_eq!(header[8..12], [0; 4], "origin at 0, 0");
+        assert_eq!(header[12..14], 240u16.to_le_bytes());
+        assert_eq!(header[14..16], 160u16.to_le_bytes());
+        assert_eq!(header[16], 16, "bits per pixel");
+        assert_eq!(header[17], 0x20, "top left origin, no alpha bits");
+    }
+
+    #[test]
+    fn footer_is_the_tga_2_signature() {
+        assert_eq!(FOOTER[..8], [0; 8], "no extension or developer area");
+        assert_eq!(&FOOTER[8..], b"TRUEVISION-XFILE.\0");
+    }
+
+    #[test]
+    fn pixels_swap_into_tga_channel_order() {
+        let red = Color(0x001F);
+        let blue = Color(0x7C00);
+        assert_eq!(to_tga_pixel(red), 0x7C00);
+        assert_eq!(to_tga_pixel(blue), 0x001F);
+        assert_eq!(to_tga_pixel(Color(0x03E0)), 0x03E0, "green stays put");
+        assert_eq!(to_tga_pixel(Color(0x8000)), 0, "the unused bit is dropped");
+    }
+
+    #[test]
+    fn tinytga_reads_the_file_back() {
+        let mut file = header_sized(3, 2).to_vec();
+        for i in 0..6u16 {
+            file.extend_from_slice(&to_tga_pixel(Color(i)).to_le_bytes());
+        }
+        file.extend_from_slice(&FOOTER);
+        let tga = Tga::from_slice(&file).unwrap();
+        let image = Image::<Tga, Bgr555>::new(&tga, Point::zero());
+        assert_eq!(image.size(), Size::new(3, 2));
+        assert_eq!((&image).into_iter().count(), 6);
+    }
+
+    #[test]
+    fn file_len_counts_header_pixels_and_footer() {
+        assert_eq!(file_len(), 18 + 2 * 240 * 160 + 26);
+    }
+}
@@ -0,0 +1,148 @@
+//! Allocation free text formatting for the HUD
+
+use core::{fmt, str};
+use embedded_graphics::{fonts::Text, prelude::*, style::TextStyle};
+
+/// Enough digits for `u32::MAX`
+pub const MAX_DIGITS: usize = 10;
+
+/// Write the decimal digits of `value` into the end of `buf`
+///
+/// Returns `None` if `buf` is too small to hold every digit.
+pub fn itoa_into(buf: &mut [u8], mut value: u32) -> Option<&str> {
+    let mut start = buf.len();
+    loop {
+        start = start.checked_sub(1)?;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    str::from_utf8(&buf[start..]).ok()
+}
+
+/// Write `prefix`, the decimal digits of `value` and `suffix` into the start of `buf`
+///
+/// Returns `None` if `buf` is too small to hold all of it.
+pub fn with_number<'a>(
+    buf: &'a mut [u8],
+    prefix: &str,
+    value: u32,
+    suffix: &str,
+) -> Option<&'a str> {
+    let mut digits = [0; MAX_DIGITS];
+    let digits = itoa_into(&mut digits, value)?;
+    let mut len = 0;
+    for part in [prefix, digits, suffix].iter() {
+        let end = len + part.len();
+        buf.get_mut(len..end)?.copy_from_slice(part.as_bytes());
+        len = end;
+    }
+    str::from_utf8(&buf[..len]).ok()
+}
+
+/// Draw `value` in decimal with its top left corner at `point`
+pub fn draw_number<D, C, F>(
+    display: &mut D,
+    value: u32,
+    point: Point,
+    style: TextStyle<C, F>,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<C>,
+    C: PixelColor,
+    F: Font + Copy,
+{
+    let mut buf = [0; MAX_DIGITS];
+    let text = itoa_into(&mut buf, value).unwrap_or_default();
+    Text::new(text, point).into_styled(style).draw(display)
+}
+
+/// `fmt::Write` into a fixed buffer, dropping whatever doesn't fit
+pub struct Truncating<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Truncating<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// Everything written so far that fit
+    pub fn as_str(&self) -> &str {
+        let written = &self.buf[..self.len];
+        // a multi byte character may have been cut in half
+        match str::from_utf8(written) {
+            Ok(text) => text,
+            Err(err) => str::from_utf8(&written[..err.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Write for Truncating<'_> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let end = (self.len + text.len()).min(self.buf.len());
+        self.buf[self.len..end].copy_from_slice(&text.as_bytes()[..end - self.len]);
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn zero_is_one_digit() {
+        let mut buf = [0; MAX_DIGITS];
+        assert_eq!(itoa_into(&mut buf, 0), Some("0"));
+    }
+
+    #[test]
+    fn max_fills_max_digits() {
+        let mut buf = [0; MAX_DIGITS];
+        assert_eq!(itoa_into(&mut buf, u32::MAX), Some("4294967295"));
+    }
+
+    #[test]
+    fn digits_go_at_the_end() {
+        let mut buf = [b'x'; 6];
+        assert_eq!(itoa_into(&mut buf, 1203), Some("1203"));
+        assert_eq!(&buf[..2], b"xx");
+    }
+
+    #[test]
+    fn too_small_buffer_is_none() {
+        let mut buf = [0; 3];
+        assert_eq!(itoa_into(&mut buf, 1000), None);
+        assert_eq!(itoa_into(&mut [], 0), None);
+        assert_eq!(itoa_into(&mut buf, 999), Some("999"));
+    }
+
+    #[test]
+    fn with_number_joins_the_parts() {
+        let mut buf = [0; 12];
+        assert_eq!(with_number(&mut buf, "Slot ", 3, ""), Some("Slot 3"));
+        assert_eq!(with_number(&mut buf, "", 42, "KB"), Some("42KB"));
+        assert_eq!(with_number(&mut buf[..3], "", 42, "KB"), None);
+    }
+
+    #[test]
+    fn truncating_keeps_what_fits() {
+        let mut buf = [0; 5];
+        let mut text = Truncating::new(&mut buf);
+        write!(text, "{}", 1234567).ok();
+        assert_eq!(text.as_str(), "12345");
+    }
+
+    #[test]
+    fn truncating_drops_a_cut_character() {
+        let mut buf = [0; 4];
+        let mut text = Truncating::new(&mut buf);
+        text.write_str("abc\u{e9}").ok();
+        assert_eq!(text.as_str(), "abc");
+    }
+}
@@ -0,0 +1,167 @@
+//! Bitmap fonts drawn from 1 bit per pixel glyph sheets
+//!
+//! A glyph sheet stores glyphs in ASCII order starting at `first`, each
+//! `glyph.height` bytes, one byte per row with the most significant bit as
+//! the leftmost pixel, so glyphs can be up to 8 pixels wide. Characters
+//! outside the sheet are drawn as a box outline.
+
+use embedded_graphics::{
+    drawable::Pixel, geometry::Size, pixelcolor::Bgr555, prelude::*, primitives::Rectangle,
+    style::PrimitiveStyle,
+};
+
+/// Fixed cell bitmap font
+#[derive(Debug, Copy, Clone)]
+pub struct BitmapFont {
+    pub sheet: &'static [u8],
+    /// First character in the sheet
+    pub first: u8,
+    /// Glyph pixels, at most 8 wide
+    pub glyph: Size,
+    /// Distance to the next character and line
+    pub advance: Size,
+}
+
+impl BitmapFont {
+    /// Rows of `c`, `None` if the sheet doesn't have it
+    fn rows(&self, c: char) -> Option<&'static [u8]> {
+        let height = self.glyph.height as usize;
+        let index = (c as u32).checked_sub(self.first as u32)? as usize;
+        self.sheet.get(index * height..(index + 1) * height)
+    }
+}
+
+/// Chunky 3x5 font covering space to underscore, digits and capitals
+pub const PIXEL_3X5: BitmapFont = BitmapFont {
+    sheet: &PIXEL_3X5_SHEET,
+    first: b' ',
+    glyph: Size::new(3, 5),
+    advance: Size::new(4, 6),
+};
+
+#[rustfmt::skip]
+const PIXEL_3X5_SHEET: [u8; 64 * 5] = [
+    0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, // space
+    0b01000000, 0b01000000, 0b01000000, 0b00000000, 0b01000000, // !
+    0b10100000, 0b10100000, 0b00000000, 0b00000000, 0b00000000, // "
+    0b10100000, 0b11100000, 0b10100000, 0b11100000, 0b10100000, // #
+    0b01100000, 0b11000000, 0b01000000, 0b01100000, 0b11000000, // $
+    0b10100000, 0b00100000, 0b01000000, 0b10000000, 0b10100000, // %
+    0b01000000, 0b10100000, 0b01000000, 0b10100000, 0b01100000, // &
+    0b01000000, 0b01000000, 0b00000000, 0b00000000, 0b00000000, // '
+    0b00100000, 0b01000000, 0b01000000, 0b01000000, 0b00100000, // (
+    0b10000000, 0b01000000, 0b01000000, 0b01000000, 0b10000000, // )
+    0b00000000, 0b10100000, 0b01000000, 0b10100000, 0b00000000, // *
+    0b00000000, 0b01000000, 0b11100000, 0b01000000, 0b00000000, // +
+    0b00000000, 0b00000000, 0b00000000, 0b01000000, 0b10000000, // ,
+    0b00000000, 0b00000000, 0b11100000, 0b00000000, 0b00000000, // -
+    0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01000000, // .
+    0b00100000, 0b00100000, 0b01000000, 0b10000000, 0b10000000, // /
+    0b11100000, 0b10100000, 0b10100000, 0b10100000, 0b11100000, // 0
+    0b01000000, 0b11000000, 0b01000000, 0b01000000, 0b11100000, // 1
+    0b11100000, 0b00100000, 0b11100000, 0b10000000, 0b11100000, // 2
+    0b11100000, 0b00100000, 0b01100000, 0b00100000, 0b11100000, // 3
+    0b10100000, 0b10100000, 0b11100000, 0b00100000, 0b00100000, // 4
+    0b11100000, 0b10000000, 0b11100000, 0b00100000, 0b11100000, // 5
+    0b11100000, 0b10000000, 0b11100000, 0b10100000, 0b11100000, // 6
+    0b11100000, 0b00100000, 0b00100000, 0b01000000, 0b01000000, // 7
+    0b11100000, 0b10100000, 0b11100000, 0b10100000, 0b11100000, // 8
+    0b11100000, 0b10100000, 0b11100000, 0b00100000, 0b11100000, // 9
+    0b00000000, 0b01000000, 0b00000000, 0b01000000, 0b00000000, // :
+    0b00000000, 0b01000000, 0b00000000, 0b01000000, 0b10000000, // ;
+    0b00100000, 0b01000000, 0b10000000, 0b01000000, 0b00100000, // <
+    0b00000000, 0b11100000, 0b00000000, 0b11100000, 0b00000000, // =
+    0b10000000, 0b01000000, 0b00100000, 0b01000000, 0b10000000, // >
+    0b11100000, 0b00100000, 0b01100000, 0b00000000, 0b01000000, // ?
+    0b01000000, 0b10100000, 0b11100000, 0b10000000, 0b01100000, // @
+    0b01000000, 0b10100000, 0b11100000, 0b10100000, 0b10100000, // A
+    0b11000000, 0b10100000, 0b11000000, 0b10100000, 0b11000000, // B
+    0b01100000, 0b10000000, 0b10000000, 0b10000000, 0b01100000, // C
+    0b11000000, 0b10100000, 0b10100000, 0b10100000, 0b11000000, // D
+    0b11100000, 0b10000000, 0b11000000, 0b10000000, 0b11100000, // E
+    0b11100000, 0b10000000, 0b11000000, 0b10000000, 0b10000000, // F
+    0b01100000, 0b10000000, 0b10100000, 0b10100000, 0b01100000, // G
+    0b10100000, 0b10100000, 0b11100000, 0b10100000, 0b10100000, // H
+    0b11100000, 0b01000000, 0b01000000, 0b01000000, 0b11100000, // I
+    0b00100000, 0b00100000, 0b00100000, 0b10100000, 0b01000000, // J
+    0b10100000, 0b10100000, 0b11000000, 0b10100000, 0b10100000, // K
+    0b10000000, 0b10000000, 0b10000000, 0b10000000, 0b11100000, // L
+    0b10100000, 0b11100000, 0b11100000, 0b10100000, 0b10100000, // M
+    0b11000000, 0b10100000, 0b10100000, 0b10100000, 0b10100000, // N
+    0b01000000, 0b10100000, 0b10100000, 0b10100000, 0b01000000, // O
+    0b11000000, 0b10100000, 0b11000000, 0b10000000, 0b10000000, // P
+    0b01000000, 0b10100000, 0b10100000, 0b11000000, 0b01100000, // Q
+    0b11000000, 0b10100000, 0b11000000, 0b10100000, 0b10100000, // R
+    0b01100000, 0b10000000, 0b01000000, 0b00100000, 0b11000000, // S
+    0b11100000, 0b01000000, 0b01000000, 0b01000000, 0b01000000, // T
+    0b10100000, 0b10100000, 0b10100000, 0b10100000, 0b11100000, // U
+    0b10100000, 0b10100000, 0b10100000, 0b10100000, 0b01000000, // V
+    0b10100000, 0b10100000, 0b11100000, 0b11100000, 0b10100000, // W
+    0b10100000, 0b10100000, 0b01000000, 0b10100000, 0b10100000, // X
+    0b10100000, 0b10100000, 0b01000000, 0b01000000, 0b01000000, // Y
+    0b11100000, 0b00100000, 0b01000000, 0b10000000, 0b11100000, // Z
+    0b01100000, 0b01000000, 0b01000000, 0b01000000, 0b01100000, // [
+    0b10000000, 0b10000000, 0b01000000, 0b00100000, 0b00100000, // \
+    0b11000000, 0b01000000, 0b01000000, 0b01000000, 0b11000000, // ]
+    0b01000000, 0b10100000, 0b00000000, 0b00000000, 0b00000000, // ^
+    0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b11100000, // _
+];
+
+/// Draw `text` with its top left corner at `position`, each font pixel as a
+/// `scale` by `scale` square, `\n` starts a new line
+pub fn draw_custom_text<D: DrawTarget<Bgr555>>(
+    display: &mut D,
+    font: &BitmapFont,
+    text: &str,
+    position: Point,
+    color: Bgr555,
+    scale: u32,
+) -> Result<(), D::Error> {
+    let scale = scale.max(1) as i32;
+    let mut cursor = position;
+    for c in text.chars() {
+        if c == '\n' {
+            cursor = Point::new(position.x, cursor.y + font.advance.height as i32 * scale);
+            continue;
+        }
+        match font.rows(c) {
+            Some(rows) => draw_glyph(display, font, rows, cursor, color, scale)?,
+            None => Rectangle::new(
+                cursor,
+                cursor
+                    + Point::new(
+                        font.glyph.width as i32 * scale - 1,
+                        font.glyph.height as i32 * scale - 1,
+                    ),
+            )
+            .into_styled(PrimitiveStyle::with_stroke(color, 1))
+            .draw(display)?,
+        }
+        cursor.x += font.advance.width as i32 * scale;
+    }
+    Ok(())
+}
+
+fn draw_glyph<D: DrawTarget<Bgr555>>(
+    display: &mut D,
+    font: &BitmapFont,
+    rows: &[u8],
+    origin: Point,
+    color: Bgr555,
+    scale: i32,
+) -> Result<(), D::Error> {
+    for (y, &row) in rows.iter().enumerate() {
+        for x in 0..font.glyph.width.min(8) as i32 {
+            if row & (0x80 >> x) == 0 {
+                continue;
+            }
+            let corner = origin + Point::new(x, y as i32) * scale;
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    Pixel(corner + Point::new(dx, dy), color).draw(display)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Debug mode that runs one logic frame per button press
+//!
+//! The gate reads the keypad through its own `Input`, so the main loop's
+//! edge detection only sees the frames that actually run.
+
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    prelude::*,
+    style::TextStyleBuilder,
+};
+use gba::{oam::write_obj_attributes, vram::bitmap::Mode3};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::PaletteColor,
+    input::{Input, InputSampling, Key, Keys},
+    sprite::{ObjSize, SpriteBuilder, SpriteTiles, Strip},
+};
+
+/// Registered palette slots, see `register_palette`
+const BLACK: PaletteColor = PaletteColor::new(1);
+const YELLOW: PaletteColor = PaletteColor::new(5);
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// 32x8 "STEP" label, after the ruler tiles
+const LABEL_TILE: usize = 126;
+
+/// OAM slot after the ruler
+const LABEL_SLOT: usize = 91;
+
+/// Runs one frame per press while stepping, hidden from the main loop's input
+pub const STEP_KEY: Key = Key::Select;
+
+/// Held with Start to turn stepping on and off
+const COMBO: Keys = Keys::NONE.with(Key::L).with(Key::R).with(Key::Start);
+
+/// Freezes the main loop between presses of `STEP_KEY`
+pub struct FrameStep {
+    keys: Input,
+    enabled: bool,
+    /// Keys of the last toggle still held, hidden from the main loop's input
+    combo: Keys,
+}
+
+impl FrameStep {
+    /// Draw the indicator into sprite tile memory, stepping starts off
+    pub fn new() -> Result<Self, TileError> {
+        let style = TextStyleBuilder::new(Font6x8)
+            .text_color(YELLOW)
+            .background_color(BLACK)
+            .build();
+        // only the top row of tiles is shown by the 32x8 strip
+        let mut tiles = SpriteTiles::new(ObjSize::S32x32);
+        Text::new("STEP", Point::zero())
+            .into_styled(style)
+            .draw(&mut tiles)?;
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        for (i, &tile) in tiles.used()[..4].iter().enumerate() {
+            block.set_tile(LABEL_TILE + i, tile)?;
+        }
+
+        let frame_step = Self {
+            keys: Input::new(1, InputSampling::VBlank),
+            enabled: false,
+            combo: Keys::NONE,
+        };
+        frame_step.write_oam();
+        Ok(frame_step)
+    }
+
+    /// Read the keypad, returns true if this frame's logic should run
+    ///
+    /// L + R + Start turns stepping on and off, `input` ignores `STEP_KEY`
+    /// while stepping and each combo key until it's released.
+    pub fn gate(&mut self, input: &mut Input) -> bool {
+        self.keys.update();
+        if self.keys.is_held(Key::L)
+            && self.keys.is_held(Key::R)
+            && self.keys.just_pressed(Key::Start)
+        {
+            self.enabled = !self.enabled;
+            // so the main loop never sees e.g. R + Start
+            self.combo = COMBO;
+            input.ignore(self.ignored());
+            self.write_oam();
+            return false;
+        }
+        let held = self.combo.intersection(self.keys.held());
+        if held != self.combo {
+            self.combo = held;
+            input.ignore(self.ignored());
+        }
+        !self.enabled || self.keys.just_pressed(STEP_KEY)
+    }
+
+    /// Keys the main loop's input should read as released
+    fn ignored(&self) -> Keys {
+        if self.enabled {
+            self.combo.with(STEP_KEY)
+        } else {
+            self.combo
+        }
+    }
+
+    fn write_oam(&self) {
+        let label = SpriteBuilder::new(LABEL_TILE)
+            .strip(Strip::Horizontal)
+            .position(Point::new(Mode3::WIDTH as i32 - 33, 1))
+            .hidden(!self.enabled)
+            .build();
+        write_obj_attributes(LABEL_SLOT, label);
+    }
+}
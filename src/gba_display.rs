@@ -2,7 +2,10 @@ use core::convert::{Infallible, TryInto};
 use embedded_graphics::{
     drawable::Pixel,
     geometry::Size,
-    pixelcolor::{raw::RawU8, Bgr555, PixelColor},
+    pixelcolor::{
+        raw::{RawU16, RawU8},
+        Bgr555, PixelColor,
+    },
     prelude::*,
 };
 use gba::{
@@ -13,6 +16,30 @@ use gba::{
 /// Empty struct representing GBA Display
 pub struct GbaDisplay;
 
+impl GbaDisplay {
+    /// Read back the color of the pixel at `point`
+    pub fn get_pixel(&self, point: Point) -> Option<Bgr555> {
+        let (x, y): (u32, u32) = point.try_into().ok()?;
+        Mode3::read(x as usize, y as usize).map(|color| RawU16::new(color.0).into())
+    }
+}
+
+/// Pixels that can be read back and painted, the display outside of tests
+pub trait Canvas {
+    fn read(&self, point: Point) -> Option<Bgr555>;
+    fn write(&mut self, point: Point, color: Bgr555);
+}
+
+impl Canvas for GbaDisplay {
+    fn read(&self, point: Point) -> Option<Bgr555> {
+        self.get_pixel(point)
+    }
+
+    fn write(&mut self, point: Point, color: Bgr555) {
+        Pixel(point, color).draw(self).ok();
+    }
+}
+
 impl DrawTarget<Bgr555> for GbaDisplay {
     type Error = Infallible;
 
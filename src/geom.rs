@@ -0,0 +1,761 @@
+//! Geometry helpers for UI interaction
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+use crate::reticle::Reticle;
+
+/// Check if `point` is inside `rect`, both corners inclusive
+pub fn rect_contains(rect: &Rectangle, point: Point) -> bool {
+    let top_left = Point::new(
+        rect.top_left.x.min(rect.bottom_right.x),
+        rect.top_left.y.min(rect.bottom_right.y),
+    );
+    let bottom_right = Point::new(
+        rect.top_left.x.max(rect.bottom_right.x),
+        rect.top_left.y.max(rect.bottom_right.y),
+    );
+    (top_left.x..=bottom_right.x).contains(&point.x)
+        && (top_left.y..=bottom_right.y).contains(&point.y)
+}
+
+/// Check if the pixel the reticle points at is inside `rect`
+///
+/// This is the hotspot, not the sprite's top left corner, so the result
+/// doesn't change with the cursor shape or size.
+pub fn hotspot_in_rect(reticle: &Reticle, rect: &Rectangle) -> bool {
+    rect_contains(rect, reticle.hotspot())
+}
+
+/// Move `point` to the nearest point inside `rect`, both corners inclusive
+pub fn clamp(rect: &Rectangle, point: Point) -> Point {
+    Point::new(
+        point
+            .x
+            .max(rect.top_left.x.min(rect.bottom_right.x))
+            .min(rect.top_left.x.max(rect.bottom_right.x)),
+        point
+            .y
+            .max(rect.top_left.y.min(rect.bottom_right.y))
+            .min(rect.top_left.y.max(rect.bottom_right.y)),
+    )
+}
+
+/// What the cursor is doing, which decides where it can go
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interaction {
+    /// Drawing a stroke, kept out of the bar
+    Paint,
+    /// Moving or picking from the bar, the whole area is open
+    Ui,
+}
+
+/// Area the cursor moves in and a bar of controls inside it that strokes
+/// can't run into
+pub struct MovementBounds {
+    area: Rectangle,
+    bar: Option<Rectangle>,
+}
+
+impl MovementBounds {
+    pub const fn new(area: Rectangle, bar: Option<Rectangle>) -> Self {
+        Self { area, bar }
+    }
+
+    pub fn area(&self) -> &Rectangle {
+        &self.area
+    }
+
+    pub fn in_bar(&self, point: Point) -> bool {
+        self.bar.map_or(false, |bar| rect_contains(&bar, point))
+    }
+
+    /// Where a cursor at `from` heading for `target` ends up
+    ///
+    /// It's clamped to the area. While painting it also stays out of the
+    /// bar, sliding along its edge on whichever axis is still free.
+    pub fn step(&self, interaction: Interaction, from: Point, target: Point) -> Point {
+        let target = clamp(&self.area, target);
+        if interaction == Interaction::Ui || !self.in_bar(target) {
+            return target;
+        }
+        [Point::new(target.x, from.y), Point::new(from.x, target.y)]
+            .iter()
+            .copied()
+            .find(|&point| !self.in_bar(point))
+            .unwrap_or(from)
+    }
+}
+
+/// Distance between `a` and `b` rounded to the nearest pixel
+pub fn distance(a: Point, b: Point) -> u32 {
+    let delta = b - a;
+    let square = (delta.x * delta.x + delta.y * delta.y) as u32;
+    // integer square root, then round up past the halfway point
+    let mut root = 0;
+    while (root + 1) * (root + 1) <= square {
+        root += 1;
+    }
+    if square - root * root > root {
+        root + 1
+    } else {
+        root
+    }
+}
+
+/// Every pixel of the one pixel wide line from `from` to `to`, both ends
+/// included, with no gaps however far apart they are
+pub fn line_points(from: Point, to: Point) -> LinePoints {
+    let delta = to - from;
+    LinePoints {
+        point: from,
+        to,
+        step: Point::new(delta.x.signum(), delta.y.signum()),
+        dx: delta.x.abs(),
+        dy: -delta.y.abs(),
+        error: delta.x.abs() - delta.y.abs(),
+        done: false,
+    }
+}
+
+/// Bresenham walk from one end of a line to the other, see `line_points`
+pub struct LinePoints {
+    point: Point,
+    to: Point,
+    step: Point,
+    dx: i32,
+    dy: i32,
+    error: i32,
+    done: bool,
+}
+
+impl Iterator for LinePoints {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if self.done {
+            return None;
+        }
+        let point = self.point;
+        if point == self.to {
+            self.done = true;
+            return Some(point);
+        }
+        let twice = 2 * self.error;
+        if twice >= self.dy {
+            self.error += self.dy;
+            self.point.x += self.step.x;
+        }
+        if twice <= self.dx {
+            self.error += self.dx;
+            self.point.y += self.step.y;
+        }
+        Some(point)
+    }
+}
+
+/// Direction that movement can be locked to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Limits movement to one axis while a modifier is held, for straight strokes
+pub struct AxisLock {
+    axis: Option<Axis>,
+}
+
+impl AxisLock {
+    pub const fn new() -> Self {
+        Self { axis: None }
+    }
+
+    /// `direction` limited to the locked axis while `engaged`
+    ///
+    /// The first movement after engaging picks the axis, diagonals lock
+    /// horizontally. Releasing unlocks.
+    pub fn apply(&mut self, engaged: bool, direction: Point) -> Point {
+        if !engaged {
+            self.axis = None;
+            return direction;
+        }
+        if self.axis.is_none() && direction != Point::zero() {
+            self.axis = Some(if direction.x.abs() >= direction.y.abs() {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            });
+        }
+        match self.axis {
+            Some(Axis::Horizontal) => Point::new(direction.x, 0),
+            Some(Axis::Vertical) => Point::new(0, direction.y),
+            None => direction,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.axis.is_some()
+    }
+}
+
+/// Cursor speed that builds up while a direction is held
+///
+/// A press moves one pixel, then the cursor waits out `tap` frames so a
+/// quick tap never moves further. Held past that, pixels per frame go up by
+/// one every `ramp` frames, to at most `cap`.
+pub struct Acceleration {
+    cap: u8,
+    ramp: u8,
+    tap: u8,
+    frames: u16,
+}
+
+impl Acceleration {
+    pub const fn new(cap: u8, ramp: u8, tap: u8) -> Self {
+        Self {
+            cap,
+            ramp,
+            tap,
+            frames: 0,
+        }
+    }
+
+    /// `direction` scaled by the current speed, releasing every key resets it
+    pub fn apply(&mut self, direction: Point) -> Point {
+        if direction == Point::zero() {
+            self.frames = 0;
+            return direction;
+        }
+        let frames = self.frames;
+        self.frames = self.frames.saturating_add(1);
+        let tap = self.tap as u16;
+        let speed = match frames {
+            0 => 1,
+            held if held <= tap => 0,
+            held => (1 + (held - tap - 1) / self.ramp.max(1) as u16).min(self.cap.max(1) as u16),
+        };
+        direction * speed as i32
+    }
+
+    /// Start over from one pixel per frame, e.g. after running into an edge
+    ///
+    /// The d-pad is still down, so this doesn't wait out the tap window again.
+    pub fn stop(&mut self) {
+        self.frames = self.frames.min(self.tap as u16 + 1);
+    }
+}
+
+/// Tuning for `Momentum`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Glide {
+    /// Top speed in pixels per frame
+    pub max_speed: u8,
+    /// Frames of holding a direction to reach top speed
+    pub ramp: u8,
+    /// 256ths of the speed kept each frame the direction is released
+    pub friction: u8,
+}
+
+/// Below this speed, in 256ths of a pixel per frame, a coasting cursor stops
+const REST_SPEED: i32 = 32;
+
+/// Cursor speed that builds up while a direction is held and keeps the
+/// cursor coasting after it's released
+///
+/// Speed and the position within the current pixel are kept in 256ths of a
+/// pixel, so the cursor can slow down smoothly. It comes to rest on the
+/// pixel it's showing rather than creeping on at a fraction of a pixel.
+pub struct Momentum {
+    glide: Glide,
+    velocity: Point,
+    /// Progress into the next pixel, 0 to 255 on each axis
+    fraction: Point,
+}
+
+impl Momentum {
+    pub const fn new(glide: Glide) -> Self {
+        Self {
+            glide,
+            velocity: Point::new(0, 0),
+            fraction: Point::new(0, 0),
+        }
+    }
+
+    /// Whole pixels to move this frame while `direction` is held
+    pub fn apply(&mut self, direction: Point) -> Point {
+        let max = self.glide.max_speed as i32 * 256;
+        let step = max / self.glide.ramp.max(1) as i32;
+        let friction = self.glide.friction as i32;
+        let axis = |velocity: i32, direction: i32| {
+            if direction != 0 {
+                (velocity + direction * step).max(-max).min(max)
+            } else {
+                match velocity * friction / 256 {
+                    slow if slow.abs() < REST_SPEED => 0,
+                    velocity => velocity,
+                }
+            }
+        };
+        self.velocity = Point::new(
+            axis(self.velocity.x, direction.x),
+            axis(self.velocity.y, direction.y),
+        );
+        // rest on the pixel already shown
+        if self.velocity.x == 0 {
+            self.fraction.x = 0;
+        }
+        if self.velocity.y == 0 {
+            self.fraction.y = 0;
+        }
+
+        let position = self.fraction + self.velocity;
+        self.fraction = Point::new(position.x & 0xff, position.y & 0xff);
+        Point::new(position.x >> 8, position.y >> 8)
+    }
+
+    /// Stop dead, e.g. after running into an edge
+    pub fn stop(&mut self) {
+        self.velocity = Point::zero();
+        self.fraction = Point::zero();
+    }
+}
+
+/// Furthest a smoothed stroke trails the cursor, in pixels
+const MAX_LAG: i32 = 6;
+
+/// Moving average of the cursor path so freehand strokes come out smoother
+///
+/// Each frame the stroke position moves `16 - strength` sixteenths of the
+/// way to the cursor, kept in 256ths of a pixel. It never trails by more
+/// than `MAX_LAG` pixels on either axis, so fast strokes stay connected to
+/// the cursor.
+pub struct Smoothing {
+    strength: u8,
+    position: Option<Point>,
+}
+
+impl Smoothing {
+    /// `strength` from 0, no smoothing, to 15
+    pub const fn new(strength: u8) -> Self {
+        Self {
+            strength: if strength > 15 { 15 } else { strength },
+            position: None,
+        }
+    }
+
+    /// Smoothed stroke position for the cursor at `point`
+    pub fn apply(&mut self, point: Point) -> Point {
+        let target = point * 256;
+        let position = match self.position {
+            Some(position) => {
+                let keep = self.strength as i32;
+                let step = (target - position) * (16 - keep) / 16;
+                let lag = MAX_LAG * 256;
+                let moved = position + step;
+                Point::new(
+                    moved.x.max(target.x - lag).min(target.x + lag),
+                    moved.y.max(target.y - lag).min(target.y + lag),
+                )
+            }
+            None => target,
+        };
+        self.position = Some(position);
+        // round to the nearest pixel, shifting so negative positions round
+        // the same way
+        Point::new((position.x + 128) >> 8, (position.y + 128) >> 8)
+    }
+
+    /// Start the next stroke at the cursor
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
+
+/// Point of `rect` a d-pad `direction` points at from its center: a corner,
+/// the middle of an edge, or the center itself when centered
+pub fn jump_target(rect: &Rectangle, direction: Point) -> Point {
+    let axis = |low: i32, high: i32, sign: i32| match sign.signum() {
+        -1 => low.min(high),
+        1 => low.max(high),
+        _ => (low + high) / 2,
+    };
+    Point::new(
+        axis(rect.top_left.x, rect.bottom_right.x, direction.x),
+        axis(rect.top_left.y, rect.bottom_right.y, direction.y),
+    )
+}
+
+/// Cursor gliding in a straight line to a target over a few frames
+pub struct Jump {
+    from: Point,
+    to: Point,
+    frame: u8,
+    frames: u8,
+}
+
+impl Jump {
+    /// Arrive at `to` after `frames` steps, the first step if 0
+    pub fn new(from: Point, to: Point, frames: u8) -> Self {
+        Self {
+            from,
+            to,
+            frame: 0,
+            frames: frames.max(1),
+        }
+    }
+}
+
+/// Positions for the following frames, ending on the target
+impl Iterator for Jump {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if self.frame == self.frames {
+            return None;
+        }
+        self.frame += 1;
+        let (frame, frames) = (self.frame as i32, self.frames as i32);
+        Some(self.from + (self.to - self.from) * frame / frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RIGHT: Point = Point::new(1, 0);
+
+    #[test]
+    fn stop_at_a_wall_starts_over_from_one_pixel() {
+        let mut acceleration = Acceleration::new(4, 2, 0);
+        for _ in 0..20 {
+            acceleration.apply(RIGHT);
+        }
+        assert_eq!(acceleration.apply(RIGHT), RIGHT * 4);
+        acceleration.stop();
+        assert_eq!(acceleration.apply(RIGHT), RIGHT);
+        // and builds up again from there
+        let mut speeds = [0; 8];
+        for speed in speeds.iter_mut() {
+            *speed = acceleration.apply(RIGHT).x;
+        }
+        assert_eq!(speeds, [1, 2, 2, 3, 3, 4, 4, 4]);
+    }
+
+    #[test]
+    fn tap_moves_exactly_one_pixel() {
+        let mut acceleration = Acceleration::new(4, 2, 6);
+        assert_eq!(acceleration.apply(RIGHT), RIGHT);
+        assert_eq!(acceleration.apply(Point::zero()), Point::zero());
+        // a second tap is one pixel again
+        assert_eq!(acceleration.apply(RIGHT), RIGHT);
+    }
+
+    #[test]
+    fn hold_waits_out_the_tap_window_then_accelerates() {
+        let mut acceleration = Acceleration::new(4, 2, 6);
+        let mut moved = 0;
+        for _ in 0..7 {
+            moved += acceleration.apply(RIGHT).x;
+        }
+        assert_eq!(moved, 1);
+        let mut speeds = [0; 8];
+        for speed in speeds.iter_mut() {
+            *speed = acceleration.apply(RIGHT).x;
+        }
+        assert_eq!(speeds, [1, 1, 2, 2, 3, 3, 4, 4]);
+    }
+
+    /// Check `points` walk from `from` to `to` one 8-connected step at a time
+    fn assert_unbroken(points: &[Point], from: Point, to: Point) {
+        assert_eq!(points.first(), Some(&from));
+        assert_eq!(points.last(), Some(&to));
+        let delta = to - from;
+        assert_eq!(points.len() as i32, delta.x.abs().max(delta.y.abs()) + 1);
+        for pair in points.windows(2) {
+            let step = pair[1] - pair[0];
+            assert!(step.x.abs() <= 1 && step.y.abs() <= 1, "gap {:?}", pair);
+            assert_ne!(step, Point::zero());
+        }
+    }
+
+    #[test]
+    fn zero_length_line_is_one_point() {
+        let points: Vec<_> = line_points(Point::new(5, 5), Point::new(5, 5)).collect();
+        assert_eq!(points, [Point::new(5, 5)]);
+    }
+
+    #[test]
+    fn shallow_lines_fill_every_column() {
+        let from = Point::new(2, 3);
+        let to = Point::new(30, 9);
+        let points: Vec<_> = line_points(from, to).collect();
+        assert_unbroken(&points, from, to);
+    }
+
+    #[test]
+    fn steep_lines_fill_every_row() {
+        let from = Point::new(10, 0);
+        let to = Point::new(13, 40);
+        let points: Vec<_> = line_points(from, to).collect();
+        assert_unbroken(&points, from, to);
+        for (y, point) in points.iter().enumerate() {
+            assert_eq!(point.y, y as i32);
+        }
+    }
+
+    #[test]
+    fn negative_directions() {
+        for &(from, to) in &[
+            (Point::new(30, 20), Point::new(0, 0)),
+            (Point::new(0, 20), Point::new(7, -15)),
+            (Point::new(12, 0), Point::new(-12, 3)),
+            (Point::new(4, 4), Point::new(-4, -4)),
+        ] {
+            let points: Vec<_> = line_points(from, to).collect();
+            assert_unbroken(&points, from, to);
+        }
+    }
+
+    #[test]
+    fn reversed_line_covers_the_same_length() {
+        let (a, b) = (Point::new(3, 17), Point::new(25, 2));
+        assert_eq!(line_points(a, b).count(), line_points(b, a).count());
+    }
+
+    /// Safe area away from the screen origin, corners inclusive
+    const SAFE: Rectangle = Rectangle::new(Point::new(8, 16), Point::new(231, 151));
+
+    #[test]
+    fn clamp_keeps_points_inside() {
+        let inside = Point::new(100, 80);
+        assert_eq!(clamp(&SAFE, inside), inside);
+        assert_eq!(clamp(&SAFE, SAFE.top_left), SAFE.top_left);
+        assert_eq!(clamp(&SAFE, SAFE.bottom_right), SAFE.bottom_right);
+    }
+
+    #[test]
+    fn clamp_moves_to_the_nearest_edge() {
+        assert_eq!(clamp(&SAFE, Point::new(0, 80)), Point::new(8, 80));
+        assert_eq!(clamp(&SAFE, Point::new(100, 3)), Point::new(100, 16));
+        assert_eq!(clamp(&SAFE, Point::new(239, 159)), Point::new(231, 151));
+        assert_eq!(clamp(&SAFE, Point::new(-5, 400)), Point::new(8, 151));
+    }
+
+    #[test]
+    fn clamp_accepts_corners_either_way_round() {
+        let flipped = Rectangle::new(SAFE.bottom_right, SAFE.top_left);
+        assert_eq!(clamp(&flipped, Point::new(0, 0)), SAFE.top_left);
+        assert!(rect_contains(&flipped, Point::new(8, 151)));
+        assert!(!rect_contains(&flipped, Point::new(7, 151)));
+    }
+
+    #[test]
+    fn axis_lock_picks_the_axis_of_the_first_movement() {
+        let mut lock = AxisLock::new();
+        assert_eq!(lock.apply(true, Point::zero()), Point::zero());
+        assert!(!lock.is_locked());
+        assert_eq!(lock.apply(true, Point::new(0, -1)), Point::new(0, -1));
+        assert!(lock.is_locked());
+        // later movement keeps only the locked axis
+        assert_eq!(lock.apply(true, Point::new(1, 1)), Point::new(0, 1));
+        assert_eq!(lock.apply(true, Point::new(-1, 0)), Point::zero());
+    }
+
+    #[test]
+    fn axis_lock_takes_diagonals_as_horizontal() {
+        let mut lock = AxisLock::new();
+        assert_eq!(lock.apply(true, Point::new(-1, 1)), Point::new(-1, 0));
+        assert_eq!(lock.apply(true, Point::new(0, 1)), Point::zero());
+    }
+
+    #[test]
+    fn releasing_the_axis_lock_unlocks() {
+        let mut lock = AxisLock::new();
+        lock.apply(true, Point::new(1, 0));
+        assert_eq!(lock.apply(false, Point::new(1, 1)), Point::new(1, 1));
+        assert!(!lock.is_locked());
+        // the next engage picks again
+        assert_eq!(lock.apply(true, Point::new(0, 1)), Point::new(0, 1));
+    }
+
+    const GLIDE: Glide = Glide {
+        max_speed: 4,
+        ramp: 12,
+        friction: 224,
+    };
+
+    #[test]
+    fn momentum_builds_up_to_top_speed() {
+        let mut momentum = Momentum::new(GLIDE);
+        for _ in 0..GLIDE.ramp {
+            momentum.apply(RIGHT);
+        }
+        for _ in 0..10 {
+            assert_eq!(momentum.apply(RIGHT), RIGHT * 4);
+        }
+    }
+
+    #[test]
+    fn momentum_slows_down_and_comes_to_rest() {
+        let mut momentum = Momentum::new(GLIDE);
+        for _ in 0..20 {
+            momentum.apply(RIGHT);
+        }
+        let mut speed = momentum.velocity.x;
+        let mut coasted = 0;
+        for _ in 0..60 {
+            coasted += momentum.apply(Point::zero()).x;
+            assert!(momentum.velocity.x <= speed, "sped up while coasting");
+            speed = momentum.velocity.x;
+        }
+        assert!(coasted > 0);
+        assert_eq!(momentum.velocity, Point::zero());
+        // at rest on a whole pixel, with no creeping on
+        assert_eq!(momentum.fraction, Point::zero());
+        for _ in 0..100 {
+            assert_eq!(momentum.apply(Point::zero()), Point::zero());
+        }
+    }
+
+    #[test]
+    fn momentum_coasts_left_too() {
+        let mut momentum = Momentum::new(GLIDE);
+        for _ in 0..20 {
+            momentum.apply(Point::new(-1, 0));
+        }
+        for _ in 0..60 {
+            assert!(momentum.apply(Point::zero()).x <= 0);
+        }
+        assert_eq!(momentum.velocity, Point::zero());
+        assert_eq!(momentum.apply(Point::zero()), Point::zero());
+    }
+
+    #[test]
+    fn momentum_stop_is_immediate() {
+        let mut momentum = Momentum::new(GLIDE);
+        for _ in 0..20 {
+            momentum.apply(RIGHT);
+        }
+        momentum.stop();
+        assert_eq!(momentum.apply(Point::zero()), Point::zero());
+    }
+
+    /// Cursor moving right along y = 50, jittering two pixels up and down
+    fn noisy(x: i32) -> Point {
+        Point::new(x, if x % 2 == 0 { 48 } else { 52 })
+    }
+
+    #[test]
+    fn smoothing_irons_out_jitter() {
+        let mut smoothing = Smoothing::new(12);
+        for x in 0..40 {
+            let smoothed = smoothing.apply(noisy(x));
+            if x >= 10 {
+                assert_eq!(smoothed.y, 50, "x {}", x);
+            }
+            assert!((0..=MAX_LAG).contains(&(x - smoothed.x)), "x {}", x);
+        }
+    }
+
+    #[test]
+    fn no_smoothing_follows_the_cursor() {
+        let mut smoothing = Smoothing::new(0);
+        for x in 0..20 {
+            assert_eq!(smoothing.apply(noisy(x)), noisy(x));
+        }
+    }
+
+    #[test]
+    fn smoothing_never_trails_past_max_lag() {
+        let mut smoothing = Smoothing::new(15);
+        smoothing.apply(Point::zero());
+        let far = Point::new(100, -80);
+        let smoothed = smoothing.apply(far);
+        assert!((far.x - smoothed.x).abs() <= MAX_LAG);
+        assert!((far.y - smoothed.y).abs() <= MAX_LAG);
+    }
+
+    #[test]
+    fn smoothing_reset_starts_at_the_cursor() {
+        let mut smoothing = Smoothing::new(12);
+        smoothing.apply(Point::new(10, 10));
+        smoothing.reset();
+        assert_eq!(smoothing.apply(Point::new(90, 40)), Point::new(90, 40));
+    }
+
+    #[test]
+    fn each_jump_lands_on_its_corner_edge_or_center() {
+        let (left, top, right, bottom) = (8, 16, 231, 151);
+        let (middle, center) = (119, 83);
+        let expected = [
+            (Point::new(-1, -1), Point::new(left, top)),
+            (Point::new(0, -1), Point::new(middle, top)),
+            (Point::new(1, -1), Point::new(right, top)),
+            (Point::new(-1, 0), Point::new(left, center)),
+            (Point::zero(), Point::new(middle, center)),
+            (Point::new(1, 0), Point::new(right, center)),
+            (Point::new(-1, 1), Point::new(left, bottom)),
+            (Point::new(0, 1), Point::new(middle, bottom)),
+            (Point::new(1, 1), Point::new(right, bottom)),
+        ];
+        for &(direction, target) in expected.iter() {
+            assert_eq!(jump_target(&SAFE, direction), target, "{:?}", direction);
+        }
+    }
+
+    #[test]
+    fn jump_ends_on_its_target() {
+        let (from, to) = (Point::new(100, 80), Point::new(231, 16));
+        let steps: Vec<Point> = Jump::new(from, to, 4).collect();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps.last(), Some(&to));
+        assert!(steps.iter().all(|&point| rect_contains(&SAFE, point)));
+        // no animation snaps there in one step
+        assert_eq!(Jump::new(from, to, 0).collect::<Vec<_>>(), [to]);
+    }
+
+    /// Bar of controls along the bottom of the safe area
+    const BOUNDS: MovementBounds = MovementBounds::new(
+        SAFE,
+        Some(Rectangle::new(Point::new(8, 140), Point::new(231, 151))),
+    );
+
+    #[test]
+    fn painting_stops_at_the_bar() {
+        let from = Point::new(50, 139);
+        assert_eq!(
+            BOUNDS.step(Interaction::Paint, from, Point::new(50, 141)),
+            from
+        );
+        // a diagonal slides along the edge instead
+        assert_eq!(
+            BOUNDS.step(Interaction::Paint, from, Point::new(52, 141)),
+            Point::new(52, 139)
+        );
+        assert!(!BOUNDS.in_bar(BOUNDS.step(Interaction::Paint, from, Point::new(50, 200))));
+    }
+
+    #[test]
+    fn ui_cursor_enters_the_bar() {
+        let from = Point::new(50, 139);
+        let target = Point::new(52, 145);
+        assert_eq!(BOUNDS.step(Interaction::Ui, from, target), target);
+        assert!(BOUNDS.in_bar(target));
+        // but still not out of the area
+        assert_eq!(
+            BOUNDS.step(Interaction::Ui, from, Point::new(50, 200)),
+            Point::new(50, 151)
+        );
+    }
+
+    #[test]
+    fn no_bar_leaves_the_whole_area_to_paint() {
+        let bounds = MovementBounds::new(SAFE, None);
+        let target = Point::new(50, 145);
+        assert_eq!(
+            bounds.step(Interaction::Paint, Point::new(50, 139), target),
+            target
+        );
+    }
+}
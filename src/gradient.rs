@@ -0,0 +1,55 @@
+//! Linear gradient fill of the whole canvas
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+use crate::{color, scheduler::Pass};
+
+/// Blends from `from` at `start` to `to` at `end`, pixels beyond either end
+/// get the nearest end color
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Gradient {
+    start: Point,
+    end: Point,
+    from: Bgr555,
+    to: Bgr555,
+    dither: bool,
+}
+
+impl Gradient {
+    /// `dither` hides banding between the 17 blend steps
+    pub fn new(start: Point, end: Point, from: Bgr555, to: Bgr555, dither: bool) -> Self {
+        Self {
+            start,
+            end,
+            from,
+            to,
+            dither,
+        }
+    }
+
+    /// Projection of `point` onto the axis in 256ths, 0 at `start` and 256 at `end`
+    fn position(&self, point: Point) -> u32 {
+        let axis = self.end - self.start;
+        let length = axis.x * axis.x + axis.y * axis.y;
+        if length == 0 {
+            return 256;
+        }
+        let offset = point - self.start;
+        let dot = offset.x * axis.x + offset.y * axis.y;
+        (dot.max(0).min(length) as u32 * 256) / length as u32
+    }
+}
+
+impl Pass for Gradient {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        let position = self.position(point);
+        let shade = if self.dither {
+            // dither between neighboring blend steps, then the rounding within each step
+            let amount = (position + color::bayer(point) as u32) / 16;
+            color::blend_dithered(self.from, self.to, amount as u8, point)
+        } else {
+            color::blend(self.from, self.to, ((position + 8) / 16) as u8)
+        };
+        Some(shade)
+    }
+}
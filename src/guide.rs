@@ -0,0 +1,176 @@
+//! Composition guides drawn with sprites over the canvas
+//!
+//! Guides never touch canvas pixels, so they can be toggled at any time
+//! and never end up in saves or undo history.
+
+use embedded_graphics::{
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::{Circle, Line},
+    style::PrimitiveStyle,
+};
+use gba::{oam::write_obj_attributes, palram::index_palram_obj_8bpp, vram::bitmap::Mode3, Color};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::PaletteColor,
+    sprite::{blank_tile, ObjSize, SpriteBuilder, SpriteTiles, Strip},
+};
+
+/// Object palette slot of the guide color, after the reticle's
+const PALETTE_INDEX: u8 = 10;
+const COLOR: Bgr555 = Bgr555::new(20, 20, 20);
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// First guide tile, after the toolbar icons
+const FIRST_TILE: usize = 96;
+const VERTICAL_TILE: usize = FIRST_TILE;
+const HORIZONTAL_TILE: usize = FIRST_TILE + 4;
+const CROSS_TILE: usize = FIRST_TILE + 8;
+/// Top left quarter of the circle, flipped for the other three
+const QUADRANT_TILE: usize = FIRST_TILE + 9;
+
+/// OAM slots for guides, behind the reticle and toolbar
+const FIRST_SLOT: usize = 16;
+const MAX_SPRITES: usize = 26;
+
+const WIDTH: i32 = Mode3::WIDTH as i32;
+const HEIGHT: i32 = Mode3::HEIGHT as i32;
+const STRIP_LEN: i32 = 32;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GuideKind {
+    /// Lines splitting the screen in thirds both ways
+    RuleOfThirds,
+    /// Small cross at the center of the screen
+    CenterCross,
+    /// Circle around the center of the screen
+    Circle,
+}
+
+impl GuideKind {
+    /// The kind after this one, `None` after the last
+    fn next(self) -> Option<Self> {
+        match self {
+            Self::RuleOfThirds => Some(Self::CenterCross),
+            Self::CenterCross => Some(Self::Circle),
+            Self::Circle => None,
+        }
+    }
+}
+
+/// The guide currently shown, if any
+pub struct Guides {
+    kind: Option<GuideKind>,
+}
+
+impl Guides {
+    /// Draw the guide shapes into sprite tile memory, no guide is shown at first
+    pub fn new() -> Result<Self, TileError> {
+        index_palram_obj_8bpp(PALETTE_INDEX).write(Color(COLOR.into_storage()));
+
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let color = PaletteColor::new(PALETTE_INDEX);
+        let stroke = PrimitiveStyle::with_stroke(color, 1);
+
+        let mut vertical = blank_tile();
+        Line::new(Point::zero(), Point::new(0, 7))
+            .into_styled(stroke)
+            .draw(&mut vertical)?;
+        let mut horizontal = blank_tile();
+        Line::new(Point::zero(), Point::new(7, 0))
+            .into_styled(stroke)
+            .draw(&mut horizontal)?;
+        for i in 0..4 {
+            block.set_tile(VERTICAL_TILE + i, vertical)?;
+            block.set_tile(HORIZONTAL_TILE + i, horizontal)?;
+        }
+
+        let mut cross = blank_tile();
+        Line::new(Point::new(3, 0), Point::new(3, 6))
+            .into_styled(stroke)
+            .draw(&mut cross)?;
+        Line::new(Point::new(0, 3), Point::new(6, 3))
+            .into_styled(stroke)
+            .draw(&mut cross)?;
+        block.set_tile(CROSS_TILE, cross)?;
+
+        // centered on the far corner, only the top left quarter lands in the tiles
+        let mut quadrant = SpriteTiles::new(ObjSize::S32x32);
+        Circle::new(Point::new(32, 32), 31)
+            .into_styled(stroke)
+            .draw(&mut quadrant)?;
+        for (i, &tile) in quadrant.used().iter().enumerate() {
+            block.set_tile(QUADRANT_TILE + i, tile)?;
+        }
+
+        let guides = Self { kind: None };
+        guides.write_oam();
+        Ok(guides)
+    }
+
+    /// Step through each kind and then no guide, returns the new kind
+    pub fn cycle(&mut self) -> Option<GuideKind> {
+        self.kind = match self.kind {
+            None => Some(GuideKind::RuleOfThirds),
+            Some(kind) => kind.next(),
+        };
+        self.write_oam();
+        self.kind
+    }
+
+    fn write_oam(&self) {
+        let mut sprites = [None; MAX_SPRITES];
+        let mut len = 0;
+        let mut place = |sprite: SpriteBuilder| {
+            sprites[len] = Some(sprite);
+            len += 1;
+        };
+        match self.kind {
+            Some(GuideKind::RuleOfThirds) => {
+                for &x in [WIDTH / 3, WIDTH * 2 / 3].iter() {
+                    for y in (0..HEIGHT).step_by(STRIP_LEN as usize) {
+                        place(
+                            SpriteBuilder::new(VERTICAL_TILE)
+                                .strip(Strip::Vertical)
+                                .position(Point::new(x, y)),
+                        );
+                    }
+                }
+                for &y in [HEIGHT / 3, HEIGHT * 2 / 3].iter() {
+                    for x in (0..WIDTH).step_by(STRIP_LEN as usize) {
+                        place(
+                            SpriteBuilder::new(HORIZONTAL_TILE)
+                                .strip(Strip::Horizontal)
+                                .position(Point::new(x, y)),
+                        );
+                    }
+                }
+            }
+            Some(GuideKind::CenterCross) => {
+                place(
+                    SpriteBuilder::new(CROSS_TILE)
+                        .position(Point::new(WIDTH / 2 - 3, HEIGHT / 2 - 3)),
+                );
+            }
+            Some(GuideKind::Circle) => {
+                for &(dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                    let corner = Point::new(WIDTH / 2 - 32 + dx * 32, HEIGHT / 2 - 32 + dy * 32);
+                    place(
+                        SpriteBuilder::new(QUADRANT_TILE)
+                            .size(ObjSize::S32x32)
+                            .flip(dx == 1, dy == 1)
+                            .position(corner),
+                    );
+                }
+            }
+            None => {}
+        }
+        // unused slots are hidden in case the previous guide used them
+        for (i, sprite) in sprites.iter().enumerate() {
+            let sprite = sprite.unwrap_or_else(|| SpriteBuilder::new(0).hidden(true));
+            write_obj_attributes(FIRST_SLOT + i, sprite.build());
+        }
+    }
+}
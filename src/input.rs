@@ -0,0 +1,422 @@
+use embedded_graphics::prelude::*;
+use gba::{
+    io::keypad::{read_key_input, KeyInput, KeyInterruptSetting, KEYCNT},
+    sync::Static,
+};
+
+use crate::irq;
+
+/// Keypad buttons
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Key {
+    A,
+    B,
+    Select,
+    Start,
+    Right,
+    Left,
+    Up,
+    Down,
+    R,
+    L,
+}
+
+impl Key {
+    pub const ALL: [Self; 10] = [
+        Self::A,
+        Self::B,
+        Self::Select,
+        Self::Start,
+        Self::Right,
+        Self::Left,
+        Self::Up,
+        Self::Down,
+        Self::R,
+        Self::L,
+    ];
+
+    const fn mask(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Set of keys, one bit per `Key`
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Keys(u16);
+
+impl Keys {
+    pub const NONE: Self = Self(0);
+
+    /// These keys plus `key`
+    pub const fn with(self, key: Key) -> Self {
+        Self(self.0 | key.mask())
+    }
+
+    /// These keys minus those in `other`
+    pub const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Keys both in these and in `other`
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn contains(self, key: Key) -> bool {
+        self.0 & key.mask() != 0
+    }
+
+    /// D-pad direction as a unit step in x and y
+    pub fn direction(self) -> Point {
+        let axis =
+            |positive, negative| self.contains(positive) as i32 - self.contains(negative) as i32;
+        Point::new(axis(Key::Right, Key::Left), axis(Key::Down, Key::Up))
+    }
+}
+
+impl From<KeyInput> for Keys {
+    fn from(input: KeyInput) -> Self {
+        let pressed = [
+            (Key::A, input.a()),
+            (Key::B, input.b()),
+            (Key::Select, input.select()),
+            (Key::Start, input.start()),
+            (Key::Right, input.right()),
+            (Key::Left, input.left()),
+            (Key::Up, input.up()),
+            (Key::Down, input.down()),
+            (Key::R, input.r()),
+            (Key::L, input.l()),
+        ];
+        Self(
+            pressed
+                .iter()
+                .filter(|(_, pressed)| *pressed)
+                .fold(0, |bits, (key, _)| bits | key.mask()),
+        )
+    }
+}
+
+/// When the keypad is read
+///
+/// `VBlank` reads once at the start of each frame, the same point every
+/// frame, so latency is steady at up to one frame. `Coalesce` also keeps
+/// every key seen by `sample` since the last update, so a key down at
+/// either read counts as down for that frame. A tap still has to span
+/// `debounce` updates to register, so with a debounce of 1 a tap shorter
+/// than a frame registers if a read catches it. Call `sample` from a mid
+/// frame interrupt, e.g. vcount. The cost is an interrupt per frame, and
+/// a key released mid frame counts as held until the next frame.
+/// `Interrupt` queues every press the keypad interrupt sees, so no tap is
+/// missed however short, even while the CPU is halted waiting for vblank.
+/// Queued presses go down without waiting out the debounce. Register
+/// `on_keypad` for the keypad interrupt. It costs an interrupt per new
+/// press.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputSampling {
+    VBlank,
+    Coalesce,
+    Interrupt,
+}
+
+/// Keys pressed during any `sample` since the last update
+static SAMPLED: Static<Keys> = Static::new(Keys::NONE);
+
+/// Read the keypad between updates, safe to call from an interrupt handler
+pub fn sample() {
+    let raw: Keys = read_key_input().into();
+    SAMPLED.write(Keys(SAMPLED.read().0 | raw.0));
+}
+
+/// Press events queued by `on_keypad`, drained by `Input::update`
+pub const QUEUE_LEN: usize = 8;
+
+/// Keys held at each keypad interrupt since the last drain
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct KeyQueue {
+    events: [Keys; QUEUE_LEN],
+    len: usize,
+}
+
+impl KeyQueue {
+    const fn new() -> Self {
+        Self {
+            events: [Keys::NONE; QUEUE_LEN],
+            len: 0,
+        }
+    }
+
+    /// Append `keys`, merging into the newest event when full so no press is lost
+    fn push(&mut self, keys: Keys) {
+        if self.len < QUEUE_LEN {
+            self.events[self.len] = keys;
+            self.len += 1;
+        } else {
+            self.events[QUEUE_LEN - 1].0 |= keys.0;
+        }
+    }
+
+    /// Every key of every event, emptying the queue
+    fn drain(&mut self) -> Keys {
+        let keys = self.events[..self.len]
+            .iter()
+            .fold(Keys::NONE, |all, keys| Keys(all.0 | keys.0));
+        self.len = 0;
+        keys
+    }
+}
+
+static QUEUE: Static<KeyQueue> = Static::new(KeyQueue::new());
+
+/// Keypad interrupt handler, queues the held keys and waits for the next new press
+pub fn on_keypad() {
+    let raw: Keys = read_key_input().into();
+    let mut queue = QUEUE.read();
+    queue.push(raw);
+    QUEUE.write(queue);
+    arm_keypad(raw);
+}
+
+/// Raise the keypad interrupt when any key outside `held` goes down
+///
+/// The interrupt fires for as long as a selected key is down, so keys
+/// already seen are left out until they are released.
+pub fn arm_keypad(held: Keys) {
+    let fresh = |key| !held.contains(key);
+    KEYCNT.write(
+        KeyInterruptSetting::new()
+            .with_a(fresh(Key::A))
+            .with_b(fresh(Key::B))
+            .with_select(fresh(Key::Select))
+            .with_start(fresh(Key::Start))
+            .with_right(fresh(Key::Right))
+            .with_left(fresh(Key::Left))
+            .with_up(fresh(Key::Up))
+            .with_down(fresh(Key::Down))
+            .with_r(fresh(Key::R))
+            .with_l(fresh(Key::L))
+            .with_irq_enabled(true),
+    );
+}
+
+/// Keypad state with edge detection, updated once per frame
+///
+/// A key only registers as pressed or released once the keypad has read
+/// the new state for `debounce` consecutive frames, filtering out contact
+/// bounce on worn hardware at the cost of `debounce - 1` frames of latency.
+pub struct Input {
+    current: Keys,
+    previous: Keys,
+    debounce: u8,
+    counts: [u8; Key::ALL.len()], // frames each key has read differently
+    held_frames: [u16; Key::ALL.len()],
+    sampling: InputSampling,
+    ignored: Keys,
+}
+
+impl Input {
+    /// A `debounce` of 1 registers every change immediately
+    pub fn new(debounce: u8, sampling: InputSampling) -> Self {
+        Self {
+            current: Keys::default(),
+            previous: Keys::default(),
+            debounce: debounce.max(1),
+            counts: [0; Key::ALL.len()],
+            held_frames: [0; Key::ALL.len()],
+            sampling,
+            ignored: Keys::NONE,
+        }
+    }
+
+    /// Sample the keypad, call once per frame
+    pub fn update(&mut self) {
+        let mut raw: Keys = read_key_input().into();
+        let mut pressed = Keys::NONE;
+        match self.sampling {
+            InputSampling::VBlank => {}
+            InputSampling::Coalesce => {
+                raw.0 |= irq::free(|| {
+                    let sampled = SAMPLED.read();
+                    SAMPLED.write(Keys::NONE);
+                    sampled
+                })
+                .0;
+            }
+            InputSampling::Interrupt => {
+                // re-arm for keys released since the interrupt last fired
+                arm_keypad(raw);
+                pressed = irq::free(|| {
+                    let mut queue = QUEUE.read();
+                    let keys = queue.drain();
+                    QUEUE.write(queue);
+                    keys
+                });
+            }
+        }
+        self.update_with_presses(raw.without(self.ignored), pressed.without(self.ignored));
+    }
+
+    /// Read `keys` as released from the keypad, e.g. while they drive something else
+    pub fn ignore(&mut self, keys: Keys) {
+        self.ignored = keys;
+    }
+
+    /// Feed one frame of raw keypad state through the debounce filter
+    pub fn update_with(&mut self, raw: Keys) {
+        self.update_with_presses(raw, Keys::NONE);
+    }
+
+    /// Like `update_with`, but keys in `pressed` go down this frame without
+    /// waiting out the debounce, even if `raw` already reads them released
+    fn update_with_presses(&mut self, raw: Keys, pressed: Keys) {
+        self.previous = self.current;
+        for (&key, count) in Key::ALL.iter().zip(self.counts.iter_mut()) {
+            if pressed.contains(key) && !self.current.contains(key) {
+                self.current.0 |= key.mask();
+                *count = 0;
+                continue;
+            }
+            if raw.contains(key) == self.current.contains(key) {
+                *count = 0;
+                continue;
+            }
+            *count += 1;
+            if *count >= self.debounce {
+                self.current.0 ^= key.mask();
+                *count = 0;
+            }
+        }
+        for (&key, frames) in Key::ALL.iter().zip(self.held_frames.iter_mut()) {
+            *frames = if self.current.contains(key) {
+                frames.saturating_add(1)
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Keys currently held down
+    pub fn held(&self) -> Keys {
+        self.current
+    }
+
+    /// Check if `key` is held down
+    pub fn is_held(&self, key: Key) -> bool {
+        self.current.contains(key)
+    }
+
+    /// Frames `key` has been held, 1 on the frame it went down and 0 while released
+    pub fn held_frames(&self, key: Key) -> u16 {
+        self.held_frames[key as usize]
+    }
+
+    /// Check if `key` went down this frame
+    pub fn just_pressed(&self, key: Key) -> bool {
+        self.current.contains(key) && !self.previous.contains(key)
+    }
+
+    /// Check if any key went down this frame
+    pub fn any_just_pressed(&self) -> bool {
+        self.current.0 & !self.previous.0 != 0
+    }
+
+    /// Check if `key` went up this frame
+    pub fn just_released(&self, key: Key) -> bool {
+        !self.current.contains(key) && self.previous.contains(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: Keys = Keys::NONE.with(Key::A);
+    const B: Keys = Keys::NONE.with(Key::B);
+
+    #[test]
+    fn drain_merges_the_queue_and_empties_it() {
+        let mut queue = KeyQueue::new();
+        queue.push(A);
+        queue.push(B);
+        assert_eq!(queue.drain(), A.with(Key::B));
+        assert_eq!(queue.drain(), Keys::NONE);
+    }
+
+    #[test]
+    fn full_queue_merges_into_the_newest_event() {
+        let mut queue = KeyQueue::new();
+        for _ in 0..QUEUE_LEN {
+            queue.push(A);
+        }
+        queue.push(B);
+        assert_eq!(queue.len, QUEUE_LEN);
+        assert_eq!(queue.events[QUEUE_LEN - 1], A.with(Key::B));
+        assert_eq!(queue.drain(), A.with(Key::B));
+    }
+
+    const N: Keys = Keys::NONE;
+
+    /// Feed `reads` one frame each, returns the frames A went down and up
+    fn edges(input: &mut Input, reads: &[Keys]) -> (Vec<usize>, Vec<usize>) {
+        let (mut pressed, mut released) = (Vec::new(), Vec::new());
+        for (frame, &raw) in reads.iter().enumerate() {
+            input.update_with(raw);
+            if input.just_pressed(Key::A) {
+                pressed.push(frame);
+            }
+            if input.just_released(Key::A) {
+                released.push(frame);
+            }
+        }
+        (pressed, released)
+    }
+
+    #[test]
+    fn debounce_drops_a_one_frame_read() {
+        let mut input = Input::new(2, InputSampling::VBlank);
+        input.update_with(A);
+        input.update_with(Keys::NONE);
+        assert!(!input.is_held(Key::A));
+    }
+
+    #[test]
+    fn bouncing_press_is_one_clean_edge() {
+        let mut input = Input::new(2, InputSampling::VBlank);
+        let (pressed, released) = edges(&mut input, &[A, N, A, N, A, A, A, A]);
+        assert_eq!(pressed, [5]);
+        assert!(released.is_empty());
+        assert!(input.is_held(Key::A));
+    }
+
+    #[test]
+    fn bouncing_release_is_one_clean_edge() {
+        let mut input = Input::new(2, InputSampling::VBlank);
+        edges(&mut input, &[A, A]);
+        let (pressed, released) = edges(&mut input, &[N, A, N, A, N, N, N, N]);
+        assert!(pressed.is_empty());
+        assert_eq!(released, [5]);
+        assert!(!input.is_held(Key::A));
+    }
+
+    #[test]
+    fn queued_press_registers_past_the_debounce() {
+        let mut input = Input::new(2, InputSampling::Interrupt);
+        // tapped and released between two updates
+        input.update_with_presses(Keys::NONE, A);
+        assert!(input.just_pressed(Key::A));
+        input.update_with(Keys::NONE);
+        assert!(input.is_held(Key::A));
+        assert!(!input.just_pressed(Key::A));
+        input.update_with(Keys::NONE);
+        assert!(input.just_released(Key::A));
+    }
+
+    #[test]
+    fn queued_press_of_a_held_key_is_not_a_new_press() {
+        let mut input = Input::new(1, InputSampling::Interrupt);
+        input.update_with(A);
+        input.update_with_presses(A, A.with(Key::B));
+        assert!(!input.just_pressed(Key::A));
+        assert!(input.just_pressed(Key::B));
+    }
+}
@@ -0,0 +1,216 @@
+//! Interrupt dispatch table
+//!
+//! `MainIrqHandler` in crt0.s acknowledges every enabled interrupt in `IF`
+//! and clears `IME` before calling `dispatch`, so handlers never nest and
+//! an interrupt raised while they run stays pending in `IF` until they
+//! return. `dispatch` must not write `IF` itself: writing back `IF.read()`
+//! would acknowledge, and drop, interrupts raised since crt0 read it. It
+//! only sets the matching `BIOS_IF` bits, which `vblank_interrupt_wait`
+//! and the other BIOS waits poll.
+//!
+//! Handlers run in registration order, so register the most latency
+//! sensitive interrupt first. They run with interrupts disabled, keep them
+//! short.
+
+use gba::{
+    io::irq::{IrqEnableSetting, IrqFlags, BIOS_IF, IE, IME},
+    sync::Static,
+};
+
+pub type Handler = fn();
+
+/// Interrupt sources, in `IE` and `IF` bit order
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Irq {
+    VBlank,
+    HBlank,
+    VCounter,
+    Timer0,
+    Timer1,
+    Timer2,
+    Timer3,
+    Serial,
+    Dma0,
+    Dma1,
+    Dma2,
+    Dma3,
+    Keypad,
+    GamePak,
+}
+
+impl Irq {
+    const COUNT: usize = 14;
+
+    /// Check if this interrupt's bit is set in `flags`
+    pub fn is_set(self, flags: IrqFlags) -> bool {
+        match self {
+            Self::VBlank => flags.vblank(),
+            Self::HBlank => flags.hblank(),
+            Self::VCounter => flags.vcounter(),
+            Self::Timer0 => flags.timer0(),
+            Self::Timer1 => flags.timer1(),
+            Self::Timer2 => flags.timer2(),
+            Self::Timer3 => flags.timer3(),
+            Self::Serial => flags.serial(),
+            Self::Dma0 => flags.dma0(),
+            Self::Dma1 => flags.dma1(),
+            Self::Dma2 => flags.dma2(),
+            Self::Dma3 => flags.dma3(),
+            Self::Keypad => flags.keypad(),
+            Self::GamePak => flags.game_pak(),
+        }
+    }
+
+    /// `flags` with this interrupt's bit set
+    pub fn set(self, flags: IrqFlags) -> IrqFlags {
+        match self {
+            Self::VBlank => flags.with_vblank(true),
+            Self::HBlank => flags.with_hblank(true),
+            Self::VCounter => flags.with_vcounter(true),
+            Self::Timer0 => flags.with_timer0(true),
+            Self::Timer1 => flags.with_timer1(true),
+            Self::Timer2 => flags.with_timer2(true),
+            Self::Timer3 => flags.with_timer3(true),
+            Self::Serial => flags.with_serial(true),
+            Self::Dma0 => flags.with_dma0(true),
+            Self::Dma1 => flags.with_dma1(true),
+            Self::Dma2 => flags.with_dma2(true),
+            Self::Dma3 => flags.with_dma3(true),
+            Self::Keypad => flags.with_keypad(true),
+            Self::GamePak => flags.with_game_pak(true),
+        }
+    }
+}
+
+/// Registered interrupts in priority order, `None` handlers only acknowledge
+type Table = [Option<(Irq, Option<Handler>)>; Irq::COUNT];
+
+static TABLE: Static<Table> = Static::new([None; Irq::COUNT]);
+
+/// The interrupt already has a handler
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AlreadyRegistered(pub Irq);
+
+/// Enable `irq` in `IE` and call `handler` each time it fires
+///
+/// The peripheral must still be told to raise the interrupt, e.g. with
+/// `DISPSTAT` for vblank.
+pub fn register(irq: Irq, handler: Option<Handler>) -> Result<(), AlreadyRegistered> {
+    let mut table = TABLE.read();
+    if table.iter().flatten().any(|&(entry, _)| entry == irq) {
+        return Err(AlreadyRegistered(irq));
+    }
+    // there is one slot per interrupt, so a free slot always remains
+    if let Some(slot) = table.iter_mut().find(|slot| slot.is_none()) {
+        *slot = Some((irq, handler));
+    }
+    TABLE.write(table);
+    IE.write(irq.set(IE.read()));
+    Ok(())
+}
+
+/// Run `f` with interrupts masked, e.g. to read and clear what a handler writes
+pub fn free<R>(f: impl FnOnce() -> R) -> R {
+    let enabled = IME.read();
+    IME.write(IrqEnableSetting::IRQ_NO);
+    let result = f();
+    IME.write(enabled);
+    result
+}
+
+/// Entries of `table` whose interrupt is set in `flags`, in priority order
+fn fired(table: &Table, flags: IrqFlags) -> impl Iterator<Item = &(Irq, Option<Handler>)> {
+    table
+        .iter()
+        .flatten()
+        .filter(move |&&(irq, _)| irq.is_set(flags))
+}
+
+/// `bios_if` with the bits of the registered interrupts in `flags` or-ed in
+///
+/// Bits already set stay set, the BIOS clears the ones it has seen.
+fn acknowledge(table: &Table, flags: IrqFlags, bios_if: IrqFlags) -> IrqFlags {
+    fired(table, flags).fold(bios_if, |bits, &(irq, _)| irq.set(bits))
+}
+
+/// Interrupt handler to pass to `set_irq_handler`
+pub extern "C" fn dispatch(flags: IrqFlags) {
+    let table = TABLE.read();
+    BIOS_IF.write(acknowledge(&table, flags, BIOS_IF.read()));
+    for handler in fired(&table, flags).filter_map(|&(_, handler)| handler) {
+        handler();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Irq; Irq::COUNT] = [
+        Irq::VBlank,
+        Irq::HBlank,
+        Irq::VCounter,
+        Irq::Timer0,
+        Irq::Timer1,
+        Irq::Timer2,
+        Irq::Timer3,
+        Irq::Serial,
+        Irq::Dma0,
+        Irq::Dma1,
+        Irq::Dma2,
+        Irq::Dma3,
+        Irq::Keypad,
+        Irq::GamePak,
+    ];
+
+    fn flags(irqs: &[Irq]) -> IrqFlags {
+        irqs.iter().fold(IrqFlags::new(), |bits, irq| irq.set(bits))
+    }
+
+    fn set(flags: IrqFlags) -> Vec<Irq> {
+        ALL.iter()
+            .copied()
+            .filter(|irq| irq.is_set(flags))
+            .collect()
+    }
+
+    fn handler() {}
+
+    #[test]
+    fn each_interrupt_has_its_own_bit() {
+        for &irq in ALL.iter() {
+            assert_eq!(set(irq.set(IrqFlags::new())), [irq]);
+        }
+    }
+
+    #[test]
+    fn only_registered_fired_interrupts_are_acknowledged() {
+        let mut table: Table = [None; Irq::COUNT];
+        table[0] = Some((Irq::VBlank, Some(handler as Handler)));
+        table[1] = Some((Irq::Timer0, None));
+        table[2] = Some((Irq::Keypad, None));
+        let fired = flags(&[Irq::VBlank, Irq::Timer0, Irq::Serial]);
+        let acknowledged = acknowledge(&table, fired, IrqFlags::new());
+        assert_eq!(set(acknowledged), [Irq::VBlank, Irq::Timer0]);
+    }
+
+    #[test]
+    fn pending_bios_bits_are_kept() {
+        let mut table: Table = [None; Irq::COUNT];
+        table[0] = Some((Irq::VBlank, None));
+        table[1] = Some((Irq::VCounter, None));
+        let pending = flags(&[Irq::VCounter]);
+        let acknowledged = acknowledge(&table, flags(&[Irq::VBlank]), pending);
+        assert_eq!(set(acknowledged), [Irq::VBlank, Irq::VCounter]);
+    }
+
+    #[test]
+    fn handlers_run_in_priority_order() {
+        let mut table: Table = [None; Irq::COUNT];
+        table[0] = Some((Irq::Timer1, None));
+        table[1] = Some((Irq::VBlank, None));
+        let pending = flags(&[Irq::VBlank, Irq::Timer1]);
+        let order: Vec<_> = fired(&table, pending).map(|&(irq, _)| irq).collect();
+        assert_eq!(order, [Irq::Timer1, Irq::VBlank]);
+    }
+}
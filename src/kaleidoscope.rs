@@ -0,0 +1,95 @@
+//! Repeat strokes around a center point for mandala like drawings
+//!
+//! Each painted pixel is turned around the center by every multiple of a
+//! whole turn divided by the segment count, using the sprite rotation
+//! table. Single pixels are turned, not strokes, so copies at angles other
+//! than quarter turns can show small gaps in lines.
+
+use embedded_graphics::prelude::*;
+
+use crate::sprite;
+
+/// Copies of every stroke turned evenly around `center`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Kaleidoscope {
+    center: Point,
+    segments: u8,
+}
+
+impl Kaleidoscope {
+    /// `segments` copies including the original, at least 1
+    pub const fn new(center: Point, segments: u8) -> Self {
+        Self {
+            center,
+            segments: if segments == 0 { 1 } else { segments },
+        }
+    }
+
+    /// `point` and each of its turned copies, the original first
+    pub fn copies(self, point: Point) -> impl Iterator<Item = Point> {
+        let segments = self.segments as u32;
+        (0..segments).map(move |i| {
+            // angle in 256ths of a turn
+            let angle = (256 * i / segments) as u8;
+            self.center + sprite::rotate(point - self.center, angle)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom;
+
+    const CENTER: Point = Point::new(100, 80);
+    const POINT: Point = Point::new(120, 80);
+
+    fn copies(segments: u8) -> Vec<Point> {
+        Kaleidoscope::new(CENTER, segments).copies(POINT).collect()
+    }
+
+    #[test]
+    fn four_segments_turn_by_quarters() {
+        let expected = [
+            POINT,
+            Point::new(100, 100),
+            Point::new(80, 80),
+            Point::new(100, 60),
+        ];
+        assert_eq!(copies(4), expected);
+    }
+
+    #[test]
+    fn eight_segments_add_the_diagonals() {
+        let expected = [
+            POINT,
+            Point::new(114, 94),
+            Point::new(100, 100),
+            Point::new(86, 94),
+            Point::new(80, 80),
+            Point::new(86, 66),
+            Point::new(100, 60),
+            Point::new(114, 66),
+        ];
+        assert_eq!(copies(8), expected);
+    }
+
+    #[test]
+    fn every_copy_stays_as_far_from_the_center() {
+        for &segments in [4, 6, 8].iter() {
+            let copies = copies(segments);
+            assert_eq!(copies.len(), segments as usize);
+            for (i, &copy) in copies.iter().enumerate() {
+                assert!(copies[..i].iter().all(|&earlier| earlier != copy));
+                let distance = geom::distance(CENTER, copy);
+                assert!((19..=20).contains(&distance), "{:?}", copy);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_segments_is_just_the_original() {
+        assert_eq!(copies(0), [POINT]);
+        assert_eq!(copies(1), [POINT]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Two canvas layers composited into the framebuffer
+//!
+//! Each layer is a full 75KB canvas in EWRAM. With the canvas snapshot
+//! that's 225KB of the 256KB, so a third layer doesn't fit.
+//!
+//! Tools keep drawing straight to the screen, which shows the composite.
+//! Only the top visible layer is edited: before the layers change, the
+//! screen is captured back into it, and pixels that still show what's
+//! underneath stay transparent. `TRANSPARENT` isn't a palette color, so
+//! clearing is the way to make the top layer transparent again. Layers
+//! are split from the canvas the first time they're used and only the
+//! composite is saved.
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+};
+use gba::{sync::Mutex, vram::bitmap::Mode3, Color};
+
+const PIXELS: usize = Mode3::WIDTH * Mode3::HEIGHT;
+
+/// Color that lets the layer below show through, a pink no tool paints
+pub const TRANSPARENT: Bgr555 = Bgr555::new(31, 0, 15);
+
+/// Shown where no layer has a color
+const BACKDROP: Bgr555 = Bgr555::WHITE;
+
+#[link_section = ".ewram"]
+static LAYERS: Mutex<[[u16; PIXELS]; 2]> = Mutex::new([[0; PIXELS]; 2]);
+
+/// Which layer is on top and which are shown
+pub struct Layers {
+    in_use: bool,
+    /// Index of the top layer
+    top: usize,
+    top_visible: bool,
+}
+
+impl Layers {
+    pub const fn new() -> Self {
+        Self {
+            in_use: false,
+            top: 1,
+            top_visible: true,
+        }
+    }
+
+    /// Go back to a single flat canvas, e.g. after loading a new one
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Show or hide the top layer, returns true if it's now shown
+    pub fn toggle_top(&mut self) -> bool {
+        self.split();
+        self.capture();
+        self.top_visible = !self.top_visible;
+        self.composite();
+        self.top_visible
+    }
+
+    /// Put the bottom layer on top and edit it
+    pub fn swap(&mut self) {
+        self.split();
+        self.capture();
+        self.top = 1 - self.top;
+        self.top_visible = true;
+        self.composite();
+    }
+
+    /// Make the top layer transparent if it's being edited, returns false
+    /// if the bottom layer is being edited instead
+    pub fn clear_top(&mut self) -> bool {
+        if !self.in_use || !self.top_visible {
+            return false;
+        }
+        LAYERS.lock()[self.top]
+            .iter_mut()
+            .for_each(|pixel| *pixel = TRANSPARENT.into_storage());
+        self.composite();
+        true
+    }
+
+    /// The first time, the canvas becomes the bottom layer under an empty top
+    fn split(&mut self) {
+        if self.in_use {
+            return;
+        }
+        let mut layers = LAYERS.lock();
+        for i in 0..PIXELS {
+            layers[1 - self.top][i] = read(i).into_storage();
+            layers[self.top][i] = TRANSPARENT.into_storage();
+        }
+        self.in_use = true;
+    }
+
+    /// Copy the screen into the edited layer, keeping it transparent where
+    /// it still shows the layers under it
+    fn capture(&self) {
+        let mut layers = LAYERS.lock();
+        let (edited, below) = if self.top_visible {
+            (self.top, Some(1 - self.top))
+        } else {
+            (1 - self.top, None)
+        };
+        for i in 0..PIXELS {
+            let under = below
+                .map(|below| raw(layers[below][i]))
+                .filter(|&color| color != TRANSPARENT)
+                .unwrap_or(BACKDROP);
+            let screen = read(i);
+            if raw(layers[edited][i]) != TRANSPARENT || screen != under {
+                layers[edited][i] = screen.into_storage();
+            }
+        }
+    }
+
+    /// Draw the visible layers over each other
+    fn composite(&self) {
+        let layers = LAYERS.lock();
+        for i in 0..PIXELS {
+            let top = Some(raw(layers[self.top][i])).filter(|_| self.top_visible);
+            let color = top
+                .into_iter()
+                .chain(Some(raw(layers[1 - self.top][i])))
+                .find(|&color| color != TRANSPARENT)
+                .unwrap_or(BACKDROP);
+            Mode3::write(
+                i % Mode3::WIDTH,
+                i / Mode3::WIDTH,
+                Color(color.into_storage()),
+            );
+        }
+    }
+}
+
+fn raw(pixel: u16) -> Bgr555 {
+    RawU16::new(pixel).into()
+}
+
+/// Screen pixel `i` in row order
+fn read(i: usize) -> Bgr555 {
+    raw(Mode3::read(i % Mode3::WIDTH, i / Mode3::WIDTH)
+        .unwrap_or(Color(0))
+        .0)
+}
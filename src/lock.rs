@@ -0,0 +1,185 @@
+//! Rectangles of the canvas that painting tools must leave alone
+//!
+//! Locks are consulted by `paint::Recorder`, so every tool that records
+//! undo history respects them. Clearing the canvas, undo and canvas passes
+//! write the display directly and ignore them.
+
+use embedded_graphics::{prelude::*, primitives::Rectangle, style::PrimitiveStyle};
+use gba::oam::write_obj_attributes;
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::PaletteColor,
+    geom,
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Regions that can be locked at once
+pub const MAX_LOCKS: usize = 4;
+
+/// Guide gray, see `guide`
+const PALETTE_INDEX: u8 = 10;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Top left corner mark, flipped for the other corners, after the recent color swatches
+const CORNER_TILE: usize = 142;
+
+/// OAM slots of the corner marks, four per lock, after the swatches
+const FIRST_SLOT: usize = 101;
+
+/// Every locked region has no room for another
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Full;
+
+/// Locked regions, corners inclusive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RegionLocks {
+    regions: [Option<Rectangle>; MAX_LOCKS],
+}
+
+impl RegionLocks {
+    pub const fn new() -> Self {
+        Self {
+            regions: [None; MAX_LOCKS],
+        }
+    }
+
+    /// Lock the rectangle between two opposite corners
+    pub fn lock(&mut self, a: Point, b: Point) -> Result<(), Full> {
+        let slot = self
+            .regions
+            .iter_mut()
+            .find(|region| region.is_none())
+            .ok_or(Full)?;
+        *slot = Some(Rectangle::new(
+            Point::new(a.x.min(b.x), a.y.min(b.y)),
+            Point::new(a.x.max(b.x), a.y.max(b.y)),
+        ));
+        Ok(())
+    }
+
+    /// Unlock every region containing `point`, returns false if there were none
+    pub fn unlock_at(&mut self, point: Point) -> bool {
+        let mut unlocked = false;
+        for region in self.regions.iter_mut() {
+            if region.map_or(false, |rect| geom::rect_contains(&rect, point)) {
+                *region = None;
+                unlocked = true;
+            }
+        }
+        unlocked
+    }
+
+    /// Check if painting `point` is forbidden
+    pub fn contains(&self, point: Point) -> bool {
+        self.regions
+            .iter()
+            .flatten()
+            .any(|rect| geom::rect_contains(rect, point))
+    }
+}
+
+/// Corner marks around each locked region, drawn with sprites like the guides
+pub struct LockMarkers {
+    visible: bool,
+}
+
+impl LockMarkers {
+    /// Draw the corner mark into sprite tile memory, no marks are shown at first
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let mut corner = blank_tile();
+        let stroke = PrimitiveStyle::with_stroke(PaletteColor::new(PALETTE_INDEX), 1);
+        Rectangle::new(Point::zero(), Point::new(3, 0))
+            .into_styled(stroke)
+            .draw(&mut corner)?;
+        Rectangle::new(Point::zero(), Point::new(0, 3))
+            .into_styled(stroke)
+            .draw(&mut corner)?;
+        block.set_tile(CORNER_TILE, corner)?;
+        let markers = Self { visible: false };
+        markers.write_oam(&RegionLocks::new());
+        Ok(markers)
+    }
+
+    /// Mark the corners of every region in `locks`
+    pub fn show(&mut self, locks: &RegionLocks) {
+        self.visible = true;
+        self.write_oam(locks);
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.write_oam(&RegionLocks::new());
+    }
+
+    fn write_oam(&self, locks: &RegionLocks) {
+        for (i, region) in locks.regions.iter().enumerate() {
+            for (corner, &(right, bottom)) in
+                [(false, false), (true, false), (false, true), (true, true)]
+                    .iter()
+                    .enumerate()
+            {
+                let sprite = match region {
+                    Some(rect) if self.visible => {
+                        let x = if right {
+                            rect.bottom_right.x - 7
+                        } else {
+                            rect.top_left.x
+                        };
+                        let y = if bottom {
+                            rect.bottom_right.y - 7
+                        } else {
+                            rect.top_left.y
+                        };
+                        SpriteBuilder::new(CORNER_TILE)
+                            .flip(right, bottom)
+                            .position(Point::new(x, y))
+                    }
+                    _ => SpriteBuilder::new(0).hidden(true),
+                };
+                write_obj_attributes(FIRST_SLOT + 4 * i + corner, sprite.build());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_the_rect_and_its_corners() {
+        let mut locks = RegionLocks::new();
+        locks.lock(Point::new(10, 20), Point::new(4, 8)).unwrap();
+        for &point in &[Point::new(4, 8), Point::new(10, 20), Point::new(7, 14)] {
+            assert!(locks.contains(point), "{:?}", point);
+        }
+        for &point in &[Point::new(3, 8), Point::new(11, 20), Point::new(7, 21)] {
+            assert!(!locks.contains(point), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn full_after_max_locks() {
+        let mut locks = RegionLocks::new();
+        for i in 0..MAX_LOCKS as i32 {
+            assert_eq!(locks.lock(Point::new(i, 0), Point::new(i, 0)), Ok(()));
+        }
+        assert_eq!(locks.lock(Point::zero(), Point::zero()), Err(Full));
+    }
+
+    #[test]
+    fn unlock_frees_every_region_at_the_point() {
+        let mut locks = RegionLocks::new();
+        locks.lock(Point::new(0, 0), Point::new(9, 9)).unwrap();
+        locks.lock(Point::new(5, 5), Point::new(20, 20)).unwrap();
+        locks.lock(Point::new(30, 30), Point::new(40, 40)).unwrap();
+        assert!(locks.unlock_at(Point::new(6, 6)));
+        assert!(!locks.contains(Point::new(1, 1)));
+        assert!(!locks.contains(Point::new(15, 15)));
+        assert!(locks.contains(Point::new(35, 35)));
+        assert!(!locks.unlock_at(Point::new(6, 6)));
+    }
+}
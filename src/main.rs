@@ -1,21 +1,149 @@
-#![no_std]
+//! Paint program for the GBA
+//!
+//! `unsafe` is denied rather than forbidden, since a few modules need what
+//! the `gba` crate has no safe API for. Each allows it for itself only:
+//!
+//! - `audio` programs the Direct Sound, DMA 1 and FIFO registers by address
+//!   and mixes into the buffers DMA 1 reads
+//! - `music` writes the square channel registers by address
+//! - `memory` takes the addresses of the `.ewram` linker symbols
+//! - `reset` calls the BIOS `SoftReset`, which never returns
+
+#![cfg_attr(not(test), no_std)]
 #![feature(start)]
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 #![feature(exclusive_range_pattern)]
 #![feature(bindings_after_at)]
+#![feature(min_const_generics)]
 
+mod analysis;
+mod animation;
+mod asset_browser;
+mod assets;
+#[cfg(feature = "audio")]
+mod audio;
+mod autosave;
+mod background;
+mod blit;
+mod budget;
+mod canvas;
+mod charblock;
+mod checksum;
+mod clipboard;
+mod color;
+mod dim;
+mod export;
+mod fmt;
+mod font;
+mod frame_step;
 mod gba_display;
-use gba_display::{GbaDisplay, PaletteColor};
+mod geom;
+mod gradient;
+mod guide;
+mod input;
+mod irq;
+mod kaleidoscope;
+mod layers;
+mod lock;
+mod memory;
+mod menu;
+mod mirror;
+#[cfg(feature = "audio")]
+mod music;
+mod noise;
+mod oam_report;
+mod outline;
+mod overlay;
+mod paint;
+mod palette_cycle;
+mod panic_screen;
+mod profiler;
+mod quantize;
+mod radial;
+mod recent;
+#[cfg(feature = "replay")]
+mod replay;
+mod reset;
+mod reticle;
+mod rng;
+mod rotate;
+mod ruler;
+mod scheduler;
+mod self_test;
+mod settings;
+mod shake;
+mod showcase;
+mod slots;
+mod snap;
+mod sprite;
+mod stamp;
+mod storage;
+mod test_pattern;
+mod thumbnail;
+mod tile_editor;
+mod timer;
+mod tint;
+mod toast;
+mod toolbar;
+mod trace;
+mod tutorial;
+mod undo;
+mod vblank;
+mod zoom;
+use analysis::{Histogram, HistogramPanel, BARS};
+use animation::{Animation, SheetLayout};
+use asset_browser::AssetBrowser;
+use assets::Asset;
+use autosave::Autosave;
+use blit::Placement;
+use budget::{Budget, BudgetLimit};
+use color::BlendMode;
+use export::AnimationExport;
+use frame_step::FrameStep;
+use gba_display::GbaDisplay;
+use geom::{Acceleration, AxisLock, Glide, Interaction, Jump, Momentum, MovementBounds, Smoothing};
+use gradient::Gradient;
+use guide::{GuideKind, Guides};
+use input::{Input, InputSampling, Key, Keys};
+use irq::Irq;
+use kaleidoscope::Kaleidoscope;
+use layers::Layers;
+use lock::{Full, LockMarkers};
+use menu::{Menu, MenuAction};
+use mirror::Mirror;
+use noise::Noise;
+use outline::Outline;
+use paint::{BrushHold, PaintState, Pressure, PressureCurve, Tool};
+use profiler::Profiler;
+use quantize::Quantize;
+use radial::RadialMenu;
+use recent::RecentSwatches;
+use reticle::{AutoOutline, Reticle, StartPosition};
+use rotate::Rotate;
+use ruler::Ruler;
+use scheduler::{Pass, PassScheduler, Step};
+use settings::BootBehavior;
+use shake::{ScreenShake, Shake};
+use slots::{SlotAction, SlotScreen};
+use snap::SnapMarker;
+use sprite::ObjSize;
+use stamp::Stamp;
+use storage::Loaded;
+use test_pattern::TestPattern;
+use tile_editor::{EditorAction, TileCells, TileEditor};
+use tint::Tint;
+use toast::Toast;
+use toolbar::Toolbar;
+use trace::{Faded, Score};
+use tutorial::Tutorial;
+use zoom::Zoom;
 
-use core::convert::{Infallible, TryFrom, TryInto};
+use core::{convert::Infallible, mem::size_of_val, ops::Range};
 
 use embedded_graphics::{
-    egtriangle,
     fonts::{Font6x8, Text},
-    image::Image,
     pixelcolor::Bgr555,
     prelude::*,
-    primitive_style,
     primitives::Rectangle,
     style::{PrimitiveStyle, TextStyle},
 };
@@ -24,17 +152,15 @@ use gba::{
     debug, fatal,
     io::{
         display::{DisplayControlSetting, DisplayMode, DisplayStatusSetting, DISPCNT, DISPSTAT},
-        irq::{set_irq_handler, IrqEnableSetting, IrqFlags, BIOS_IF, IE, IF, IME},
+        irq::{set_irq_handler, IrqEnableSetting, IME},
         keypad::read_key_input,
     },
-    oam::{write_obj_attributes, OBJAttr0, OBJAttr1, OBJAttr2, ObjectAttributes},
-    palram::index_palram_obj_8bpp,
-    vram::{bitmap::Mode3, get_8bpp_character_block, Tile8bpp},
+    palram::{index_palram_bg_8bpp, index_palram_obj_8bpp},
+    save::Error as SaveError,
+    vram::bitmap::Mode3,
     Color,
 };
 
-use tinytga::Tga;
-
 const COLORS: [Bgr555; 8] = [
     Bgr555::BLACK,
     Bgr555::RED,
@@ -46,8 +172,263 @@ const COLORS: [Bgr555; 8] = [
     Bgr555::WHITE,
 ];
 
+/// Seconds after the first unsaved change before autosaving, `None` to disable
+const AUTOSAVE_INTERVAL: Option<u32> = Some(30);
+
+/// Asset shown behind the title menu
+const MENU_BACKGROUND: &str = "amy";
+
+/// Fit one copy of the menu background or tile a small texture
+const MENU_BACKGROUND_PLACEMENT: Placement = Placement::Fit;
+
+/// Frames each mascot animation step stays on screen
+const MASCOT_PERIOD: u16 = 8;
+
+/// Top left corner of the mascot on the menu
+const MASCOT_POSITION: Point = Point::new(220, 4);
+
+/// Asset shown over the canvas with R + Down
+const VIEWER_IMAGE: &str = "amy";
+
+/// Cursor sprite size, the shapes scale to fill it
+const RETICLE_SIZE: ObjSize = ObjSize::S8x8;
+
+/// Frames per cursor blink, `None` for a steady cursor
+const RETICLE_BLINK_PERIOD: Option<u16> = Some(30);
+
+/// Draw the circle cursor anti-aliased, using three more object palette slots
+const RETICLE_SMOOTHING: bool = false;
+
+/// Only move the cursor sprite during vblank, see `Reticle::set_deferred`
+const RETICLE_VBLANK_ONLY: bool = true;
+
+/// Clockwise turn of the cursor in 256ths of a turn, `None` to draw it upright
+const RETICLE_ROTATION: Option<u8> = None;
+
+/// Outline the cursor in black over light canvas and white over dark
+const RETICLE_AUTO_OUTLINE: bool = false;
+
+/// Brush opacity ramp while A is held, `None` for a hard brush
+const BRUSH_PRESSURE: Option<Pressure> = Some(Pressure {
+    curve: PressureCurve::EaseIn,
+    ramp_frames: 45,
+    cap: 16,
+});
+
+/// `BrushHold::Modifier(key)` makes a tap of A one dab and A + `key` a
+/// gap free stroke, `BrushHold::Dab` dabs every frame A is held
+const BRUSH_HOLD: BrushHold = BrushHold::Dab;
+
+/// Area the cursor can move in, corners inclusive, keeps tools off reserved screen regions
+const MOVEMENT_BOUNDS: Rectangle = Rectangle::new(
+    Point::zero(),
+    Point::new(Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1),
+);
+
+/// Keep strokes from running into the toolbar, the cursor still goes in
+/// when A isn't held to pick a tool, and a press that starts there doesn't
+/// paint anyway
+const PALETTE_BAR: bool = true;
+
+/// Where the cursor starts on boot
+const START_POSITION: StartPosition = StartPosition::Saved;
+
+/// Hold B to move along one axis only, B then cycles colors when released
+/// instead of pressed
+const AXIS_LOCK: bool = true;
+
+/// Top cursor speed in pixels per frame and frames held per speed step,
+/// `None` always moves one pixel
+const CURSOR_ACCELERATION: Option<(u8, u8)> = Some((4, 12));
+
+/// Frames after a d-pad press before the cursor moves again, so a tap
+/// moves exactly one pixel, 0 keeps moving from the second frame
+const CURSOR_TAP_WINDOW: u8 = 6;
+
+/// Let the cursor coast to a stop after the d-pad is released, instead of
+/// `CURSOR_ACCELERATION`, e.g. `Glide { max_speed: 4, ramp: 12, friction: 224 }`.
+/// `None` stops the cursor as soon as the keys go up.
+const CURSOR_MOMENTUM: Option<Glide> = None;
+
+/// Strokes follow a smoothed cursor path while A is held, from 1 to 15, the
+/// stroke trails the cursor by at most a few pixels. `None` paints at the cursor.
+const STROKE_SMOOTHING: Option<u8> = None;
+
+/// Jolt of the cursor when the canvas is cleared, `None` turns it off
+const SCREEN_SHAKE: Option<Shake> = Some(Shake {
+    frames: 12,
+    intensity: 3,
+});
+
+/// Held together, the usual soft reset combo, see `reset`
+const RESET_KEYS: [Key; 4] = [Key::A, Key::B, Key::Select, Key::Start];
+
+/// Check the hardware at power on and show the results, holding L skips it
+const SELF_TEST: bool = true;
+
+/// Prompt through moving, painting and undoing the first time painting
+/// starts, Start skips it
+const TUTORIAL: bool = true;
+
+/// Frames a key must read the same before a press or release registers
+const DEBOUNCE_FRAMES: u8 = 2;
+
+/// When the keypad is read, see `InputSampling` for the tradeoffs
+const INPUT_SAMPLING: InputSampling = InputSampling::Coalesce;
+
+/// Scanline of the extra mid frame keypad read when coalescing
+const SAMPLE_LINE: u16 = 80;
+
+/// How long fills and canvas passes may run each frame
+const FRAME_BUDGET: BudgetLimit = BudgetLimit::VBlank;
+
+/// Channel steps each warm or cool tint shifts the canvas by
+const TINT_STEP: i8 = 1;
+
+/// Sixteenths the canvas behind the toolbar is darkened by, `None` leaves it
+const TOOLBAR_DIM: Option<u8> = Some(8);
+
+/// Split the canvas into two layers: B + Select shows or hides the top
+/// layer and B + Start swaps them
+const LAYER_CONTROLS: bool = true;
+
+/// Magnification of the zoomed view, must divide 240 and 160
+const ZOOM_LEVEL: u8 = 2;
+
+/// Holding Select opens a ring of tools around the cursor, the d-pad picks
+/// one on release and a tap still cycles to the next tool
+const RADIAL_MENU: bool = true;
+
+/// With the radial menu off, Select + d-pad jumps the cursor to that edge
+/// or corner of the movement bounds and Select + A to their center
+const CORNER_JUMPS: bool = !RADIAL_MENU;
+
+/// Frames a jump glides for, 0 lands at once
+const JUMP_FRAMES: u8 = 6;
+
+/// Show a bar in the bottom right corner with how much of each frame the
+/// main loop used, green under half, yellow, then red near the limit
+const PROFILER: bool = cfg!(feature = "debug-tools");
+
+/// Seed of the global generator every random effect forks from, so a
+/// fixed seed gives the same shakes and noise every run for screenshot
+/// diffs, `None` seeds from the timer when the menu is first left
+const RNG_SEED: Option<u32> = None;
+
+/// Seed of the noise menu item, the same seed always gives the same noise,
+/// `None` takes one from the global generator, see `RNG_SEED`
+const NOISE_SEED: Option<u32> = Some(1);
+
+/// Pixels between the noise's random values, 1 is white noise
+const NOISE_SCALE: u8 = 8;
+
+/// Colors the noise picks from, `None` is grayscale
+const NOISE_COLORS: Option<&[Bgr555]> = None;
+
+/// Show panic messages on a red screen, not only in the emulator log
+const PANIC_SCREEN: bool = true;
+
+/// Clockwise turn of the canvas per B + R, in 256ths of a turn
+const ROTATE_STEP: u8 = 16;
+
+/// The line and polygon tools snap to loose ends of strokes within this
+/// many pixels of the cursor, `None` turns snapping off
+const LINE_SNAP_RADIUS: Option<u8> = Some(4);
+
+/// Object palette slots whose colors rotate, and the frames between steps,
+/// to animate sprites drawn with them
+const PALETTE_CYCLE: Option<(Range<u8>, u8)> = None;
+
+/// Shade the canvas this many pixels in from the screen edges, which a TV
+/// may cut off, `None` shows no safe area
+const SAFE_AREA_INSET: Option<u8> = None;
+
+/// Sixteenths the canvas outside the safe area is darkened by
+const SAFE_AREA_DIM: u8 = 6;
+
+/// Repeat brush and line strokes this many times around the screen center,
+/// e.g. 4, 6 or 8, `None` paints each stroke once
+const KALEIDOSCOPE: Option<u8> = None;
+
+/// Rows of the mirror preview redrawn each frame, toggled with Select +
+/// Start, see `mirror`, `None` leaves the combo off
+const MIRROR_PREVIEW_ROWS: Option<u8> = Some(8);
+
+/// Whole canvas operations run by the pass scheduler
+enum CanvasPass {
+    Histogram(Histogram<BARS>),
+    Gradient(Gradient),
+    TestPattern(TestPattern),
+    Quantize(Quantize<{ COLORS.len() }>),
+    Outline(Outline),
+    Faded(Faded),
+    Score(Score),
+    Tint(Tint),
+    Noise(Noise),
+    Rotate(Rotate),
+}
+
+impl CanvasPass {
+    /// Toast text shown with the progress of filters that rewrite the canvas
+    fn progress_label(&self) -> Option<&'static str> {
+        match self {
+            Self::Quantize(_) => Some("Quantize "),
+            Self::Outline(_) => Some("Outline "),
+            Self::Faded(_) => Some("Trace "),
+            Self::Tint(_) => Some("Tint "),
+            Self::Noise(_) => Some("Noise "),
+            Self::Rotate(_) => Some("Rotate "),
+            _ => None,
+        }
+    }
+}
+
+impl Pass for CanvasPass {
+    fn pixel(&mut self, point: Point, color: Bgr555) -> Option<Bgr555> {
+        match self {
+            Self::Histogram(histogram) => histogram.pixel(point, color),
+            Self::Gradient(gradient) => gradient.pixel(point, color),
+            Self::TestPattern(pattern) => pattern.pixel(point, color),
+            Self::Quantize(quantize) => quantize.pixel(point, color),
+            Self::Outline(outline) => outline.pixel(point, color),
+            Self::Faded(faded) => faded.pixel(point, color),
+            Self::Score(score) => score.pixel(point, color),
+            Self::Tint(tint) => tint.pixel(point, color),
+            Self::Noise(noise) => noise.pixel(point, color),
+            Self::Rotate(rotate) => rotate.pixel(point, color),
+        }
+    }
+}
+
+/// Which screen the main loop is driving
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    Menu,
+    Paint,
+    /// Primitives showcase, any key returns to the menu
+    Showcase,
+    /// Calibration pattern, any key returns to the menu
+    TestPattern,
+    /// Image shown over a snapshot of the canvas, any key returns to painting
+    Viewer,
+    /// Save slot list shown over a snapshot of the canvas
+    Slots,
+    /// Magnified canvas drawn from a snapshot, B returns to painting
+    Zoom,
+    /// Custom stamp editor, leaving returns to the menu
+    TileEditor,
+    /// Power on check results, any key goes on to the menu
+    SelfTest,
+    /// Embedded images shown over a snapshot of the menu, B returns to it
+    Assets,
+}
+
+#[cfg(not(test))]
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
+    if PANIC_SCREEN {
+        panic_screen::draw(info);
+    }
     fatal!("{}", info);
     loop {}
 }
@@ -66,90 +447,1139 @@ fn main(_argc: isize, _argv: *const *const u8) -> isize {
 
     debug!("Register palette");
     register_palette();
+    register_bg_palette(&COLORS);
 
     debug!("Draw cursor");
-    draw_cursor().ok();
+    let mut paint = PaintState::new(COLORS[0]);
+    if !cfg!(feature = "replay") {
+        paint.pressure = BRUSH_PRESSURE; // the recorded strokes expect a hard brush
+        paint.hold = BRUSH_HOLD;
+        paint.kaleidoscope = KALEIDOSCOPE.map(|segments| {
+            let center = Point::new(Mode3::WIDTH as i32 / 2, Mode3::HEIGHT as i32 / 2);
+            Kaleidoscope::new(center, segments)
+        });
+    }
+    let mut reticle = Reticle::new(paint.tool.reticle(), RETICLE_SIZE, paint.color).unwrap();
+    reticle.set_blink(RETICLE_BLINK_PERIOD);
+    reticle.set_smoothing(RETICLE_SMOOTHING);
+    reticle.set_rotation(RETICLE_ROTATION);
+    let mut auto_outline = RETICLE_AUTO_OUTLINE.then(AutoOutline::new);
+    let mut mirror = Mirror::new(MIRROR_PREVIEW_ROWS.unwrap_or(0) as usize);
+    if RETICLE_VBLANK_ONLY {
+        vblank::on_vblank(reticle::commit).ok();
+        reticle.set_deferred(true);
+    }
+    let mut toolbar = Toolbar::new().unwrap();
+    toolbar.set_dim(TOOLBAR_DIM);
+    dim::mark_safe_area(SAFE_AREA_INSET, SAFE_AREA_DIM);
+    let mut radial = RadialMenu::new();
+    let mut swatches = RecentSwatches::new().unwrap();
+    let mut lock_markers = LockMarkers::new().unwrap();
+    let mut slot_screen = SlotScreen::new();
+    let mut zoom = Zoom::new(ZOOM_LEVEL);
+    let mut asset_browser = AssetBrowser::new();
+    let mut layers = Layers::new();
+    let mut guides = Guides::new().unwrap();
+    let mut ruler = Ruler::new().unwrap();
+    let mut frame_step = FrameStep::new().unwrap();
+    let mut profiler = Profiler::new().unwrap();
+    let mut snap_marker = SnapMarker::new().unwrap();
+    // after every sprite registered its colors
+    vblank::on_vblank(palette_cycle::tick).ok();
+    if let Some((range, speed)) = PALETTE_CYCLE {
+        palette_cycle::start_palette_cycle(range, speed);
+    }
+    profiler.set_visible(PROFILER);
 
     debug!("Create display");
     let mut display = GbaDisplay;
-    draw_background(&mut display).ok();
-    draw_hud(&mut display).ok();
+    let mut menu = Menu::new();
+    if !menu.set_menu_background(MENU_BACKGROUND, MENU_BACKGROUND_PLACEMENT) {
+        debug!("No menu background named {}", MENU_BACKGROUND);
+    }
+    menu.draw(&mut display).ok();
+    reticle.hide();
+    let layout = SheetLayout {
+        cell: ObjSize::S16x16,
+        frames: 4,
+    };
+    let mut mascot = Animation::new(&assets::MASCOT, layout, &COLORS, MASCOT_PERIOD);
+    if mascot.is_none() {
+        debug!("Mascot sheet doesn't match its layout");
+    }
+    let mut animation_export: Option<AnimationExport> = None;
+
+    debug!("Set up save media");
+    storage::init();
+    let mut settings = storage::load_settings();
+    let cells = settings.stamp_tile.map(|tile| TileCells::unpack(&tile));
+    if let Some(cells) = cells {
+        paint.stamp = Stamp::new("Custom", cells.mask_rows());
+    }
+    // replays don't expect the tutorial's toasts or its use of Start
+    let mut tutorial =
+        Tutorial::new(TUTORIAL && !cfg!(feature = "replay") && !settings.tutorial_done);
+    let mut tile_editor = TileEditor::new(cells.unwrap_or_else(TileCells::new), &COLORS);
+
+    // a replay's first presses are meant for the menu
+    let self_test =
+        SELF_TEST && !cfg!(feature = "replay") && !Keys::from(read_key_input()).contains(Key::L);
+    if self_test {
+        debug!("Run self test");
+        let results = self_test::run_all();
+        for &(check, ok) in results.iter() {
+            debug!("{}: {}", check.name(), if ok { "pass" } else { "FAIL" });
+        }
+        self_test::draw(&mut display, &results).ok();
+    }
 
     debug!("Enable interrupts");
-    set_irq_handler(irq_handler);
-    DISPSTAT.write(DisplayStatusSetting::new().with_vblank_irq_enable(true));
-    IE.write(IrqFlags::new().with_vblank(true));
+    set_irq_handler(irq::dispatch);
+    #[cfg(feature = "audio")]
+    irq::register(Irq::VBlank, Some(audio::on_vblank)).ok(); // first, sound can't wait
+    #[cfg(not(feature = "audio"))]
+    irq::register(Irq::VBlank, None).ok(); // only wakes vblank_interrupt_wait
+    irq::register(Irq::Timer0, Some(timer::on_overflow)).ok();
+    let mut dispstat = DisplayStatusSetting::new().with_vblank_irq_enable(true);
+    match INPUT_SAMPLING {
+        InputSampling::VBlank => {}
+        InputSampling::Coalesce => {
+            irq::register(Irq::VCounter, Some(input::sample)).ok();
+            dispstat = dispstat
+                .with_vcounter_irq_enable(true)
+                .with_vcount_setting(SAMPLE_LINE);
+        }
+        InputSampling::Interrupt => {
+            irq::register(Irq::Keypad, Some(input::on_keypad)).ok();
+            input::arm_keypad(Keys::NONE);
+        }
+    }
+    DISPSTAT.write(dispstat);
     IME.write(IrqEnableSetting::IRQ_YES);
+    timer::init();
+    #[cfg(feature = "audio")]
+    {
+        audio::init();
+        music::play(settings.music.map(usize::from), settings.music_volume);
+        vblank::on_vblank(music::tick).ok();
+    }
 
-    const WIDTH: u32 = Mode3::WIDTH as u32;
-    const HEIGHT: u32 = Mode3::HEIGHT as u32;
-    let mut point = Point::try_from((WIDTH, HEIGHT)).unwrap() / 2;
+    // replays start from a known canvas and cursor so the recorded strokes land the same way
+    let (start, boot) = if cfg!(feature = "replay") {
+        (StartPosition::Center, BootBehavior::ShowImage)
+    } else {
+        (START_POSITION, settings.boot)
+    };
+    // replays need the same random effects as when they were recorded
+    let seed = if cfg!(feature = "replay") {
+        Some(RNG_SEED.unwrap_or(0))
+    } else {
+        RNG_SEED
+    };
+    let mut seeded = seed.is_some();
+    rng::set_seed(seed.unwrap_or(0));
+    let movement_bounds =
+        MovementBounds::new(MOVEMENT_BOUNDS, PALETTE_BAR.then(|| toolbar.bounds()));
+    let mut point = geom::clamp(movement_bounds.area(), start.resolve(settings.cursor));
 
     debug!("Start main loop");
     DISPCNT.write(DISPCNT.read().with_force_vblank(false)); // enable display
 
+    let mut state = if self_test {
+        State::SelfTest
+    } else {
+        State::Menu
+    };
     let mut color_index = 0;
+    let mut axis_lock = AxisLock::new();
+    // first corner of a region to lock
+    let mut lock_corner: Option<Point> = None;
+    let mut shake = ScreenShake::new(SCREEN_SHAKE);
+    // the recorded strokes expect one pixel a frame from the first
+    let (cap, ramp) = if cfg!(feature = "replay") {
+        (1, 1)
+    } else {
+        CURSOR_ACCELERATION.unwrap_or((1, 1))
+    };
+    let tap = if cfg!(feature = "replay") {
+        0
+    } else {
+        CURSOR_TAP_WINDOW
+    };
+    let mut acceleration = Acceleration::new(cap, ramp, tap);
+    let mut momentum = CURSOR_MOMENTUM.map(Momentum::new);
+    let mut smoothing = Smoothing::new(STROKE_SMOOTHING.unwrap_or(0));
+    let mut b_used = false; // B was part of a combo since it went down
+    let mut r_used = false; // R was part of a combo since it went down
+    let mut select_used = false; // Select was part of a jump since it went down
+    let mut stroke_from_bar = false; // A went down over the palette bar
+    let mut jump: Option<Jump> = None;
+    let mut input = Input::new(DEBOUNCE_FRAMES, INPUT_SAMPLING);
+    let mut autosave = Autosave::new(AUTOSAVE_INTERVAL);
+    let mut toast = Toast::new();
+    let mut passes: PassScheduler<CanvasPass> = PassScheduler::new();
+    let mut histogram_panel = HistogramPanel::new();
+    #[cfg(feature = "replay")]
+    let mut replay = replay::Replay::new(&replay::EXAMPLE, &replay::EXAMPLE_EXPECTED);
+    debug!(
+        "Undo stack: {} entries, {} bytes",
+        paint.undo_stack().capacity(),
+        size_of_val(paint.undo_stack())
+    );
 
     loop {
-        // sleep until vblank interrupt
+        // sleep until vblank interrupt, timing the work done since the last one
+        profiler.stop();
         gba::bios::vblank_interrupt_wait();
+        profiler.start();
+
+        // freeze between steps with L + R + Start
+        if cfg!(feature = "debug-tools") && !frame_step.gate(&mut input) {
+            continue;
+        }
+
+        vblank::run_callbacks();
+        reticle.tick();
+        reticle.set_shake(shake.tick());
+        toast.tick(&mut display).ok();
+        mirror.tick();
+
+        // these screens draw over the canvas, which waits in the snapshot
+        let covered = state == State::Viewer || state == State::Slots || state == State::Zoom;
+
+        // save the canvas if it has unsaved changes, and isn't covered or mirrored
+        if !covered && !mirror.is_open() && autosave.is_due(timer::seconds()) {
+            // don't save overlays
+            toast.hide(&mut display).ok();
+            histogram_panel.hide(&mut display).ok();
+            settings.cursor = Some(point);
+            match storage::save_canvas().and_then(|()| storage::save_settings(&settings)) {
+                Ok(()) => {
+                    autosave.mark_saved();
+                    toast.show(&mut display, "Autosaved").ok()
+                }
+                Err(err) => {
+                    debug!("Autosave failed: {:?}", err);
+                    autosave.mark_failed(timer::seconds());
+                    toast.show(&mut display, "Autosave failed").ok()
+                }
+            };
+        }
+
+        // restart through the BIOS with A + B + Select + Start, e.g. to get back to a flashcart menu
+        if RESET_KEYS.iter().all(|&key| input.is_held(key))
+            && RESET_KEYS.iter().any(|&key| input.just_pressed(key))
+        {
+            mirror.close();
+            if covered {
+                canvas::restore_canvas();
+            }
+            if autosave.is_dirty() && (state == State::Paint || covered) {
+                toast.hide(&mut display).ok();
+                histogram_panel.hide(&mut display).ok();
+                settings.cursor = Some(point);
+                if let Err(err) =
+                    storage::save_canvas().and_then(|()| storage::save_settings(&settings))
+                {
+                    debug!("Save before reset failed: {:?}", err);
+                }
+            }
+            reset::soft_reset();
+        }
+
+        // continue long running work
+        let mut budget = Budget::start(FRAME_BUDGET);
+        if paint.resume(&mut display, &mut budget) {
+            autosave.mark_dirty(timer::seconds());
+        }
+        // a fill still running would draw under the toast
+        if !paint.is_busy() && paint.take_split() {
+            toast.show(&mut display, "Too big, undo split").ok();
+        }
+        if paint.take_too_big() {
+            toast.show(&mut display, "Too big to move").ok();
+        }
+        match passes.step(&mut display, &mut budget) {
+            Step::Done(CanvasPass::Histogram(histogram)) => {
+                debug!(
+                    "Histogram {:?} other {} unique ~{}",
+                    histogram.counts(),
+                    histogram.other(),
+                    histogram.unique()
+                );
+                histogram_panel.show(&mut display, &histogram).ok();
+            }
+            Step::Done(CanvasPass::Gradient(mut gradient)) => {
+                toast.map_backup(|point, color| gradient.pixel(point, color));
+                autosave.mark_dirty(timer::seconds());
+            }
+            Step::Done(mut pass @ CanvasPass::Quantize(_))
+            | Step::Done(mut pass @ CanvasPass::Outline(_))
+            | Step::Done(mut pass @ CanvasPass::Rotate(_)) => {
+                toast.map_backup(|point, color| pass.pixel(point, color));
+                canvas::discard_snapshot();
+                toast.show(&mut display, "Filter done").ok();
+                autosave.mark_dirty(timer::seconds());
+            }
+            Step::Done(mut pass @ CanvasPass::Faded(_))
+            | Step::Done(mut pass @ CanvasPass::Tint(_))
+            | Step::Done(mut pass @ CanvasPass::Noise(_)) => {
+                toast.map_backup(|point, color| pass.pixel(point, color));
+                toast.hide(&mut display).ok();
+                autosave.mark_dirty(timer::seconds());
+            }
+            Step::Done(CanvasPass::Score(score)) => {
+                let mut buf = [0; 16];
+                let text = fmt::with_number(&mut buf, "Score ", score.percent(), "%");
+                toast.show(&mut display, text.unwrap_or_default()).ok();
+            }
+            Step::Running(progress) => {
+                if let Some(label) = passes.pass().and_then(CanvasPass::progress_label) {
+                    let mut buf = [0; 16];
+                    let text = fmt::with_number(&mut buf, label, progress.percent(), "%");
+                    toast.show(&mut display, text.unwrap_or_default()).ok();
+                }
+            }
+            _ => {}
+        }
 
         // read buttons input
-        let input = read_key_input();
+        #[cfg(not(feature = "replay"))]
+        input.update();
+        #[cfg(feature = "replay")]
+        replay.update(&mut input, &display);
+
+        if state == State::Menu {
+            // how long the player took to pick an item is the one unpredictable input
+            if !seeded && input.held() != Keys::NONE {
+                rng::set_seed(timer::ticks());
+                seeded = true;
+            }
+            if let Some(mascot) = mascot.as_mut() {
+                mascot.show(MASCOT_POSITION);
+                mascot.tick().ok();
+            }
+            // log the mascot's frames as TGA files with L + R, a frame per loop
+            if let Some(export) = animation_export.as_mut() {
+                if export.step() {
+                    animation_export = None;
+                }
+            } else if cfg!(feature = "debug-tools")
+                && input.is_held(Key::L)
+                && input.just_pressed(Key::R)
+            {
+                animation_export = AnimationExport::new(&assets::MASCOT, layout, MASCOT_PERIOD);
+            }
+            match menu.update(&input, &mut display) {
+                Ok(Some(action))
+                    if action == MenuAction::Paint
+                        || action == MenuAction::Quantize
+                        || action == MenuAction::Outline
+                        || action == MenuAction::Trace
+                        || action == MenuAction::Noise =>
+                {
+                    let intact = open_canvas(&mut display, boot).unwrap_or(true);
+                    draw_hud(&mut display).ok();
+                    if !intact {
+                        toast.show(&mut display, "Save corrupt").ok();
+                    }
+                    paint.clear_history();
+                    layers.reset();
+                    reticle.move_to(point);
+                    toolbar.show(paint.tool);
+                    swatches.show(&paint.recent);
+                    lock_markers.show(&paint.locks);
+                    match action {
+                        MenuAction::Quantize => {
+                            passes.submit(CanvasPass::Quantize(Quantize::new(COLORS)))
+                        }
+                        MenuAction::Outline => {
+                            canvas::snapshot_canvas();
+                            passes.submit(CanvasPass::Outline(Outline));
+                        }
+                        MenuAction::Trace => passes.submit(CanvasPass::Faded(Faded)),
+                        MenuAction::Noise => passes.submit(CanvasPass::Noise(Noise::new(
+                            NOISE_SEED.unwrap_or_else(|| rng::fork().next_u32()),
+                            NOISE_SCALE,
+                            NOISE_COLORS,
+                        ))),
+                        _ => {}
+                    }
+                    state = State::Paint;
+                }
+                Ok(Some(MenuAction::Showcase)) => {
+                    showcase::draw(&mut display).ok();
+                    state = State::Showcase;
+                }
+                Ok(Some(MenuAction::TileEditor)) => {
+                    tile_editor.open(&mut display).ok();
+                    state = State::TileEditor;
+                }
+                Ok(Some(MenuAction::Assets)) => {
+                    toast.hide(&mut display).ok();
+                    asset_browser.open(&mut display).ok();
+                    state = State::Assets;
+                }
+                Ok(Some(MenuAction::TestPattern)) => {
+                    passes.submit(CanvasPass::TestPattern(TestPattern));
+                    state = State::TestPattern;
+                }
+                #[cfg(feature = "audio")]
+                Ok(Some(MenuAction::Music)) => {
+                    settings.music = match settings.music {
+                        None => Some(0),
+                        Some(track) if (track as usize + 1) < music::TRACKS.len() => {
+                            Some(track + 1)
+                        }
+                        Some(_) => None,
+                    };
+                    music::play(settings.music.map(usize::from), settings.music_volume);
+                    if let Err(err) = storage::save_settings(&settings) {
+                        debug!("Saving the music choice failed: {:?}", err);
+                    }
+                    let text = match settings.music {
+                        Some(track) => music::TRACKS[track as usize].name,
+                        None => "Music off",
+                    };
+                    toast.show(&mut display, text).ok();
+                }
+                #[cfg(not(feature = "audio"))]
+                Ok(Some(MenuAction::Music)) => {
+                    toast.show(&mut display, "Built without audio").ok();
+                }
+                _ => {}
+            }
+            // music volume with left and right
+            #[cfg(feature = "audio")]
+            {
+                if input.just_pressed(Key::Left) || input.just_pressed(Key::Right) {
+                    settings.music_volume = if input.just_pressed(Key::Right) {
+                        (settings.music_volume + 1).min(music::MAX_VOLUME)
+                    } else {
+                        settings.music_volume.saturating_sub(1)
+                    };
+                    music::set_volume(settings.music_volume);
+                    if let Err(err) = storage::save_settings(&settings) {
+                        debug!("Saving the music volume failed: {:?}", err);
+                    }
+                    let mut buf = [0; 16];
+                    let text =
+                        fmt::with_number(&mut buf, "Volume ", settings.music_volume as u32, "");
+                    toast.show(&mut display, text.unwrap_or_default()).ok();
+                }
+            }
+            if state != State::Menu {
+                if let Some(mascot) = mascot.as_mut() {
+                    mascot.hide();
+                }
+            }
+            continue;
+        }
+
+        if state == State::Showcase {
+            if input.any_just_pressed() {
+                menu.draw(&mut display).ok();
+                state = State::Menu;
+            }
+            continue;
+        }
+
+        if state == State::Assets {
+            if asset_browser.update(&input, &mut display).unwrap_or(false) {
+                state = State::Menu;
+            }
+            continue;
+        }
+
+        if state == State::SelfTest {
+            if input.any_just_pressed() {
+                self_test::hide_test_sprite();
+                menu.draw(&mut display).ok();
+                state = State::Menu;
+            }
+            continue;
+        }
+
+        if state == State::TileEditor {
+            let leave = match tile_editor.update(&input, &mut display) {
+                Ok(Some(EditorAction::Done(cells))) => {
+                    paint.stamp = Stamp::new("Custom", cells.mask_rows());
+                    settings.stamp_tile = Some(cells.pack());
+                    if let Err(err) = storage::save_settings(&settings) {
+                        debug!("Saving the stamp failed: {:?}", err);
+                    }
+                    true
+                }
+                Ok(Some(EditorAction::Cancel)) => true,
+                _ => false,
+            };
+            if leave {
+                tile_editor.close();
+                menu.draw(&mut display).ok();
+                state = State::Menu;
+            }
+            continue;
+        }
+
+        if state == State::TestPattern {
+            if input.any_just_pressed() {
+                passes.cancel();
+                menu.draw(&mut display).ok();
+                state = State::Menu;
+            }
+            continue;
+        }
+
+        if state == State::Viewer {
+            if input.any_just_pressed() {
+                canvas::restore_canvas();
+                reticle.move_to(point);
+                toolbar.show(paint.tool);
+                swatches.show(&paint.recent);
+                lock_markers.show(&paint.locks);
+                state = State::Paint;
+            }
+            continue;
+        }
+
+        if state == State::Zoom {
+            if input.just_pressed(Key::B) {
+                zoom.close();
+                reticle.move_to(point);
+                toolbar.show(paint.tool);
+                swatches.show(&paint.recent);
+                lock_markers.show(&paint.locks);
+                state = State::Paint;
+            } else {
+                // one canvas pixel a frame, panning takes a few frames anyway
+                let target = point + input.held().direction();
+                point = geom::clamp(movement_bounds.area(), target);
+                reticle.move_to(zoom.update(point));
+            }
+            continue;
+        }
+
+        if state == State::Slots {
+            let back = match slot_screen.update(&input, &mut display) {
+                Ok(Some(SlotAction::Load(n))) => {
+                    // loading draws the whole canvas, the toast must not restore over it
+                    toast.hide(&mut display).ok();
+                    match storage::load_slot(n) {
+                        Ok(Loaded::Canvas) => {
+                            canvas::discard_snapshot();
+                            paint.clear_history();
+                            layers.reset();
+                            autosave.mark_dirty(timer::seconds());
+                            true
+                        }
+                        Ok(Loaded::Empty) => {
+                            toast.show(&mut display, "Empty slot").ok();
+                            false
+                        }
+                        Ok(Loaded::Corrupt) => {
+                            toast.show(&mut display, "Save corrupt").ok();
+                            false
+                        }
+                        Err(err) => {
+                            debug!("Load failed: {:?}", err);
+                            toast.show(&mut display, "Load failed").ok();
+                            false
+                        }
+                    }
+                }
+                Ok(Some(SlotAction::Save(n))) => {
+                    toast.hide(&mut display).ok();
+                    canvas::restore_canvas();
+                    let mut buf = [0; storage::NAME_LEN];
+                    let name = fmt::with_number(&mut buf, "Slot ", n as u32 + 1, "");
+                    let saved = storage::save_slot(n, name.unwrap_or_default());
+                    canvas::snapshot_canvas();
+                    slot_screen.open(&mut display).ok();
+                    let text = match saved {
+                        Ok(()) => "Saved",
+                        Err(SaveError::OutOfBounds) => "Too big for a slot",
+                        Err(err) => {
+                            debug!("Save failed: {:?}", err);
+                            "Save failed"
+                        }
+                    };
+                    toast.show(&mut display, text).ok();
+                    false
+                }
+                Ok(Some(SlotAction::Close)) => {
+                    toast.hide(&mut display).ok();
+                    canvas::restore_canvas();
+                    true
+                }
+                _ => false,
+            };
+            if back {
+                reticle.move_to(point);
+                toolbar.show(paint.tool);
+                swatches.show(&paint.recent);
+                lock_markers.show(&paint.locks);
+                state = State::Paint;
+            }
+            continue;
+        }
+
+        // any key dismisses the histogram
+        if histogram_panel.is_visible() && input.any_just_pressed() {
+            histogram_panel.hide(&mut display).ok();
+            continue;
+        }
+
+        // R on its own redoes when released, R with any other key is a combo
+        if input.just_pressed(Key::R) {
+            r_used = false;
+        }
+        if input.is_held(Key::R) && input.held().without(Keys::NONE.with(Key::R)) != Keys::NONE {
+            r_used = true;
+        }
+
+        // prompt for each basic control until it's been used, Start skips
+        if tutorial.is_active() {
+            let skipped = input.just_pressed(Key::Start);
+            if skipped {
+                tutorial.skip();
+            }
+            let advanced = skipped || tutorial.update(&input);
+            match tutorial.prompt() {
+                Some(prompt) if advanced || !toast.is_visible() => {
+                    toast.show(&mut display, prompt).ok();
+                }
+                Some(_) => {}
+                None => {
+                    settings.tutorial_done = true;
+                    if let Err(err) = storage::save_settings(&settings) {
+                        debug!("Saving the tutorial flag failed: {:?}", err);
+                    }
+                    let text = if skipped {
+                        "Tutorial skipped"
+                    } else {
+                        "Tutorial done"
+                    };
+                    toast.show(&mut display, text).ok();
+                }
+            }
+            if skipped {
+                continue;
+            }
+        }
+
+        // report RAM usage with L + R + Select
+        if cfg!(feature = "debug-tools")
+            && input.is_held(Key::L)
+            && input.is_held(Key::R)
+            && input.just_pressed(Key::Select)
+        {
+            memory::report(&[
+                ("Undo stack", size_of_val(paint.undo_stack())),
+                ("Redo stack", size_of_val(paint.redo_stack())),
+                ("Paint state", size_of_val(&paint)),
+                ("Toast", size_of_val(&toast)),
+                ("Histogram panel", size_of_val(&histogram_panel)),
+                ("Pass scheduler", size_of_val(&passes)),
+                ("Input", size_of_val(&input)),
+            ]);
+            continue;
+        }
+
+        // log the canvas as a TGA file with L + R + Up
+        if cfg!(feature = "debug-tools")
+            && input.is_held(Key::L)
+            && input.is_held(Key::R)
+            && input.just_pressed(Key::Up)
+        {
+            // overlays aren't part of the drawing
+            toast.hide(&mut display).ok();
+            histogram_panel.hide(&mut display).ok();
+            export::export_canvas();
+            continue;
+        }
+
+        // log sprite attributes and the cursor tiles with L + R + B
+        if cfg!(feature = "debug-tools")
+            && input.is_held(Key::L)
+            && input.is_held(Key::R)
+            && input.just_pressed(Key::B)
+        {
+            b_used = true;
+            oam_report::report(reticle.tiles());
+            continue;
+        }
+
+        // warm the canvas with L + R + Right, cool it with L + R + Left
+        if input.is_held(Key::L)
+            && input.is_held(Key::R)
+            && (input.just_pressed(Key::Right) || input.just_pressed(Key::Left))
+        {
+            if passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                let delta = if input.just_pressed(Key::Right) {
+                    TINT_STEP
+                } else {
+                    -TINT_STEP
+                };
+                // undoing single pixels after the shift would leave patches
+                paint.clear_history();
+                passes.submit(CanvasPass::Tint(Tint::new(delta)));
+            }
+            continue;
+        }
+
+        // analyze canvas colors with R + Start
+        if input.is_held(Key::R) && input.just_pressed(Key::Start) {
+            if !passes.is_running() {
+                passes.submit(CanvasPass::Histogram(Histogram::new(COLORS)));
+            }
+            continue;
+        }
+
+        // cycle composition guides with R + Select
+        if input.is_held(Key::R) && input.just_pressed(Key::Select) {
+            let text = match guides.cycle() {
+                Some(GuideKind::RuleOfThirds) => "Guide: thirds",
+                Some(GuideKind::CenterCross) => "Guide: center",
+                Some(GuideKind::Circle) => "Guide: circle",
+                None => "Guide off",
+            };
+            toast.show(&mut display, text).ok();
+            continue;
+        }
+
+        // toggle dithered fills with R + B
+        if input.is_held(Key::R) && input.just_pressed(Key::B) {
+            b_used = true;
+            paint.dither = !paint.dither;
+            let text = if paint.dither {
+                "Dither on"
+            } else {
+                "Dither off"
+            };
+            toast.show(&mut display, text).ok();
+            continue;
+        }
+
+        // score the canvas against the traced image with R + Up
+        if input.is_held(Key::R) && input.just_pressed(Key::Up) {
+            if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                // overlays aren't part of the drawing
+                toast.hide(&mut display).ok();
+                histogram_panel.hide(&mut display).ok();
+                passes.submit(CanvasPass::Score(Score::new()));
+            }
+            continue;
+        }
+
+        // R + Left marks a corner, again locks the region up to the cursor or unlocks the one under it
+        if input.is_held(Key::R) && input.just_pressed(Key::Left) {
+            let text = if paint.locks.unlock_at(point) {
+                lock_corner = None;
+                "Unlocked"
+            } else {
+                match lock_corner.take() {
+                    Some(corner) => match paint.locks.lock(corner, point) {
+                        Ok(()) => "Locked",
+                        Err(Full) => "Too many locks",
+                    },
+                    None => {
+                        lock_corner = Some(point);
+                        "Lock from here"
+                    }
+                }
+            };
+            lock_markers.show(&paint.locks);
+            toast.show(&mut display, text).ok();
+            continue;
+        }
+
+        // cycle the brush stamp with R + Right
+        if input.is_held(Key::R) && input.just_pressed(Key::Right) {
+            paint.stamp = paint.stamp.next();
+            toast.show(&mut display, paint.stamp.name).ok();
+            continue;
+        }
+
+        // pick a save slot with L + R + A
+        if input.is_held(Key::L) && input.is_held(Key::R) && input.just_pressed(Key::A) {
+            if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                toast.hide(&mut display).ok();
+                histogram_panel.hide(&mut display).ok();
+                mirror.close();
+                canvas::snapshot_canvas();
+                slot_screen.open(&mut display).ok();
+                reticle.hide();
+                toolbar.hide();
+                swatches.hide();
+                lock_markers.hide();
+                state = State::Slots;
+            }
+            continue;
+        }
+
+        // magnify the canvas around the cursor with L + R + Down
+        if input.is_held(Key::L) && input.is_held(Key::R) && input.just_pressed(Key::Down) {
+            if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                toast.hide(&mut display).ok();
+                mirror.close();
+                reticle.move_to(zoom.open(point));
+                toolbar.hide();
+                swatches.hide();
+                lock_markers.hide();
+                state = State::Zoom;
+            }
+            continue;
+        }
+
+        // view an image with R + Down, the canvas is restored afterwards
+        if input.is_held(Key::R) && input.just_pressed(Key::Down) {
+            match assets::find(VIEWER_IMAGE).and_then(Asset::tga) {
+                // background work would draw over the image
+                Some(_) if paint.is_busy() || passes.is_running() => {
+                    toast.show(&mut display, "Busy").ok();
+                }
+                Some(tga) => {
+                    // the toast restores what was under it, which the snapshot would keep
+                    toast.hide(&mut display).ok();
+                    mirror.close();
+                    canvas::snapshot_canvas();
+                    blit::draw(&mut display, &tga, Placement::Fit).ok();
+                    reticle.hide();
+                    toolbar.hide();
+                    swatches.hide();
+                    lock_markers.hide();
+                    state = State::Viewer;
+                }
+                None => debug!("No image named {}", VIEWER_IMAGE),
+            }
+            continue;
+        }
 
-        // clear
-        if input.start() {
-            draw_background(&mut display).ok();
+        // measure from the cursor with R + L, again to stop
+        if input.is_held(Key::R) && input.just_pressed(Key::L) {
+            ruler.toggle(point);
+            continue;
+        }
+
+        // cycle brush and line blend modes with R + A
+        if input.is_held(Key::R) && input.just_pressed(Key::A) {
+            paint.blend = paint.blend.next();
+            let text = match paint.blend {
+                BlendMode::Normal => "Blend: normal",
+                BlendMode::Additive => "Blend: add",
+                BlendMode::Subtractive => "Blend: subtract",
+                BlendMode::Multiply => "Blend: multiply",
+            };
+            toast.show(&mut display, text).ok();
+            continue;
+        }
+
+        // show or hide the top layer with B + Select, swap layers with B + Start
+        if LAYER_CONTROLS
+            && input.is_held(Key::B)
+            && (input.just_pressed(Key::Select) || input.just_pressed(Key::Start))
+        {
+            b_used = true;
+            if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+                continue;
+            }
+            // drop previews and overlays so they aren't captured into a layer
+            paint.set_tool(paint.tool, &mut display);
+            toast.hide(&mut display).ok();
+            histogram_panel.hide(&mut display).ok();
+            let text = if input.just_pressed(Key::Start) {
+                layers.swap();
+                "Layers swapped"
+            } else if layers.toggle_top() {
+                "Top layer shown"
+            } else {
+                "Top layer hidden"
+            };
+            // undoing single pixels after compositing would leave patches
+            paint.clear_history();
+            autosave.mark_dirty(timer::seconds());
+            toast.show(&mut display, text).ok();
+            continue;
+        }
+
+        // turn the canvas with B + R
+        if input.is_held(Key::B) && input.just_pressed(Key::R) {
+            b_used = true;
+            if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                // overlays would be turned into the drawing
+                toast.hide(&mut display).ok();
+                histogram_panel.hide(&mut display).ok();
+                // undoing single pixels after turning would leave patches
+                paint.clear_history();
+                mirror.close();
+                rotate_canvas(&mut passes, ROTATE_STEP);
+            }
+            continue;
+        }
+
+        // preview the canvas mirrored with Select + Start, again to stop
+        if MIRROR_PREVIEW_ROWS.is_some()
+            && input.is_held(Key::Select)
+            && input.just_pressed(Key::Start)
+        {
+            if mirror.is_open() {
+                mirror.close();
+            } else if paint.is_busy() || passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                toast.hide(&mut display).ok();
+                histogram_panel.hide(&mut display).ok();
+                mirror.open();
+            }
+            // Select isn't picking a tool this time
+            radial.close();
+            select_used = true;
+            continue;
+        }
+
+        // clear, only the top layer while it's being edited
+        if input.just_pressed(Key::Start) {
+            toast.hide(&mut display).ok();
+            if !layers.clear_top() {
+                background::draw(&mut display).ok();
+            }
             draw_hud(&mut display).ok();
+            paint.clear_history();
+            autosave.mark_dirty(timer::seconds());
+            shake.start();
             continue;
         }
 
-        // cycle cursor
-        if input.b() {
-            color_index += 1;
-            if color_index >= COLORS.len() {
-                color_index = 0;
+        // undo and redo wait for a fill, which adds to the newest step
+        let undo_pressed = input.just_pressed(Key::L) && !input.is_held(Key::R);
+        let redo_pressed = input.just_released(Key::R) && !r_used;
+        if paint.is_busy() && (undo_pressed || redo_pressed) {
+            toast.show(&mut display, "Busy").ok();
+        }
+
+        // undo one pixel per frame while held, R + L is the ruler
+        if input.is_held(Key::L) && !input.is_held(Key::R) && paint.undo(&mut display) {
+            autosave.mark_dirty(timer::seconds());
+        }
+
+        // redo one step per tap of R
+        if redo_pressed && paint.redo(&mut display) {
+            autosave.mark_dirty(timer::seconds());
+        }
+
+        // cycle color, on release if holding B locks an axis
+        let cycle = if AXIS_LOCK {
+            input.just_released(Key::B) && !b_used
+        } else {
+            input.just_pressed(Key::B)
+        };
+        if !input.is_held(Key::B) {
+            b_used = false;
+        }
+        if cycle {
+            color_index = (color_index + 1) % COLORS.len();
+            paint.color = COLORS[color_index];
+            reticle.set_color(paint.color);
+        }
+
+        // pick a tool from the ring while Select is held, the cursor stays put
+        if RADIAL_MENU {
+            if input.just_pressed(Key::Select) {
+                radial.open(point);
+            }
+            if radial.is_open() {
+                if input.is_held(Key::Select) {
+                    radial.update(input.held().direction());
+                    continue;
+                }
+                let tool = radial.close().unwrap_or_else(|| paint.tool.next());
+                paint.set_tool(tool, &mut display);
+                reticle.set_style(tool.reticle());
+                toolbar.show(tool);
+            }
+        } else if CORNER_JUMPS {
+            // Select on its own cycles the tool when released
+            if input.just_pressed(Key::Select) {
+                select_used = false;
+            }
+            if input.is_held(Key::Select) {
+                let center = input.just_pressed(Key::A);
+                let dpad = [Key::Up, Key::Down, Key::Left, Key::Right];
+                if center || dpad.iter().any(|&key| input.just_pressed(key)) {
+                    select_used = true;
+                    let direction = if center {
+                        Point::zero()
+                    } else {
+                        input.held().direction()
+                    };
+                    let target = geom::jump_target(movement_bounds.area(), direction);
+                    jump = Some(Jump::new(point, target, JUMP_FRAMES));
+                }
+            } else if input.just_released(Key::Select) && !select_used {
+                paint.cycle_tool(&mut display);
+                reticle.set_style(paint.tool.reticle());
+                toolbar.show(paint.tool);
             }
+        } else if input.just_pressed(Key::Select) {
+            // cycle tool
+            paint.cycle_tool(&mut display);
+            reticle.set_style(paint.tool.reticle());
+            toolbar.show(paint.tool);
         }
 
         // adjust game state and wait for vblank
-        let offset = Point::new(input.x_tribool() as i32, input.y_tribool() as i32);
-        point += offset;
-
-        if let Ok((x @ 0..WIDTH, y @ 0..HEIGHT)) = point.try_into() {
-            move_cursor(color_index as u16, x as u16, y as u16);
-            if input.a() {
-                Pixel(Point::new(x as i32, y as i32), COLORS[color_index])
-                    .draw(&mut display)
-                    .ok();
+        let jumping = CORNER_JUMPS && input.is_held(Key::Select);
+        let dpad = if jumping {
+            Point::zero()
+        } else {
+            input.held().direction()
+        };
+        let direction = axis_lock.apply(AXIS_LOCK && input.is_held(Key::B), dpad);
+        b_used |= axis_lock.is_locked();
+        // B held as the stroke modifier isn't a color change when released
+        b_used |= BRUSH_HOLD == BrushHold::Modifier(Key::B) && input.is_held(Key::A);
+        let step = match momentum.as_mut() {
+            Some(momentum) => momentum.apply(direction),
+            None => acceleration.apply(direction),
+        };
+        let target = match jump.as_mut().and_then(Iterator::next) {
+            Some(next) => next,
+            None => {
+                jump = None;
+                point + step
             }
+        };
+        // a press on the bar picks from it, a stroke from the canvas stays out
+        if input.just_pressed(Key::A) {
+            stroke_from_bar = movement_bounds.in_bar(point);
+        }
+        let interaction = if input.is_held(Key::A) && !stroke_from_bar {
+            Interaction::Paint
         } else {
-            point -= offset; // undo
+            Interaction::Ui
+        };
+        point = movement_bounds.step(interaction, point, target);
+        // don't keep accelerating or coasting into the edge
+        if point != target {
+            acceleration.stop();
+            if let Some(momentum) = momentum.as_mut() {
+                momentum.stop();
+            }
+        }
+        reticle.move_to(point);
+        if let Some(color) = auto_outline
+            .as_mut()
+            .and_then(|outline| outline.update(&display, point))
+        {
+            reticle.set_outline(Some(color)).ok();
+        }
+        ruler.update(point);
+        // connect shapes exactly by starting and ending lines on loose ends
+        let snapped = match LINE_SNAP_RADIUS {
+            Some(radius) if paint.tool == Tool::Line || paint.tool == Tool::Polygon => {
+                snap::nearest_end(&display, point, radius as i32, &COLORS)
+            }
+            _ => None,
+        };
+        snap_marker.show(snapped);
+        if jumping {
+            continue;
         }
 
-        // wait for button to be released
-        while read_key_input().b() {
-            gba::bios::vblank_interrupt_wait();
+        // the toolbar is not part of the canvas
+        if geom::rect_contains(&toolbar.bounds(), point) {
+            match toolbar.tool_at(&reticle) {
+                Some(tool) if input.just_pressed(Key::A) => {
+                    paint.set_tool(tool, &mut display);
+                    reticle.set_style(tool.reticle());
+                    toolbar.show(tool);
+                }
+                _ => {}
+            }
+            continue;
+        }
+        // so are the recent color swatches, empty ones are hidden and paintable
+        if let Some(color) = swatches.color_at(&reticle) {
+            if input.just_pressed(Key::A) {
+                paint.color = color;
+                reticle.set_color(color);
+            }
+            continue;
+        }
+
+        if !input.is_held(Key::A) {
+            smoothing.reset();
+        }
+        let stroke = snapped.unwrap_or_else(|| smoothing.apply(point));
+        let color = paint.color;
+        if paint.apply(&input, stroke, &mut display) {
+            autosave.mark_dirty(timer::seconds());
+        }
+        if !paint.is_busy() && paint.take_split() {
+            toast.show(&mut display, "Too big, undo split").ok();
+        }
+        if paint.take_too_big() {
+            toast.show(&mut display, "Too big to move").ok();
+        }
+        if paint.color != color {
+            reticle.set_color(paint.color); // picked by the eyedropper
+        }
+        swatches.update(&paint.recent);
+        if let Some(gradient) = paint.take_gradient() {
+            if passes.is_running() {
+                toast.show(&mut display, "Busy").ok();
+            } else {
+                // undoing single pixels under a gradient would leave holes
+                paint.clear_history();
+                passes.submit(CanvasPass::Gradient(gradient));
+            }
         }
     }
 }
 
-extern "C" fn irq_handler(flags: IrqFlags) {
-    if flags.vblank() {
-        BIOS_IF.write(BIOS_IF.read().with_vblank(true)); // clear vblank flag
-        IF.write(IF.read().with_vblank(true));
+/// Fill the canvas as `boot` says, so painting never starts on leftover VRAM
+///
+/// Returns false if the saved canvas failed its checksum, the image is
+/// shown instead.
+fn open_canvas(display: &mut GbaDisplay, boot: BootBehavior) -> Result<bool, Infallible> {
+    match boot {
+        BootBehavior::FreshClear(color) => display.clear(color)?,
+        BootBehavior::RestoreSaved => {
+            background::draw(display)?;
+            match storage::load_canvas() {
+                Ok(Loaded::Canvas) => {}
+                Ok(Loaded::Empty) => debug!("No saved canvas"),
+                Ok(Loaded::Corrupt) => {
+                    debug!("Saved canvas is corrupt");
+                    return Ok(false);
+                }
+                Err(err) => debug!("Load failed: {:?}", err),
+            }
+        }
+        BootBehavior::ShowImage => background::draw(display)?,
     }
+    Ok(true)
 }
 
-fn draw_background(display: &mut GbaDisplay) -> Result<(), Infallible> {
-    let tga = Tga::from_slice(include_bytes!("../assets/amy.tga")).unwrap();
-    let image: Image<Tga, Bgr555> = Image::new(&tga, Point::zero());
-    image.draw(display)?;
-    Ok(())
+/// Start turning the canvas clockwise by `angle` in 256ths of a turn
+fn rotate_canvas(passes: &mut PassScheduler<CanvasPass>, angle: u8) {
+    canvas::snapshot_canvas();
+    passes.submit(CanvasPass::Rotate(Rotate::new(angle, Bgr555::WHITE)));
 }
 
 fn draw_hud(display: &mut GbaDisplay) -> Result<(), Infallible> {
-    Rectangle::new(Point::new(0, 0), Point::new(72, 24))
+    Rectangle::new(Point::new(0, 0), Point::new(72, 40))
         .into_styled(PrimitiveStyle::with_fill(Bgr555::WHITE))
         .draw(display)?;
     Text::new("A: Draw", Point::new(1, 1))
@@ -158,12 +1588,21 @@ fn draw_hud(display: &mut GbaDisplay) -> Result<(), Infallible> {
     Text::new("B: Color", Point::new(1, 9))
         .into_styled(TextStyle::new(Font6x8, Bgr555::GREEN))
         .draw(display)?;
-    Text::new("Start: Clear", Point::new(1, 17))
+    Text::new("Select: Tool", Point::new(1, 17))
+        .into_styled(TextStyle::new(Font6x8, Bgr555::MAGENTA))
+        .draw(display)?;
+    Text::new("L: Undo", Point::new(1, 25))
+        .into_styled(TextStyle::new(Font6x8, Bgr555::BLACK))
+        .draw(display)?;
+    Text::new("Start: Clear", Point::new(1, 33))
         .into_styled(TextStyle::new(Font6x8, Bgr555::BLUE))
         .draw(display)?;
     Ok(())
 }
 
+/// Fill the object palette, which only sprites read
+///
+/// The Mode3 canvas stores colors directly and reads neither palette.
 fn register_palette() {
     // slot 0 is for transparency
     for (i, color) in COLORS.iter().enumerate() {
@@ -171,30 +1610,10 @@ fn register_palette() {
     }
 }
 
-fn draw_cursor() -> Result<(), Infallible> {
-    let mut tile = Tile8bpp([PaletteColor::TANSPARENT.into_storage().into(); 16]);
-
-    for i in 1..=COLORS.len() {
-        let color = PaletteColor::new(i as u8);
-        egtriangle!(
-            points = [(0, 0), (7, 4), (4, 7)],
-            style = primitive_style!(stroke_color = color, fill_color = color, stroke_width = 1)
-        )
-        .draw(&mut tile)?;
-
-        get_8bpp_character_block(5).index(i).write(tile);
+/// Fill the background palette from slot 1, which only tiled backgrounds read
+fn register_bg_palette(colors: &[Bgr555]) {
+    // slot 0 is the backdrop shown where no layer draws
+    for (i, color) in colors.iter().enumerate() {
+        index_palram_bg_8bpp(i as u8 + 1).write(Color(color.into_storage()));
     }
-
-    Ok(())
-}
-
-fn move_cursor(index: u16, x: u16, y: u16) {
-    write_obj_attributes(
-        0,
-        ObjectAttributes {
-            attr0: OBJAttr0::new().with_row_coordinate(y).with_is_8bpp(true),
-            attr1: OBJAttr1::new().with_col_coordinate(x),
-            attr2: OBJAttr2::new().with_tile_id(514 + (index * 2)),
-        },
-    );
 }
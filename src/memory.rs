@@ -0,0 +1,63 @@
+//! Rough RAM usage report for development
+//!
+//! The canvas snapshot and the layers are statics in `.ewram`, which the
+//! linker script brackets with `__ewram_start` and `__ewram_end`. The rest
+//! of the large buffers live on the IWRAM stack, so the stack depth in the
+//! main loop is most of the IWRAM in use. `.data` and `.bss` take the rest
+//! and aren't counted.
+//!
+//! Only the addresses of the linker symbols are read, which still takes
+//! `unsafe`.
+
+#![allow(unsafe_code)]
+
+use gba::debug;
+
+extern "C" {
+    static __ewram_start: u8;
+    static __ewram_end: u8;
+}
+
+/// User stack pointer set in crt0.s
+const STACK_TOP: usize = 0x3007f00;
+
+const IWRAM_START: usize = 0x300_0000;
+const IWRAM_LEN: usize = 32 * 1024;
+const EWRAM_LEN: usize = 256 * 1024;
+
+/// Bytes of `.ewram` statics
+pub fn ewram_used() -> usize {
+    // SAFETY: only the addresses are taken, the symbols are never read
+    let (start, end) = unsafe { (&__ewram_start as *const u8, &__ewram_end as *const u8) };
+    end as usize - start as usize
+}
+
+/// Bytes of stack in use by the caller and everything above it
+#[inline(never)]
+pub fn stack_used() -> usize {
+    let marker = 0u8;
+    STACK_TOP.saturating_sub(&marker as *const u8 as usize)
+}
+
+/// Log the size of each named buffer and how much IWRAM is left
+pub fn report(buffers: &[(&str, usize)]) {
+    for &(name, size) in buffers {
+        debug!("{}: {} bytes", name, size);
+    }
+    let total: usize = buffers.iter().map(|&(_, size)| size).sum();
+    let used = stack_used();
+    debug!("Buffers: {} bytes", total);
+    debug!(
+        "Stack: {} bytes used, at most {} of {} IWRAM bytes free",
+        used,
+        (STACK_TOP - IWRAM_START).saturating_sub(used),
+        IWRAM_LEN
+    );
+    let ewram = ewram_used();
+    debug!(
+        "EWRAM: {} bytes used, {} of {} bytes free",
+        ewram,
+        EWRAM_LEN.saturating_sub(ewram),
+        EWRAM_LEN
+    );
+}
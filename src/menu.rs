@@ -0,0 +1,156 @@
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    style::TextStyle,
+};
+use gba::vram::bitmap::Mode3;
+
+use crate::{
+    assets::{self, Asset},
+    blit::{self, Placement},
+    color, font,
+    gba_display::GbaDisplay,
+    input::{Input, Key},
+};
+
+/// What selecting a menu item does
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MenuAction {
+    Paint,
+    /// Paint after snapping the canvas to the registered colors
+    Quantize,
+    /// Paint after turning the canvas into an outline drawing
+    Outline,
+    /// Paint over a faded copy of the background image, R + Up scores it
+    Trace,
+    /// Paint over a noise texture
+    Noise,
+    Showcase,
+    TestPattern,
+    /// Draw a custom brush stamp
+    TileEditor,
+    /// Page through the embedded images
+    Assets,
+    /// Switch to the next background tune, or mute after the last
+    Music,
+}
+
+const ITEMS: [(&str, MenuAction); 10] = [
+    ("Paint", MenuAction::Paint),
+    ("Quantize", MenuAction::Quantize),
+    ("Outline", MenuAction::Outline),
+    ("Trace", MenuAction::Trace),
+    ("Noise", MenuAction::Noise),
+    ("Primitives", MenuAction::Showcase),
+    ("Test pattern", MenuAction::TestPattern),
+    ("Tile editor", MenuAction::TileEditor),
+    ("Assets", MenuAction::Assets),
+    ("Music", MenuAction::Music),
+];
+
+const TITLE: &str = "AMY PAINT";
+const PANEL_WIDTH: i32 = 96;
+const LINE_HEIGHT: i32 = 10;
+const PANEL_HEIGHT: i32 = LINE_HEIGHT * (ITEMS.len() as i32 + 2); // title, items, margin
+
+/// Title screen with a list of actions
+pub struct Menu {
+    selected: usize,
+    background: Option<&'static Asset>,
+    placement: Placement,
+}
+
+impl Menu {
+    pub const fn new() -> Self {
+        Self {
+            selected: 0,
+            background: None,
+            placement: Placement::Fit,
+        }
+    }
+
+    /// Show the named asset behind the menu, returns false if there is no such asset
+    pub fn set_menu_background(&mut self, name: &str, placement: Placement) -> bool {
+        self.background = assets::find(name);
+        self.placement = placement;
+        self.background.is_some()
+    }
+
+    /// Draw the whole menu over the display
+    pub fn draw(&self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        match self.background.and_then(Asset::tga) {
+            Some(tga) => blit::draw(display, &tga, self.placement)?,
+            None => display.clear(Bgr555::BLACK)?,
+        }
+
+        // darken the background behind the text so it stays readable
+        let top_left = panel_top_left();
+        for y in top_left.y..top_left.y + PANEL_HEIGHT {
+            for x in top_left.x..top_left.x + PANEL_WIDTH {
+                let point = Point::new(x, y);
+                if let Some(pixel) = display.get_pixel(point) {
+                    Pixel(point, color::blend(pixel, Bgr555::BLACK, 10)).draw(display)?;
+                }
+            }
+        }
+
+        font::draw_custom_text(
+            display,
+            &font::PIXEL_3X5,
+            TITLE,
+            top_left + Point::new(4, 2),
+            Bgr555::YELLOW,
+            2,
+        )?;
+        for i in 0..ITEMS.len() {
+            self.draw_item(display, i)?;
+        }
+        Ok(())
+    }
+
+    /// Move the selection with up and down, returns the action chosen with A
+    pub fn update(
+        &mut self,
+        input: &Input,
+        display: &mut GbaDisplay,
+    ) -> Result<Option<MenuAction>, Infallible> {
+        let previous = self.selected;
+        if input.just_pressed(Key::Up) {
+            self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+        }
+        if input.just_pressed(Key::Down) {
+            self.selected = (self.selected + 1) % ITEMS.len();
+        }
+        if self.selected != previous {
+            self.draw_item(display, previous)?;
+            self.draw_item(display, self.selected)?;
+        }
+
+        if input.just_pressed(Key::A) || input.just_pressed(Key::Start) {
+            return Ok(Some(ITEMS[self.selected].1));
+        }
+        Ok(None)
+    }
+
+    /// Redrawing the same glyphs in another color needs no erasing
+    fn draw_item(&self, display: &mut GbaDisplay, i: usize) -> Result<(), Infallible> {
+        let point = panel_top_left() + Point::new(10, LINE_HEIGHT * (i as i32 + 1) + 4);
+        let color = if i == self.selected {
+            Bgr555::WHITE
+        } else {
+            Bgr555::new(16, 16, 16)
+        };
+        Text::new(ITEMS[i].0, point)
+            .into_styled(TextStyle::new(Font6x8, color))
+            .draw(display)
+    }
+}
+
+fn panel_top_left() -> Point {
+    Point::new(
+        (Mode3::WIDTH as i32 - PANEL_WIDTH) / 2,
+        (Mode3::HEIGHT as i32 - PANEL_HEIGHT) / 2,
+    )
+}
@@ -0,0 +1,68 @@
+//! Preview the canvas mirrored, the left half reflected onto the right
+//!
+//! Opening takes a canvas snapshot so closing can put the right half back
+//! as it was, only strokes on the left half are kept. While open, each
+//! frame copies a few rows of the left half flipped onto the right, so a
+//! stroke shows up mirrored once its rows come around again. A row is 120 reads and writes of VRAM, about 2500 cycles, so the
+//! default 8 rows a frame take around a quarter of vblank and the whole
+//! screen is refreshed every 20 frames. Strokes on the right half are
+//! overwritten the same way.
+
+use gba::vram::bitmap::Mode3;
+
+use crate::canvas;
+
+const HALF: usize = Mode3::WIDTH / 2;
+
+/// Rows copied per frame and the next row to copy
+pub struct Mirror {
+    rows: usize,
+    row: usize,
+    open: bool,
+}
+
+impl Mirror {
+    /// Copy `rows` rows a frame while open, at least one
+    pub const fn new(rows: usize) -> Self {
+        Self {
+            rows: if rows == 0 { 1 } else { rows },
+            row: 0,
+            open: false,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Snapshot the canvas and start mirroring from the top row
+    pub fn open(&mut self) {
+        canvas::snapshot_canvas();
+        self.row = 0;
+        self.open = true;
+    }
+
+    /// Put the right half back from the snapshot, does nothing if closed
+    pub fn close(&mut self) {
+        if self.open {
+            canvas::restore_columns(HALF..Mode3::WIDTH);
+            self.open = false;
+        }
+    }
+
+    /// Mirror the next rows, call once per frame
+    pub fn tick(&mut self) {
+        if !self.open {
+            return;
+        }
+        for _ in 0..self.rows {
+            let y = self.row;
+            for x in 0..HALF {
+                if let Some(color) = Mode3::read(x, y) {
+                    Mode3::write(Mode3::WIDTH - 1 - x, y, color);
+                }
+            }
+            self.row = (self.row + 1) % Mode3::HEIGHT;
+        }
+    }
+}
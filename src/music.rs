@@ -0,0 +1,191 @@
+//! Looping chiptunes on the two square wave channels
+//!
+//! `tick` runs as a vblank callback, so note lengths are in frames. Square
+//! 1 plays the melody and square 2 the bass. Each voice loops on its own,
+//! so both voices of a track must add up to the same number of frames or
+//! they drift apart.
+//!
+//! The registers are written by address, like in `audio`, which turns on
+//! the sound hardware and must be initialized first.
+
+#![allow(unsafe_code)]
+
+use gba::sync::Static;
+use voladdress::VolAddress;
+
+/// Loudest `play` volume
+pub const MAX_VOLUME: u8 = 7;
+
+/// A tone rate held for some frames, a `None` rate is a rest
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Note {
+    rate: Option<u16>,
+    frames: u8,
+}
+
+/// `hz` held for `frames`
+const fn note(hz: u32, frames: u8) -> Note {
+    Note {
+        rate: Some((2048 - 131_072 / hz) as u16),
+        frames,
+    }
+}
+
+/// Silence for `frames`
+const fn rest(frames: u8) -> Note {
+    Note { rate: None, frames }
+}
+
+const C3: u32 = 131;
+const F3: u32 = 175;
+const G3: u32 = 196;
+const C4: u32 = 262;
+const D4: u32 = 294;
+const E4: u32 = 330;
+const F4: u32 = 349;
+const G4: u32 = 392;
+const A4: u32 = 440;
+const B4: u32 = 494;
+const C5: u32 = 523;
+const D5: u32 = 587;
+
+/// Melody and bass, each looping
+pub struct Track {
+    pub name: &'static str,
+    voices: [&'static [Note]; 2],
+}
+
+/// Built in tunes, in the order the menu cycles through them
+pub static TRACKS: [Track; 2] = [
+    Track {
+        name: "Ode",
+        voices: [
+            &[
+                note(E4, 15),
+                note(E4, 15),
+                note(F4, 15),
+                note(G4, 15),
+                note(G4, 15),
+                note(F4, 15),
+                note(E4, 15),
+                note(D4, 15),
+                note(C4, 15),
+                note(C4, 15),
+                note(D4, 15),
+                note(E4, 15),
+                note(E4, 22),
+                note(D4, 8),
+                note(D4, 24),
+                rest(6),
+            ],
+            &[note(C3, 60), note(G3, 60), note(C3, 60), note(G3, 60)],
+        ],
+    },
+    Track {
+        name: "Arpeggio",
+        voices: [
+            &[
+                note(C4, 8),
+                note(E4, 8),
+                note(G4, 8),
+                note(C5, 8),
+                note(F4, 8),
+                note(A4, 8),
+                note(C5, 8),
+                note(A4, 8),
+                note(G4, 8),
+                note(B4, 8),
+                note(D5, 8),
+                note(B4, 8),
+                note(C5, 8),
+                note(G4, 8),
+                note(E4, 8),
+                rest(8),
+            ],
+            &[note(C3, 32), note(F3, 32), note(G3, 32), note(C3, 32)],
+        ],
+    },
+];
+
+/// Initial volume 12 fading every 3 steps, half duty
+const MELODY_ENVELOPE: u16 = 0xC380;
+/// Initial volume 10 fading every 5 steps, quarter duty
+const BASS_ENVELOPE: u16 = 0xA540;
+/// Start the note, without a length limit
+const TRIGGER: u16 = 0x8000;
+/// Square 1 and 2 on both speakers, the volume goes in the low bits
+const ENABLE_SQUARES: u16 = 0x3300;
+
+const SOUND1CNT_L: usize = 0x0400_0060;
+const SOUND1CNT_H: usize = 0x0400_0062;
+const SOUND1CNT_X: usize = 0x0400_0064;
+const SOUND2CNT_L: usize = 0x0400_0068;
+const SOUND2CNT_H: usize = 0x0400_006C;
+const SOUNDCNT_L: usize = 0x0400_0080;
+
+/// Envelope register, frequency register and envelope setting of each voice
+const VOICES: [(usize, usize, u16); 2] = [
+    (SOUND1CNT_H, SOUND1CNT_X, MELODY_ENVELOPE),
+    (SOUND2CNT_L, SOUND2CNT_H, BASS_ENVELOPE),
+];
+
+/// Index into `TRACKS` of the playing track
+static TRACK: Static<Option<usize>> = Static::new(None);
+
+/// Next note of each voice and frames until it starts
+static POSITIONS: Static<[(usize, u8); 2]> = Static::new([(0, 0); 2]);
+
+/// Start `track` from the beginning at `volume`, up to `MAX_VOLUME`,
+/// `None` mutes
+pub fn play(track: Option<usize>, volume: u8) {
+    TRACK.write(track.filter(|&track| track < TRACKS.len()));
+    POSITIONS.write([(0, 0); 2]);
+    // SAFETY: this is the documented sweep register, 0 turns the sweep off
+    unsafe { VolAddress::<u16>::new(SOUND1CNT_L).write(0) };
+    set_volume(volume);
+}
+
+/// Change the volume, up to `MAX_VOLUME`, without restarting the track
+pub fn set_volume(volume: u8) {
+    let volume = volume.min(MAX_VOLUME) as u16;
+    let control = match TRACK.read() {
+        Some(_) => ENABLE_SQUARES | volume << 4 | volume,
+        None => 0,
+    };
+    // SAFETY: these are the documented sound registers, written with values
+    // that are valid for them
+    unsafe { VolAddress::<u16>::new(SOUNDCNT_L).write(control) };
+}
+
+/// Vblank callback, starts each voice's next note when the last one is over
+pub fn tick() {
+    let track = match TRACK.read() {
+        Some(track) => &TRACKS[track],
+        None => return,
+    };
+    let mut positions = POSITIONS.read();
+    for ((voice, position), &(envelope, frequency, shape)) in track
+        .voices
+        .iter()
+        .zip(positions.iter_mut())
+        .zip(VOICES.iter())
+    {
+        let (index, frames) = position;
+        if *frames == 0 {
+            let note = voice[*index];
+            let (shape, rate) = match note.rate {
+                Some(rate) => (shape, rate),
+                None => (0, 0), // zero initial volume
+            };
+            // SAFETY: as in `set_volume`
+            unsafe {
+                VolAddress::<u16>::new(envelope).write(shape);
+                VolAddress::<u16>::new(frequency).write(TRIGGER | rate);
+            }
+            *frames = note.frames;
+            *index = (*index + 1) % voice.len();
+        }
+        *frames -= 1;
+    }
+    POSITIONS.write(positions);
+}
@@ -0,0 +1,131 @@
+//! Fill the canvas with random noise as a texture to paint over
+//!
+//! Every value comes from an `Rng` seeded by the seed and the position
+//! alone, so a seed always gives the same noise whatever order the pixels
+//! are visited in.
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+use crate::{rng::Rng, scheduler::Pass};
+
+/// Value noise, white noise at scale 1
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Noise {
+    seed: u32,
+    /// Pixels between random values, blended in between
+    scale: i32,
+    /// Colors the noise picks from, grayscale when `None`
+    colors: Option<&'static [Bgr555]>,
+}
+
+impl Noise {
+    /// A `scale` of 0 is treated as 1
+    pub fn new(seed: u32, scale: u8, colors: Option<&'static [Bgr555]>) -> Self {
+        Self {
+            seed,
+            scale: scale.max(1) as i32,
+            colors,
+        }
+    }
+
+    /// Noise value at `point` from 0 to 255
+    pub fn value(&self, point: Point) -> u32 {
+        let cell = Point::new(point.x / self.scale, point.y / self.scale);
+        // weights of the next lattice point in 256ths
+        let fx = (point.x % self.scale * 256 / self.scale) as u32;
+        let fy = (point.y % self.scale * 256 / self.scale) as u32;
+        let lerp = |a: u32, b: u32, t: u32| (a * (256 - t) + b * t) / 256;
+        let top = lerp(
+            self.lattice(cell),
+            self.lattice(cell + Point::new(1, 0)),
+            fx,
+        );
+        let bottom = lerp(
+            self.lattice(cell + Point::new(0, 1)),
+            self.lattice(cell + Point::new(1, 1)),
+            fx,
+        );
+        lerp(top, bottom, fy)
+    }
+
+    /// Random value from 0 to 255 at a lattice point
+    fn lattice(&self, cell: Point) -> u32 {
+        let mut mixed = self.seed
+            ^ (cell.x as u32).wrapping_mul(0x9e37_79b1)
+            ^ (cell.y as u32).wrapping_mul(0x85eb_ca77);
+        // xorshift hardly spreads a change in the low bits to the top byte
+        // read below, so every bit is mixed into every other first
+        mixed ^= mixed >> 16;
+        mixed = mixed.wrapping_mul(0x85eb_ca6b);
+        mixed ^= mixed >> 13;
+        mixed = mixed.wrapping_mul(0xc2b2_ae35);
+        mixed ^= mixed >> 16;
+        Rng::new(mixed).next_u32() >> 24
+    }
+}
+
+impl Pass for Noise {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        let value = self.value(point);
+        Some(match self.colors.filter(|colors| !colors.is_empty()) {
+            Some(colors) => colors[value as usize * colors.len() / 256],
+            None => {
+                let level = (value >> 3) as u8;
+                Bgr555::new(level, level, level)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pass `noise` over a 16x16 block in row order
+    fn render(mut noise: Noise) -> Vec<Option<Bgr555>> {
+        let black = Bgr555::new(0, 0, 0);
+        (0..16 * 16)
+            .map(|i| noise.pixel(Point::new(i % 16, i / 16), black))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_noise() {
+        for &scale in [1, 4].iter() {
+            assert_eq!(
+                render(Noise::new(1234, scale, None)),
+                render(Noise::new(1234, scale, None))
+            );
+        }
+    }
+
+    #[test]
+    fn another_seed_gives_other_noise() {
+        for seed in 1230..1240 {
+            assert_ne!(
+                render(Noise::new(seed, 1, None)),
+                render(Noise::new(seed + 1, 1, None)),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn scaled_noise_hits_the_lattice_values() {
+        let noise = Noise::new(7, 4, None);
+        assert_eq!(
+            noise.value(Point::new(8, 12)),
+            noise.lattice(Point::new(2, 3))
+        );
+    }
+
+    #[test]
+    fn colors_come_from_the_palette() {
+        const COLORS: [Bgr555; 2] = [Bgr555::RED, Bgr555::BLUE];
+        let pixels = render(Noise::new(5, 1, Some(&COLORS)));
+        assert!(pixels
+            .iter()
+            .all(|pixel| pixel.map_or(false, |color| COLORS.contains(&color))));
+    }
+}
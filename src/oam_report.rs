@@ -0,0 +1,75 @@
+//! Sprite state dump for development
+//!
+//! Logs every visible OAM entry and the pixels of a range of sprite tiles,
+//! so placement bugs can be read off the emulator log.
+
+use core::ops::Range;
+use gba::{
+    debug,
+    oam::{read_obj_attributes, ObjectRender},
+};
+
+use crate::charblock::CharBlock;
+
+/// OAM entries
+const SLOTS: usize = 128;
+
+/// Character block of every sprite tile in bitmap modes
+const CHARACTER_BLOCK: usize = 5;
+
+/// Log the attributes of each visible sprite, then the palette indices of `tiles`
+pub fn report(tiles: Range<usize>) {
+    debug!("slot   x   y tile flags");
+    for slot in 0..SLOTS {
+        let attributes = match read_obj_attributes(slot) {
+            Some(attributes) => attributes,
+            None => continue,
+        };
+        let (attr0, attr1, attr2) = (attributes.attr0, attributes.attr1, attributes.attr2);
+        let render = attr0.obj_rendering();
+        if matches!(render, ObjectRender::Disabled) {
+            continue;
+        }
+        debug!(
+            "{:>4} {:>3} {:>3} {:>4} {:?} {:?} size {} 8bpp {} flip {}{} priority {}",
+            slot,
+            attr1.col_coordinate(),
+            attr0.row_coordinate(),
+            attr2.tile_id(),
+            render,
+            attr0.obj_shape(),
+            attr1.obj_size(),
+            attr0.is_8bpp() as u8,
+            attr1.hflip() as u8,
+            attr1.vflip() as u8,
+            attr2.priority()
+        );
+    }
+
+    let block = match CharBlock::new(CHARACTER_BLOCK) {
+        Ok(block) => block,
+        Err(err) => {
+            debug!("No sprite tiles: {:?}", err);
+            return;
+        }
+    };
+    for index in tiles {
+        let tile = match block.get_tile(index) {
+            Ok(tile) => tile,
+            Err(err) => {
+                debug!("Tile {}: {:?}", index, err);
+                return;
+            }
+        };
+        debug!("Tile {}", index);
+        // each word holds four pixels, the leftmost in the low byte
+        for row in tile.0.chunks(2) {
+            let [a, b, c, d] = row[0].to_le_bytes();
+            let [e, f, g, h] = row[1].to_le_bytes();
+            debug!(
+                "{:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x}",
+                a, b, c, d, e, f, g, h
+            );
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Edge detection filter turning the canvas into an outline drawing
+//!
+//! The pass overwrites pixels as it goes, so it must read the original
+//! image from `canvas`'s snapshot rather than the framebuffer. Reading the
+//! framebuffer happens to work for right and down neighbors, which aren't
+//! written yet, but any kernel looking left or up would compare against
+//! pixels it already turned into outline and trace its own output.
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+};
+
+use crate::{canvas, color, scheduler::Pass};
+
+/// Sum of channel differences above which neighbors count as an edge
+const THRESHOLD: u32 = 6;
+
+const EDGE: Bgr555 = Bgr555::BLACK;
+const PAPER: Bgr555 = Bgr555::WHITE;
+
+/// Marks pixels that differ from their right or lower neighbor, take
+/// `canvas::snapshot_canvas` before submitting
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Outline;
+
+impl Outline {
+    /// Outline color at `point` from the snapshot, `None` without one
+    fn color(point: Point) -> Option<Bgr555> {
+        let source = |dx, dy| {
+            canvas::snapshot_pixel(point.x + dx, point.y + dy)
+                .map(|color| Bgr555::from(RawU16::new(color.0)))
+        };
+        let here = source(0, 0)?;
+        let edge = [source(1, 0), source(0, 1)]
+            .iter()
+            .flatten()
+            .any(|&neighbor| color::difference(here, neighbor) > THRESHOLD);
+        Some(if edge { EDGE } else { PAPER })
+    }
+}
+
+impl Pass for Outline {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        Self::color(point)
+    }
+}
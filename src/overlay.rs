@@ -0,0 +1,195 @@
+//! Save and restore the canvas under UI drawn into the framebuffer
+//!
+//! Mode3 has a single layer, so anything drawn over the canvas has to put
+//! back exactly the pixels it covered when it goes away. Overlays obscure a
+//! rectangle before drawing and reveal it after, instead of each keeping
+//! its own backup.
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*, primitives::Rectangle};
+use gba::vram::bitmap::Mode3;
+
+use crate::{gba_display::Canvas, geom};
+
+/// The rectangle holds more pixels than the cache
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TooLarge(pub usize);
+
+/// Canvas pixels under one rectangle of at most `N` pixels
+pub struct Obscured<const N: usize> {
+    pixels: [Bgr555; N],
+    area: Option<Rectangle>,
+}
+
+impl<const N: usize> Obscured<N> {
+    pub const fn new() -> Self {
+        Self {
+            pixels: [Bgr555::BLACK; N],
+            area: None,
+        }
+    }
+
+    pub fn is_obscured(&self) -> bool {
+        self.area.is_some()
+    }
+
+    /// Save the pixels in `rect`, corners inclusive, revealing any earlier rectangle first
+    ///
+    /// Only the part of `rect` on screen is saved.
+    pub fn obscure(&mut self, display: &mut impl Canvas, rect: Rectangle) -> Result<(), TooLarge> {
+        self.reveal(display);
+        let screen = Rectangle::new(
+            Point::zero(),
+            Point::new(Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1),
+        );
+        let area = Rectangle::new(
+            clamp(&screen, rect.top_left, rect.bottom_right, i32::min),
+            clamp(&screen, rect.top_left, rect.bottom_right, i32::max),
+        );
+        let len = points(&area).count();
+        if len > N {
+            return Err(TooLarge(len));
+        }
+        for (pixel, point) in self.pixels.iter_mut().zip(points(&area)) {
+            *pixel = display.read(point).unwrap_or(Bgr555::BLACK);
+        }
+        self.area = Some(area);
+        Ok(())
+    }
+
+    /// Put back the saved pixels exactly, does nothing if nothing is obscured
+    pub fn reveal(&mut self, display: &mut impl Canvas) {
+        if let Some(area) = self.area.take() {
+            for (&color, point) in self.pixels.iter().zip(points(&area)) {
+                display.write(point, color);
+            }
+        }
+    }
+
+    /// Run `f` on each saved pixel, e.g. so a pass that ran under the overlay
+    /// isn't undone when it's revealed
+    pub fn map(&mut self, mut f: impl FnMut(Point, Bgr555) -> Option<Bgr555>) {
+        if let Some(area) = self.area {
+            for (color, point) in self.pixels.iter_mut().zip(points(&area)) {
+                if let Some(new) = f(point, *color) {
+                    *color = new;
+                }
+            }
+        }
+    }
+}
+
+/// Corner of `a` and `b` picked by `pick` on each axis, moved onto `screen`
+fn clamp(screen: &Rectangle, a: Point, b: Point, pick: fn(i32, i32) -> i32) -> Point {
+    geom::clamp(screen, Point::new(pick(a.x, b.x), pick(a.y, b.y)))
+}
+
+/// Points of `area` in row order
+fn points(area: &Rectangle) -> impl Iterator<Item = Point> {
+    let (top_left, bottom_right) = (area.top_left, area.bottom_right);
+    (top_left.y..=bottom_right.y)
+        .flat_map(move |y| (top_left.x..=bottom_right.x).map(move |x| Point::new(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whole screen held in memory
+    struct Screen(Vec<Bgr555>);
+
+    impl Screen {
+        /// Neighboring pixels all in different colors
+        fn new() -> Self {
+            let pixels = (0..Mode3::WIDTH * Mode3::HEIGHT)
+                .map(|i| Bgr555::new((i % 32) as u8, (i / 32 % 32) as u8, (i / 1024 % 32) as u8));
+            Self(pixels.collect())
+        }
+
+        fn fill(&mut self, color: Bgr555) {
+            self.0.iter_mut().for_each(|pixel| *pixel = color);
+        }
+    }
+
+    impl Canvas for Screen {
+        fn read(&self, point: Point) -> Option<Bgr555> {
+            let inside = (0..Mode3::WIDTH as i32).contains(&point.x)
+                && (0..Mode3::HEIGHT as i32).contains(&point.y);
+            inside.then(|| self.0[point.x as usize + point.y as usize * Mode3::WIDTH])
+        }
+
+        fn write(&mut self, point: Point, color: Bgr555) {
+            self.0[point.x as usize + point.y as usize * Mode3::WIDTH] = color;
+        }
+    }
+
+    fn rect(x0: i32, y0: i32, x1: i32, y1: i32) -> Rectangle {
+        Rectangle::new(Point::new(x0, y0), Point::new(x1, y1))
+    }
+
+    #[test]
+    fn reveal_restores_exactly() {
+        let mut screen = Screen::new();
+        let original = Screen::new().0;
+        let mut under = Obscured::<64>::new();
+        under.obscure(&mut screen, rect(10, 20, 17, 27)).unwrap();
+        assert!(under.is_obscured());
+        screen.fill(Bgr555::WHITE);
+        under.reveal(&mut screen);
+        assert!(!under.is_obscured());
+        for y in 20..=27 {
+            for x in 10..=17 {
+                let i = x + y * Mode3::WIDTH;
+                assert_eq!(screen.0[i], original[i]);
+            }
+        }
+        // nothing outside was touched
+        assert_eq!(screen.0[9 + 20 * Mode3::WIDTH], Bgr555::WHITE);
+    }
+
+    #[test]
+    fn off_screen_part_is_left_out() {
+        let mut screen = Screen::new();
+        let mut under = Obscured::<16>::new();
+        // only the 4x4 corner is on screen
+        assert_eq!(under.obscure(&mut screen, rect(-4, -4, 3, 3)), Ok(()));
+        let mut under = Obscured::<16>::new();
+        let right = Mode3::WIDTH as i32;
+        assert_eq!(
+            under.obscure(&mut screen, rect(right - 4, 0, right + 4, 3)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn larger_than_the_cache_is_refused() {
+        let mut screen = Screen::new();
+        let mut under = Obscured::<63>::new();
+        assert_eq!(
+            under.obscure(&mut screen, rect(0, 0, 7, 7)),
+            Err(TooLarge(64))
+        );
+        assert!(!under.is_obscured());
+    }
+
+    #[test]
+    fn obscuring_again_reveals_the_earlier_rect() {
+        let mut screen = Screen::new();
+        let original = Screen::new().0;
+        let mut under = Obscured::<16>::new();
+        under.obscure(&mut screen, rect(0, 0, 3, 3)).unwrap();
+        screen.write(Point::new(1, 1), Bgr555::WHITE);
+        under.obscure(&mut screen, rect(50, 50, 53, 53)).unwrap();
+        assert_eq!(screen.0[1 + Mode3::WIDTH], original[1 + Mode3::WIDTH]);
+    }
+
+    #[test]
+    fn map_changes_what_reveal_puts_back() {
+        let mut screen = Screen::new();
+        let mut under = Obscured::<16>::new();
+        under.obscure(&mut screen, rect(0, 0, 3, 3)).unwrap();
+        under.map(|point, _| (point.x == 2).then(|| Bgr555::RED));
+        under.reveal(&mut screen);
+        assert_eq!(screen.read(Point::new(2, 3)), Some(Bgr555::RED));
+        assert_ne!(screen.read(Point::new(1, 3)), Some(Bgr555::RED));
+    }
+}
@@ -0,0 +1,1132 @@
+use core::convert::Infallible;
+use embedded_graphics::{
+    drawable::Pixel,
+    geometry::Size,
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+    primitives::{Line, Triangle},
+    style::{PrimitiveStyle, PrimitiveStyleBuilder},
+};
+
+use crate::{
+    background,
+    budget::Budget,
+    clipboard::{self, Clipboard},
+    color::{self, BlendMode},
+    gba_display::{Canvas, GbaDisplay},
+    geom,
+    gradient::Gradient,
+    input::{Input, Key},
+    kaleidoscope::Kaleidoscope,
+    lock::RegionLocks,
+    recent::RecentColors,
+    reticle::ReticleStyle,
+    stamp::Stamp,
+    undo::{PixelChange, UndoStack},
+};
+
+/// Number of painted pixels that can be undone, each costs 6 bytes of stack
+pub const UNDO_DEPTH: usize = 1024;
+
+/// Number of undone pixels that can be redone, the oldest undone are dropped first
+///
+/// As deep as the undo history, so undoing any one step fits.
+pub const REDO_DEPTH: usize = UNDO_DEPTH;
+
+/// Most pixels the move tool picks up
+///
+/// Picking up and dropping a region are one undo step of twice its pixels.
+pub const MAX_CARRIED: usize = UNDO_DEPTH / 2;
+
+/// Seed points the flood fill can queue at once
+const FILL_STACK: usize = 512;
+
+/// Most corners a polygon can have, placing the last one closes it
+const MAX_VERTICES: usize = 8;
+
+/// Placing a vertex this close to the first one closes the polygon
+const CLOSE_DISTANCE: i32 = 2;
+
+/// Shape of the brush opacity ramp
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PressureCurve {
+    Linear,
+    /// Stays light for longer, then rises quickly
+    EaseIn,
+}
+
+/// Simulated brush pressure, the opacity grows the longer A is held
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pressure {
+    pub curve: PressureCurve,
+    /// Frames from the lightest dab to `cap`
+    pub ramp_frames: u16,
+    /// Highest opacity in sixteenths, 16 is the full brush color
+    pub cap: u8,
+}
+
+impl Pressure {
+    /// Opacity in sixteenths after A has been held for `held` frames
+    pub fn opacity(&self, held: u16) -> u8 {
+        let cap = self.cap.min(16) as u32;
+        let ramp = self.ramp_frames.max(1) as u32;
+        let t = (held as u32).min(ramp); // progress out of ramp
+        let scaled = match self.curve {
+            PressureCurve::Linear => cap * t / ramp,
+            PressureCurve::EaseIn => cap * t * t / (ramp * ramp),
+        };
+        (scaled as u8).max(1)
+    }
+}
+
+/// What holding A does with the brush
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BrushHold {
+    /// Dab at the cursor every frame A is held
+    Dab,
+    /// A tap places a single dab, holding the key as well paints a stroke
+    /// along the cursor's path, joining each frame's position to the last
+    Modifier(Key),
+}
+
+/// Painting tools, cycled with Select
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tool {
+    /// Paint while A is held
+    Brush,
+    /// A marks the start, A again draws to the cursor
+    Line,
+    /// A flood fills the area under the cursor
+    Fill,
+    /// A picks the color under the cursor
+    Eyedropper,
+    /// A marks two corners to copy, then A pastes the copy at the cursor
+    Stamp,
+    /// A places each corner, A on the first corner fills the polygon
+    Polygon,
+    /// A marks the start in the current color, A again marks the end in the
+    /// current color and fills the canvas with a gradient between them
+    Gradient,
+    /// A marks two corners and picks the region up, A again drops it at the cursor
+    Move,
+}
+
+impl Tool {
+    pub const ALL: [Self; 8] = [
+        Self::Brush,
+        Self::Line,
+        Self::Fill,
+        Self::Eyedropper,
+        Self::Stamp,
+        Self::Polygon,
+        Self::Gradient,
+        Self::Move,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Brush => Self::Line,
+            Self::Line => Self::Fill,
+            Self::Fill => Self::Eyedropper,
+            Self::Eyedropper => Self::Stamp,
+            Self::Stamp => Self::Polygon,
+            Self::Polygon => Self::Gradient,
+            Self::Gradient => Self::Move,
+            Self::Move => Self::Brush,
+        }
+    }
+
+    /// Default cursor shape for each tool
+    pub fn reticle(self) -> ReticleStyle {
+        match self {
+            Self::Brush => ReticleStyle::Arrow,
+            Self::Line => ReticleStyle::Crosshair,
+            Self::Fill => ReticleStyle::Square,
+            Self::Eyedropper => ReticleStyle::Circle,
+            Self::Stamp => ReticleStyle::Crosshair,
+            Self::Polygon => ReticleStyle::Crosshair,
+            Self::Gradient => ReticleStyle::Crosshair,
+            Self::Move => ReticleStyle::Square,
+        }
+    }
+}
+
+/// Active tool and color plus the undo and redo history
+pub struct PaintState {
+    pub tool: Tool,
+    pub color: Bgr555,
+    /// Fill with a dithered half blend of the old and new color
+    pub dither: bool,
+    /// Brush opacity ramp, `None` always paints the full color
+    pub pressure: Option<Pressure>,
+    /// How brush and line strokes combine with the canvas
+    pub blend: BlendMode,
+    /// Shape of each brush dab
+    pub stamp: Stamp,
+    /// Whether a held A dabs or needs a modifier to paint strokes
+    pub hold: BrushHold,
+    /// Repeats brush and line strokes around a center, `None` paints once
+    pub kaleidoscope: Option<Kaleidoscope>,
+    /// Colors picked with the eyedropper
+    pub recent: RecentColors,
+    /// Regions no tool may paint over
+    pub locks: RegionLocks,
+    anchor: Option<Point>,
+    fill: Option<FloodFill>,
+    clipboard: Clipboard,
+    polygon: Polygon,
+    /// Start of a gradient and its color
+    gradient_start: Option<(Point, Bgr555)>,
+    /// Inverted line from the gradient start to the cursor
+    axis_preview: Option<(Point, Point)>,
+    gradient: Option<Gradient>,
+    /// Region picked up by the move tool, held in the clipboard
+    carried: Option<Carried>,
+    /// Last brush dab of a stroke, joined to the next one
+    last_dab: Option<Point>,
+    /// Set by a step too big for the undo history, see `take_split`
+    split: bool,
+    /// Set by a move selection over `MAX_CARRIED`, see `take_too_big`
+    too_big: bool,
+    undo: UndoStack<PixelChange, UNDO_DEPTH>,
+    /// Undone steps, cleared by any new edit
+    redo: UndoStack<PixelChange, REDO_DEPTH>,
+}
+
+impl PaintState {
+    pub fn new(color: Bgr555) -> Self {
+        Self {
+            tool: Tool::Brush,
+            color,
+            dither: false,
+            pressure: None,
+            blend: BlendMode::Normal,
+            stamp: Stamp::ALL[0],
+            hold: BrushHold::Dab,
+            kaleidoscope: None,
+            recent: RecentColors::new(),
+            locks: RegionLocks::new(),
+            anchor: None,
+            fill: None,
+            clipboard: Clipboard::new(),
+            polygon: Polygon::new(),
+            gradient_start: None,
+            axis_preview: None,
+            gradient: None,
+            carried: None,
+            last_dab: None,
+            split: false,
+            too_big: false,
+            undo: UndoStack::new(),
+            redo: UndoStack::new(),
+        }
+    }
+
+    /// Switch to the next tool
+    pub fn cycle_tool(&mut self, display: &mut GbaDisplay) {
+        self.set_tool(self.tool.next(), display);
+    }
+
+    /// Switch tools, dropping any half placed shape or copied region
+    pub fn set_tool(&mut self, tool: Tool, display: &mut GbaDisplay) {
+        self.tool = tool;
+        self.anchor = None;
+        self.drop_carried(display);
+        self.clipboard.clear();
+        self.polygon.cancel(display);
+        self.gradient_start = None;
+        erase_axis(&mut self.axis_preview, display);
+    }
+
+    pub fn undo_stack(&self) -> &UndoStack<PixelChange, UNDO_DEPTH> {
+        &self.undo
+    }
+
+    pub fn redo_stack(&self) -> &UndoStack<PixelChange, REDO_DEPTH> {
+        &self.redo
+    }
+
+    /// Forget the undo and redo history, e.g. after clearing the canvas
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.anchor = None;
+        self.fill = None;
+        self.polygon.forget();
+        self.gradient_start = None;
+        self.axis_preview = None;
+        self.carried = None;
+    }
+
+    /// Check if a step outgrew the undo history since the last call
+    ///
+    /// The rest of it was recorded as a new step, so undo reverts it in
+    /// parts and the oldest part may already be forgotten.
+    pub fn take_split(&mut self) -> bool {
+        core::mem::replace(&mut self.split, false)
+    }
+
+    /// Check if the move tool refused a selection since the last call
+    pub fn take_too_big(&mut self) -> bool {
+        core::mem::replace(&mut self.too_big, false)
+    }
+
+    /// A finished gradient to run as a canvas pass
+    pub fn take_gradient(&mut self) -> Option<Gradient> {
+        self.gradient.take()
+    }
+
+    /// Check if a flood fill is still in progress
+    pub fn is_busy(&self) -> bool {
+        self.fill.is_some()
+    }
+
+    /// Continue an in progress flood fill within `budget`, returns true if the canvas changed
+    pub fn resume(&mut self, display: &mut impl Canvas, budget: &mut Budget) -> bool {
+        let fill = match self.fill.as_mut() {
+            Some(fill) => fill,
+            None => return false,
+        };
+        let mut recorder = Recorder::new(display, &mut self.undo, &self.locks);
+        // the whole fill is one step, however many frames it takes
+        if fill.started {
+            recorder.continue_stroke();
+            recorder.step_len = fill.step_len;
+        } else {
+            recorder.begin_stroke();
+        }
+        let done = fill.step(&mut recorder, budget);
+        fill.started = recorder.started;
+        fill.step_len = recorder.step_len;
+        self.split |= recorder.split;
+        recorder.end_stroke();
+        if done {
+            self.fill = None;
+        }
+        let changed = recorder.changed;
+        if changed {
+            self.mark_edited();
+        }
+        changed
+    }
+
+    /// Revert the most recent undo step, returns false if there is nothing to undo
+    /// or a fill is in progress
+    ///
+    /// A step never holds more than the undo depth, see `take_split`. A fill
+    /// appends to the newest step each frame, so undoing under it would
+    /// merge the rest of the fill into an older step.
+    pub fn undo(&mut self, display: &mut GbaDisplay) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        self.cancel_previews(display);
+        step_back(&mut self.undo, &mut self.redo, display)
+    }
+
+    /// Repaint the most recently undone step, returns false if there is nothing to redo
+    /// or a fill is in progress
+    pub fn redo(&mut self, display: &mut GbaDisplay) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        self.cancel_previews(display);
+        step_back(&mut self.redo, &mut self.undo, display)
+    }
+
+    /// A new edit makes the undone steps impossible to redo
+    fn mark_edited(&mut self) {
+        self.redo.clear();
+    }
+
+    /// Previews must not cover undone or redone pixels
+    fn cancel_previews(&mut self, display: &mut GbaDisplay) {
+        self.polygon.cancel(display);
+        self.gradient_start = None;
+        erase_axis(&mut self.axis_preview, display);
+        self.drop_carried(display);
+    }
+
+    /// Apply the active tool at `point`, returns true if the canvas changed
+    pub fn apply(&mut self, input: &Input, point: Point, display: &mut GbaDisplay) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        let mut recorder = Recorder::new(display, &mut self.undo, &self.locks);
+        // each action is one undo step: a dab, a line, a paste or a polygon
+        recorder.begin_stroke();
+        match self.tool {
+            Tool::Brush if input.is_held(Key::A) => {
+                recorder.kaleidoscope = self.kaleidoscope;
+                let previous = self.last_dab.replace(point);
+                let line = match self.hold {
+                    BrushHold::Dab => Some((point, 0)),
+                    BrushHold::Modifier(_) if input.just_pressed(Key::A) => Some((point, 0)),
+                    // from the last frame's position, which is already painted
+                    BrushHold::Modifier(key) if input.is_held(key) => {
+                        previous.map(|last| (last, 1))
+                    }
+                    BrushHold::Modifier(_) => None,
+                };
+                // skipping the only point of a one point line paints nothing
+                let (from, skip) = line.unwrap_or((point, 1));
+                for center in geom::line_points(from, point).skip(skip) {
+                    for point in self.stamp.points(center) {
+                        if let Some(canvas) = recorder.get_pixel(point) {
+                            let color = self.blend.apply(canvas, self.color);
+                            let color = match self.pressure {
+                                Some(pressure) => {
+                                    let opacity = pressure.opacity(input.held_frames(Key::A));
+                                    color::blend(canvas, color, opacity)
+                                }
+                                None => color,
+                            };
+                            Pixel(point, color).draw(&mut recorder).ok();
+                        }
+                    }
+                }
+            }
+            Tool::Line if input.just_pressed(Key::A) => match self.anchor.take() {
+                Some(start) => {
+                    recorder.blend = self.blend;
+                    recorder.kaleidoscope = self.kaleidoscope;
+                    Line::new(start, point)
+                        .into_styled(PrimitiveStyle::with_stroke(self.color, 1))
+                        .draw(&mut recorder)
+                        .ok();
+                }
+                None => self.anchor = Some(point),
+            },
+            Tool::Fill if input.just_pressed(Key::A) => {
+                self.fill = FloodFill::new(recorder.display, point, self.color, self.dither);
+            }
+            Tool::Eyedropper if input.just_pressed(Key::A) => {
+                if let Some(color) = recorder.display.get_pixel(point) {
+                    self.color = color;
+                    self.recent.push(color);
+                }
+            }
+            Tool::Stamp if input.just_pressed(Key::A) => {
+                if !self.clipboard.is_empty() {
+                    recorder.draw_iter(self.clipboard.pixels(point)).ok();
+                } else if let Some(corner) = self.anchor.take() {
+                    self.clipboard.copy(recorder.display, corner, point);
+                } else {
+                    self.anchor = Some(point);
+                }
+            }
+            Tool::Polygon if input.just_pressed(Key::A) => {
+                if self.polygon.closes(point) {
+                    self.polygon.fill(&mut recorder, self.color).ok();
+                } else {
+                    self.polygon.push(recorder.display, point);
+                    if self.polygon.len == MAX_VERTICES {
+                        self.polygon.fill(&mut recorder, self.color).ok();
+                    }
+                }
+            }
+            Tool::Move => match self.carried.as_mut() {
+                Some(carried) if input.just_pressed(Key::A) => {
+                    carried.erase_preview(recorder.display, self.clipboard.size());
+                    self.carried = None;
+                    // undone together with the pick up
+                    recorder.continue_stroke();
+                    recorder.draw_iter(self.clipboard.pixels(point)).ok();
+                    self.clipboard.clear();
+                }
+                Some(carried) => carried.show_preview(recorder.display, &self.clipboard, point),
+                None if input.just_pressed(Key::A) => match self.anchor.take() {
+                    Some(corner) => {
+                        let size = clipboard::copy_size(corner, point);
+                        if (size.width * size.height) as usize > MAX_CARRIED {
+                            self.too_big = true;
+                        } else {
+                            let origin = Point::new(corner.x.min(point.x), corner.y.min(point.y));
+                            self.clipboard.copy(recorder.display, corner, point);
+                            recorder.draw_iter(background_pixels(origin, size)).ok();
+                            self.carried = Some(Carried::new(origin));
+                        }
+                    }
+                    None => self.anchor = Some(point),
+                },
+                None => {}
+            },
+            Tool::Gradient => match self.gradient_start {
+                Some((start, from)) if input.just_pressed(Key::A) => {
+                    erase_axis(&mut self.axis_preview, recorder.display);
+                    self.gradient_start = None;
+                    self.gradient =
+                        Some(Gradient::new(start, point, from, self.color, self.dither));
+                }
+                Some((start, _)) => {
+                    if self.axis_preview != Some((start, point)) {
+                        erase_axis(&mut self.axis_preview, recorder.display);
+                        invert_line(recorder.display, start, point, 0);
+                        self.axis_preview = Some((start, point));
+                    }
+                }
+                None if input.just_pressed(Key::A) => {
+                    self.gradient_start = Some((point, self.color));
+                }
+                None => {}
+            },
+            _ => {}
+        }
+        recorder.end_stroke();
+        self.split |= recorder.split;
+        let changed = recorder.changed;
+        if changed {
+            self.mark_edited();
+        }
+        changed
+    }
+
+    /// Put a picked up region back where it came from, finishing its undo step
+    fn drop_carried(&mut self, display: &mut GbaDisplay) {
+        if let Some(mut carried) = self.carried.take() {
+            carried.erase_preview(display, self.clipboard.size());
+            let mut recorder = Recorder::new(display, &mut self.undo, &self.locks);
+            recorder.continue_stroke();
+            recorder
+                .draw_iter(self.clipboard.pixels(carried.origin))
+                .ok();
+            recorder.end_stroke();
+            self.clipboard.clear();
+        }
+    }
+}
+
+/// Revert the newest step in `from`, recording the colors it covered in `to`
+///
+/// Changes pop newest first, so the first one pushed onto `to` starts the
+/// step there and popping `to` replays the step in its original order.
+fn step_back<const N: usize, const M: usize>(
+    from: &mut UndoStack<PixelChange, N>,
+    to: &mut UndoStack<PixelChange, M>,
+    canvas: &mut impl Canvas,
+) -> bool {
+    // a step whose start was pushed out would only be partly reverted,
+    // leaving a canvas that never existed
+    if !from.iter_newest().any(|change| change.first) {
+        from.clear();
+        return false;
+    }
+    let mut reverted = false;
+    while let Some(change) = from.pop() {
+        if let Some(color) = canvas.read(change.point()) {
+            to.push(PixelChange {
+                color,
+                first: !reverted,
+                ..change
+            });
+        }
+        canvas.write(change.point(), change.color);
+        reverted = true;
+        if change.first {
+            break;
+        }
+    }
+    reverted
+}
+
+/// Remove the gradient axis preview from the canvas
+///
+/// Takes the field rather than `self` so it can run while a `Recorder`
+/// borrows the undo stack.
+fn erase_axis(axis_preview: &mut Option<(Point, Point)>, display: &mut GbaDisplay) {
+    if let Some((start, end)) = axis_preview.take() {
+        invert_line(display, start, end, 0);
+    }
+}
+
+/// Corners of a polygon being placed, with the edges previewed on the canvas
+///
+/// Preview pixels are inverted instead of backed up, inverting the same
+/// edges again restores the canvas exactly.
+struct Polygon {
+    vertices: [Point; MAX_VERTICES],
+    len: usize,
+}
+
+impl Polygon {
+    const fn new() -> Self {
+        Self {
+            vertices: [Point::zero(); MAX_VERTICES],
+            len: 0,
+        }
+    }
+
+    /// Check if placing a corner at `point` should close the polygon
+    fn closes(&self, point: Point) -> bool {
+        let first = self.vertices[0];
+        self.len >= 3
+            && (point.x - first.x).abs() <= CLOSE_DISTANCE
+            && (point.y - first.y).abs() <= CLOSE_DISTANCE
+    }
+
+    /// Add a corner and preview the edge to it
+    fn push(&mut self, display: &mut GbaDisplay, point: Point) {
+        if self.len > 0 && self.vertices[self.len - 1] == point {
+            return; // no zero length edges
+        }
+        self.vertices[self.len] = point;
+        self.len += 1;
+        self.invert_edge(display, self.len - 1);
+    }
+
+    /// Erase the preview and fill the polygon as triangles fanned from the first corner
+    fn fill(&mut self, target: &mut Recorder, color: Bgr555) -> Result<(), Infallible> {
+        self.cancel(target.display);
+        let style = PrimitiveStyleBuilder::new()
+            .fill_color(color)
+            .stroke_color(color) // covers the seams between triangles
+            .stroke_width(1)
+            .build();
+        let first = self.vertices[0];
+        for pair in self.vertices[..self.len].windows(2).skip(1) {
+            Triangle::new(first, pair[0], pair[1])
+                .into_styled(style)
+                .draw(target)?;
+        }
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Erase the preview and drop the corners
+    fn cancel(&mut self, display: &mut GbaDisplay) {
+        for i in 0..self.len {
+            self.invert_edge(display, i);
+        }
+        self.len = 0;
+    }
+
+    /// Drop the corners without touching the canvas, e.g. after it was redrawn
+    fn forget(&mut self) {
+        self.len = 0;
+    }
+
+    /// Invert the pixels of the edge ending at corner `i`
+    ///
+    /// The start pixel belongs to the previous edge, so shared corners
+    /// aren't inverted twice. The first corner is a single pixel.
+    fn invert_edge(&self, display: &mut GbaDisplay, i: usize) {
+        let end = self.vertices[i];
+        match i {
+            0 => invert_line(display, end, end, 0),
+            _ => invert_line(display, self.vertices[i - 1], end, 1),
+        }
+    }
+}
+
+/// Region lifted off the canvas by the move tool
+#[derive(Debug, Copy, Clone)]
+struct Carried {
+    /// Where the region was picked up from
+    origin: Point,
+    /// Top left of the region previewing where it will drop
+    preview: Option<Point>,
+    /// Canvas pixels under the preview, row by row
+    under: [Bgr555; MAX_CARRIED],
+}
+
+impl Carried {
+    fn new(origin: Point) -> Self {
+        Self {
+            origin,
+            preview: None,
+            under: [Bgr555::BLACK; MAX_CARRIED],
+        }
+    }
+
+    /// Draw the carried pixels at `top_left` without recording them
+    fn show_preview(&mut self, display: &mut GbaDisplay, clipboard: &Clipboard, top_left: Point) {
+        if self.preview != Some(top_left) {
+            self.erase_preview(display, clipboard.size());
+            for (under, Pixel(point, color)) in
+                self.under.iter_mut().zip(clipboard.pixels(top_left))
+            {
+                if let Some(pixel) = display.get_pixel(point) {
+                    *under = pixel;
+                    Pixel(point, color).draw(display).ok();
+                }
+            }
+            self.preview = Some(top_left);
+        }
+    }
+
+    /// Put back the canvas pixels under the preview
+    fn erase_preview(&mut self, display: &mut GbaDisplay, size: Size) {
+        if let Some(top_left) = self.preview.take() {
+            let width = size.width as i32;
+            let count = (size.width * size.height) as usize;
+            for (i, &color) in self.under[..count].iter().enumerate() {
+                let point = top_left + Point::new(i as i32 % width, i as i32 / width);
+                if display.get_pixel(point).is_some() {
+                    Pixel(point, color).draw(display).ok();
+                }
+            }
+        }
+    }
+}
+
+/// Background image pixels of the region at `origin`
+fn background_pixels(origin: Point, size: Size) -> impl Iterator<Item = Pixel<Bgr555>> {
+    let width = size.width as i32;
+    (0..(size.width * size.height) as i32).map(move |i| {
+        let point = origin + Point::new(i % width, i / width);
+        let raw = background::pixel(point.x as usize, point.y as usize);
+        Pixel(point, RawU16::new(raw).into())
+    })
+}
+
+/// Invert the pixels of a line after the first `skip`, inverting again restores them
+fn invert_line(display: &mut GbaDisplay, start: Point, end: Point, skip: usize) {
+    let line = Line::new(start, end).into_styled(PrimitiveStyle::with_stroke(Bgr555::WHITE, 1));
+    invert_pixels(
+        display,
+        line.into_iter().skip(skip).map(|Pixel(point, _)| point),
+    );
+}
+
+fn invert_pixels(display: &mut GbaDisplay, points: impl Iterator<Item = Point>) {
+    for point in points {
+        if let Some(pixel) = display.get_pixel(point) {
+            Pixel(point, color::invert(pixel)).draw(display).ok();
+        }
+    }
+}
+
+/// Draw target that records the previous color of each painted pixel for undo
+///
+/// Each pixel is its own undo step outside a stroke, see `begin_stroke`.
+/// Pixels in locked regions are skipped.
+pub struct Recorder<'a, D = GbaDisplay> {
+    display: &'a mut D,
+    undo: &'a mut UndoStack<PixelChange, UNDO_DEPTH>,
+    locks: &'a RegionLocks,
+    changed: bool,
+    /// Inside a stroke
+    grouped: bool,
+    /// The stroke has recorded its first pixel
+    started: bool,
+    /// Applied to each pixel against the canvas before it's recorded
+    blend: BlendMode,
+    /// Also draws each pixel's turned copies
+    kaleidoscope: Option<Kaleidoscope>,
+    /// Changes recorded in the current step
+    step_len: usize,
+    /// A step outgrew the undo history and went on as a new step
+    split: bool,
+}
+
+impl<'a, D: Canvas> Recorder<'a, D> {
+    pub fn new(
+        display: &'a mut D,
+        undo: &'a mut UndoStack<PixelChange, UNDO_DEPTH>,
+        locks: &'a RegionLocks,
+    ) -> Self {
+        Self {
+            display,
+            undo,
+            locks,
+            changed: false,
+            grouped: false,
+            started: false,
+            blend: BlendMode::Normal,
+            kaleidoscope: None,
+            step_len: 0,
+            split: false,
+        }
+    }
+
+    /// Record the following pixels as one undo step, until `end_stroke`
+    pub fn begin_stroke(&mut self) {
+        self.grouped = true;
+        self.started = false;
+    }
+
+    /// Like `begin_stroke`, but append to the step recorded last
+    pub fn continue_stroke(&mut self) {
+        self.grouped = true;
+        self.started = true;
+    }
+
+    /// Record each following pixel as its own undo step again
+    pub fn end_stroke(&mut self) {
+        self.grouped = false;
+    }
+
+    pub fn get_pixel(&self, point: Point) -> Option<Bgr555> {
+        self.display.read(point)
+    }
+
+    /// Draw a blended `pixel`, skipping pixels that are off screen, locked
+    /// or already the result
+    fn draw_one(&mut self, pixel: Pixel<Bgr555>) -> Result<(), Infallible> {
+        let Pixel(point, color) = pixel;
+        if self.locks.contains(point) {
+            return Ok(());
+        }
+        match self.display.read(point) {
+            Some(previous) if previous != self.blend.apply(previous, color) => {
+                let continuing = self.grouped && self.started;
+                let first = starts_step(continuing, self.step_len);
+                self.split |= continuing && first;
+                self.step_len = if first { 1 } else { self.step_len + 1 };
+                self.undo.push(PixelChange {
+                    x: point.x as u8,
+                    y: point.y as u8,
+                    color: previous,
+                    first,
+                });
+                self.started = true;
+                self.changed = true;
+                self.display.write(point, self.blend.apply(previous, color));
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Check if the next change starts a new undo step
+///
+/// A step holding the whole undo history would push out its own start and
+/// undoing it would run on into nothing, so it's split there instead.
+fn starts_step(continuing: bool, step_len: usize) -> bool {
+    !continuing || step_len >= UNDO_DEPTH
+}
+
+impl<'a> DrawTarget<Bgr555> for Recorder<'a> {
+    type Error = Infallible;
+
+    /// Draw `pixel` and its kaleidoscope copies, see `draw_one`
+    fn draw_pixel(&mut self, pixel: Pixel<Bgr555>) -> Result<(), Self::Error> {
+        match self.kaleidoscope {
+            Some(kaleidoscope) => {
+                for point in kaleidoscope.copies(pixel.0) {
+                    self.draw_one(Pixel(point, pixel.1))?;
+                }
+                Ok(())
+            }
+            None => self.draw_one(pixel),
+        }
+    }
+
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+/// Scanline flood fill of the area matching the color at the seed point
+///
+/// Seeds that don't fit in the fixed stack are dropped, which can leave
+/// very convoluted areas partially filled.
+struct FloodFill {
+    old: Bgr555,
+    color: Bgr555,
+    dither: bool,
+    stack: [(u8, u8); FILL_STACK],
+    len: usize,
+    /// Some span is filled, later frames continue its undo step
+    started: bool,
+    /// Changes in that step so far
+    step_len: usize,
+}
+
+impl FloodFill {
+    /// Returns `None` if the area at `seed` is already `color`
+    fn new(canvas: &impl Canvas, seed: Point, color: Bgr555, dither: bool) -> Option<Self> {
+        let old = canvas.read(seed).filter(|&old| old != color)?;
+        let mut stack = [(0, 0); FILL_STACK];
+        stack[0] = (seed.x as u8, seed.y as u8);
+        Some(Self {
+            old,
+            color,
+            dither,
+            stack,
+            len: 1,
+            started: false,
+            step_len: 0,
+        })
+    }
+
+    fn matches(&self, target: &Recorder<impl Canvas>, x: i32, y: i32) -> bool {
+        target.get_pixel(Point::new(x, y)) == Some(self.old)
+    }
+
+    /// Fill color at `point`, never the old color or the pixel would be filled again
+    fn shade(&self, point: Point) -> Bgr555 {
+        if !self.dither {
+            return self.color;
+        }
+        match color::blend_dithered(self.old, self.color, 8, point) {
+            shade if shade == self.old => self.color,
+            shade => shade,
+        }
+    }
+
+    /// Fill spans until `budget` is exhausted, returns true when the fill is complete
+    fn step(&mut self, target: &mut Recorder<impl Canvas>, budget: &mut Budget) -> bool {
+        while self.len > 0 && !budget.exhausted() {
+            self.len -= 1;
+            let (x, y) = (self.stack[self.len].0 as i32, self.stack[self.len].1 as i32);
+            if !self.matches(target, x, y) {
+                continue;
+            }
+
+            // find the extent of this span
+            let mut left = x;
+            while self.matches(target, left - 1, y) {
+                left -= 1;
+            }
+            let mut right = x;
+            while self.matches(target, right + 1, y) {
+                right += 1;
+            }
+
+            // queue the start of each matching span above and below
+            for &row in [y - 1, y + 1].iter() {
+                let mut in_span = false;
+                for column in left..=right {
+                    let matching = self.matches(target, column, row);
+                    if matching && !in_span && self.len < FILL_STACK {
+                        self.stack[self.len] = (column as u8, row as u8);
+                        self.len += 1;
+                    }
+                    in_span = matching;
+                }
+            }
+
+            for column in left..=right {
+                let point = Point::new(column, y);
+                target.draw_one(Pixel(point, self.shade(point))).ok();
+            }
+            budget.spend((right - left + 1) as usize * 3); // span is read three times
+        }
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::BudgetLimit;
+
+    /// Canvas of one row
+    struct Row([Bgr555; 16]);
+
+    impl Canvas for Row {
+        fn read(&self, point: Point) -> Option<Bgr555> {
+            self.0.get(point.x as usize).copied()
+        }
+
+        fn write(&mut self, point: Point, color: Bgr555) {
+            self.0[point.x as usize] = color;
+        }
+    }
+
+    /// Paint `x` with `color` as Recorder would, `first` starts the step
+    fn paint(
+        row: &mut Row,
+        undo: &mut UndoStack<PixelChange, UNDO_DEPTH>,
+        x: u8,
+        color: Bgr555,
+        first: bool,
+    ) {
+        undo.push(PixelChange {
+            x,
+            y: 0,
+            color: row.0[x as usize],
+            first,
+        });
+        row.0[x as usize] = color;
+    }
+
+    #[test]
+    fn undoing_a_line_restores_all_its_pixels_at_once() {
+        let mut row = Row([Bgr555::WHITE; 16]);
+        let mut undo = UndoStack::new();
+        let mut redo: UndoStack<PixelChange, REDO_DEPTH> = UndoStack::new();
+        paint(&mut row, &mut undo, 0, Bgr555::BLUE, true);
+        for x in 2..10 {
+            paint(&mut row, &mut undo, x, Bgr555::RED, x == 2);
+        }
+        assert!(step_back(&mut undo, &mut redo, &mut row));
+        assert!(row.0[2..10].iter().all(|&color| color == Bgr555::WHITE));
+        // the dab before the line is its own step
+        assert_eq!(row.0[0], Bgr555::BLUE);
+        assert_eq!(undo.len(), 1);
+    }
+
+    #[test]
+    fn undo_redo_and_an_edit_clearing_redo() {
+        let mut state = PaintState::new(Bgr555::BLACK);
+        let mut row = Row([Bgr555::WHITE; 16]);
+        for x in 0..4 {
+            paint(&mut row, &mut state.undo, x, Bgr555::RED, x == 0);
+        }
+        let painted = row.0;
+
+        assert!(step_back(&mut state.undo, &mut state.redo, &mut row));
+        assert_eq!(row.0, [Bgr555::WHITE; 16]);
+        assert!(step_back(&mut state.redo, &mut state.undo, &mut row));
+        assert_eq!(row.0, painted);
+
+        assert!(step_back(&mut state.undo, &mut state.redo, &mut row));
+        state.mark_edited();
+        assert!(!step_back(&mut state.redo, &mut state.undo, &mut row));
+        assert_eq!(row.0, [Bgr555::WHITE; 16]);
+    }
+
+    #[test]
+    fn a_step_that_lost_its_start_is_not_reverted() {
+        let mut row = Row([Bgr555::WHITE; 16]);
+        let mut undo = UndoStack::new();
+        let mut redo: UndoStack<PixelChange, REDO_DEPTH> = UndoStack::new();
+        paint(&mut row, &mut undo, 3, Bgr555::RED, false);
+        assert!(!step_back(&mut undo, &mut redo, &mut row));
+        assert_eq!(row.0[3], Bgr555::RED);
+        assert!(undo.is_empty());
+    }
+
+    #[test]
+    fn steps_split_before_holding_the_whole_history() {
+        assert!(starts_step(false, 0));
+        assert!(!starts_step(true, 1));
+        assert!(!starts_step(true, UNDO_DEPTH - 1));
+        assert!(starts_step(true, UNDO_DEPTH));
+    }
+
+    #[test]
+    fn locked_pixels_are_left_alone() {
+        let mut row = Row([Bgr555::WHITE; 16]);
+        let mut undo = UndoStack::new();
+        let mut locks = RegionLocks::new();
+        locks.lock(Point::new(7, 0), Point::new(4, 0)).unwrap();
+        let mut recorder = Recorder::new(&mut row, &mut undo, &locks);
+        for x in 0..16 {
+            recorder.draw_one(Pixel(Point::new(x, 0), Bgr555::RED)).ok();
+        }
+        for (x, &color) in row.0.iter().enumerate() {
+            let locked = (4..=7).contains(&x);
+            assert_eq!(color == Bgr555::WHITE, locked, "x {}", x);
+        }
+        assert_eq!(undo.len(), 12);
+    }
+
+    /// Canvas of 8x8 pixels
+    struct Grid([[Bgr555; 8]; 8]);
+
+    impl Canvas for Grid {
+        fn read(&self, point: Point) -> Option<Bgr555> {
+            if (0..8).contains(&point.x) && (0..8).contains(&point.y) {
+                Some(self.0[point.y as usize][point.x as usize])
+            } else {
+                None
+            }
+        }
+
+        fn write(&mut self, point: Point, color: Bgr555) {
+            self.0[point.y as usize][point.x as usize] = color;
+        }
+    }
+
+    #[test]
+    fn undo_waits_for_a_fill_to_finish() {
+        let mut state = PaintState::new(Bgr555::RED);
+        let mut grid = Grid([[Bgr555::WHITE; 8]; 8]);
+        state.fill = FloodFill::new(&grid, Point::zero(), Bgr555::RED, false);
+        // each span of 8 pixels is read three times
+        let frame = || Budget::start(BudgetLimit::Pixels(24));
+
+        assert!(state.resume(&mut grid, &mut frame()));
+        assert!(state.is_busy());
+        assert_eq!(grid.0[0], [Bgr555::RED; 8]);
+        // refused before it touches the display
+        assert!(!state.undo(&mut GbaDisplay));
+        assert!(!state.redo(&mut GbaDisplay));
+        assert_eq!(grid.0[0], [Bgr555::RED; 8]);
+        assert_eq!(state.undo.len(), 8);
+
+        let mut frames = 1;
+        while state.is_busy() {
+            state.resume(&mut grid, &mut frame());
+            frames += 1;
+        }
+        assert_eq!(frames, 8);
+        assert_eq!(grid.0, [[Bgr555::RED; 8]; 8]);
+        // the fill is still a single step
+        assert!(step_back(&mut state.undo, &mut state.redo, &mut grid));
+        assert_eq!(grid.0, [[Bgr555::WHITE; 8]; 8]);
+        assert!(state.undo.is_empty());
+    }
+
+    const LINEAR: Pressure = Pressure {
+        curve: PressureCurve::Linear,
+        ramp_frames: 32,
+        cap: 16,
+    };
+
+    const EASE_IN: Pressure = Pressure {
+        curve: PressureCurve::EaseIn,
+        ..LINEAR
+    };
+
+    #[test]
+    fn linear_pressure_ramps_evenly_to_the_cap() {
+        assert_eq!(LINEAR.opacity(0), 1);
+        assert_eq!(LINEAR.opacity(1), 1);
+        assert_eq!(LINEAR.opacity(8), 4);
+        assert_eq!(LINEAR.opacity(16), 8);
+        assert_eq!(LINEAR.opacity(32), 16);
+        assert_eq!(LINEAR.opacity(1000), 16);
+    }
+
+    #[test]
+    fn ease_in_pressure_stays_light_then_catches_up() {
+        assert_eq!(EASE_IN.opacity(0), 1);
+        assert_eq!(EASE_IN.opacity(1), 1);
+        assert_eq!(EASE_IN.opacity(8), 1);
+        assert_eq!(EASE_IN.opacity(16), 4);
+        assert_eq!(EASE_IN.opacity(24), 9);
+        assert_eq!(EASE_IN.opacity(32), 16);
+        assert_eq!(EASE_IN.opacity(1000), 16);
+        for held in 1..32 {
+            assert!(
+                EASE_IN.opacity(held) <= LINEAR.opacity(held),
+                "held {}",
+                held
+            );
+        }
+    }
+
+    #[test]
+    fn pressure_never_paints_nothing() {
+        let faint = Pressure { cap: 0, ..LINEAR };
+        assert!((0..40).all(|held| faint.opacity(held) == 1));
+    }
+
+    #[test]
+    fn pressure_cap_is_at_most_the_full_color() {
+        let over = Pressure { cap: 40, ..LINEAR };
+        assert_eq!(over.opacity(16), 8);
+        assert_eq!(over.opacity(32), 16);
+        let partial = Pressure { cap: 12, ..EASE_IN };
+        assert_eq!(partial.opacity(1000), 12);
+    }
+
+    #[test]
+    fn no_ramp_jumps_to_the_cap() {
+        for &curve in [PressureCurve::Linear, PressureCurve::EaseIn].iter() {
+            let instant = Pressure {
+                curve,
+                ramp_frames: 0,
+                cap: 10,
+            };
+            assert_eq!(instant.opacity(0), 1);
+            assert_eq!(instant.opacity(1), 10);
+            assert_eq!(instant.opacity(5), 10);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Animate sprites by rotating colors through object palette slots
+//!
+//! Every few frames each slot in the range takes the color of the slot
+//! before it and the first takes the last one's, so anything drawn with
+//! those slots seems to flow like water or flicker like fire without a
+//! pixel being redrawn. The Mode3 canvas stores colors directly, so only
+//! sprites animate.
+
+use core::ops::Range;
+use gba::{palram::index_palram_obj_8bpp, sync::Static, Color};
+
+/// Slots rotated and the frames each step is held
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Cycle {
+    first: u8,
+    len: u8,
+    period: u8,
+    /// Frames until the next step
+    wait: u8,
+    /// Steps taken, modulo `len`
+    offset: u8,
+}
+
+static CYCLE: Static<Option<Cycle>> = Static::new(None);
+
+/// Rotate the colors of object palette slots `range` one step every
+/// `speed` frames, stopping any earlier cycle
+pub fn start_palette_cycle(range: Range<u8>, speed: u8) {
+    stop_palette_cycle();
+    let len = range.end.saturating_sub(range.start);
+    if len < 2 {
+        return;
+    }
+    let period = speed.max(1);
+    CYCLE.write(Some(Cycle {
+        first: range.start,
+        len,
+        period,
+        wait: period,
+        offset: 0,
+    }));
+}
+
+/// Stop cycling and put every color back in its own slot
+pub fn stop_palette_cycle() {
+    if let Some(cycle) = CYCLE.read() {
+        let mut colors = read(&cycle);
+        let back = (cycle.len - cycle.offset) % cycle.len;
+        for _ in 0..back {
+            rotate(&mut colors[..cycle.len as usize]);
+        }
+        write(&cycle, &colors);
+    }
+    CYCLE.write(None);
+}
+
+/// Vblank callback, takes a step when it's due
+pub fn tick() {
+    let mut cycle = match CYCLE.read() {
+        Some(cycle) => cycle,
+        None => return,
+    };
+    cycle.wait -= 1;
+    if cycle.wait == 0 {
+        cycle.wait = cycle.period;
+        cycle.offset = (cycle.offset + 1) % cycle.len;
+        let mut colors = read(&cycle);
+        rotate(&mut colors[..cycle.len as usize]);
+        write(&cycle, &colors);
+    }
+    CYCLE.write(Some(cycle));
+}
+
+/// Move every color one place later, the last wraps around to the front
+pub fn rotate(colors: &mut [Color]) {
+    if let Some(&last) = colors.last() {
+        colors.copy_within(..colors.len() - 1, 1);
+        colors[0] = last;
+    }
+}
+
+fn read(cycle: &Cycle) -> [Color; 256] {
+    let mut colors = [Color(0); 256];
+    for i in 0..cycle.len {
+        colors[i as usize] = index_palram_obj_8bpp(cycle.first + i).read();
+    }
+    colors
+}
+
+fn write(cycle: &Cycle, colors: &[Color; 256]) {
+    for i in 0..cycle.len {
+        index_palram_obj_8bpp(cycle.first + i).write(colors[i as usize]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors(values: &[u16]) -> Vec<Color> {
+        values.iter().map(|&value| Color(value)).collect()
+    }
+
+    fn values(colors: &[Color]) -> Vec<u16> {
+        colors.iter().map(|color| color.0).collect()
+    }
+
+    #[test]
+    fn each_step_moves_colors_one_slot_later() {
+        let mut range = colors(&[1, 2, 3, 4]);
+        rotate(&mut range);
+        assert_eq!(values(&range), [4, 1, 2, 3]);
+        rotate(&mut range);
+        assert_eq!(values(&range), [3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn a_full_cycle_puts_colors_back() {
+        let mut range = colors(&[1, 2, 3, 4, 5]);
+        for _ in 0..5 {
+            rotate(&mut range);
+        }
+        assert_eq!(values(&range), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn stopping_mid_cycle_undoes_the_steps_taken() {
+        // what `stop_palette_cycle` does after two steps of three slots
+        let (len, offset) = (3, 2);
+        let mut range = colors(&[1, 2, 3]);
+        for _ in 0..offset + (len - offset) % len {
+            rotate(&mut range);
+        }
+        assert_eq!(values(&range), [1, 2, 3]);
+    }
+
+    #[test]
+    fn short_ranges_are_left_alone() {
+        let mut empty: [Color; 0] = [];
+        rotate(&mut empty);
+        let mut one = colors(&[7]);
+        rotate(&mut one);
+        assert_eq!(values(&one), [7]);
+    }
+}
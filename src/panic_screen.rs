@@ -0,0 +1,75 @@
+//! Show a panic on screen, so a crash on hardware can be read without a log
+//!
+//! Nothing here may panic or allocate. The message is formatted into a
+//! fixed buffer and cut off when it doesn't fit on screen.
+
+use core::{convert::Infallible, fmt::Write, panic::PanicInfo};
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    style::TextStyle,
+};
+use gba::{
+    io::{
+        display::{DisplayControlSetting, DisplayMode, DISPCNT},
+        irq::{IrqEnableSetting, IME},
+    },
+    vram::bitmap::Mode3,
+};
+
+use crate::{fmt::Truncating, gba_display::GbaDisplay};
+
+/// Characters that fit across the screen, with a margin
+const COLUMNS: usize = (Mode3::WIDTH - 8) / 6;
+
+/// Lines after the title that fit down the screen
+const ROWS: usize = (Mode3::HEIGHT - 20) / 9;
+
+/// Fill the screen red and draw `info` wrapped over it
+pub fn draw(info: &PanicInfo) {
+    // stop callbacks from drawing over the message and hide the sprites
+    IME.write(IrqEnableSetting::IRQ_NO);
+    DISPCNT.write(
+        DisplayControlSetting::new()
+            .with_mode(DisplayMode::Mode3)
+            .with_bg2(true),
+    );
+
+    let mut buf = [0; COLUMNS * ROWS];
+    let mut message = Truncating::new(&mut buf);
+    write!(message, "{}", info).ok();
+    draw_text(&mut GbaDisplay, message.as_str()).ok();
+}
+
+fn draw_text(display: &mut GbaDisplay, message: &str) -> Result<(), Infallible> {
+    display.clear(Bgr555::RED)?;
+    let style = TextStyle::new(Font6x8, Bgr555::WHITE);
+    Text::new("PANIC", Point::new(4, 4))
+        .into_styled(style)
+        .draw(display)?;
+    let mut row = 0;
+    for line in message.lines() {
+        let mut rest = line;
+        // an empty line still takes a row
+        loop {
+            if row == ROWS {
+                return Ok(());
+            }
+            let split = rest
+                .char_indices()
+                .nth(COLUMNS)
+                .map_or(rest.len(), |(i, _)| i);
+            let (shown, next) = rest.split_at(split);
+            Text::new(shown, Point::new(4, 16 + 9 * row as i32))
+                .into_styled(style)
+                .draw(display)?;
+            row += 1;
+            rest = next;
+            if rest.is_empty() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Bar showing how much of the frame the main loop's work took
+//!
+//! Timer 2 ticks at 16.78 MHz / 64, every 3.8 µs, so a frame from vblank to
+//! vblank is about 4389 ticks and the 16 bit counter wraps after 250 ms.
+//! It's restarted when the loop wakes up for vblank and read just before
+//! it sleeps again, which includes the time spent in interrupt handlers.
+//!
+//! The bar is drawn with sprites so no canvas pixel is touched. All its
+//! tiles use one palette slot, recolored to show how close to the budget
+//! the frame came.
+
+use embedded_graphics::{
+    pixelcolor::Bgr555, prelude::*, primitives::Rectangle, style::PrimitiveStyle,
+};
+use gba::{
+    io::timers::{TimerControlSetting, TimerTickRate, TM2CNT_H, TM2CNT_L},
+    oam::write_obj_attributes,
+    palram::index_palram_obj_8bpp,
+    vram::bitmap::Mode3,
+    Color,
+};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::PaletteColor,
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Timer ticks from one vblank to the next, 280896 cycles / 64
+const FRAME_TICKS: u32 = 4389;
+
+/// Bar color, after the recent color swatches
+const PALETTE_INDEX: u8 = 22;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Tiles with 1 to 8 columns filled, after the self test tile
+const FIRST_TILE: usize = 145;
+
+/// OAM slots of the bar segments, between the toolbar and the guides
+const FIRST_SLOT: usize = 10;
+
+/// Sprites in the bar, 8 pixels of it each
+const SEGMENTS: usize = 6;
+
+const ORIGIN: Point = Point::new(Mode3::WIDTH as i32 - 8 * SEGMENTS as i32 - 2, 151);
+
+/// Shares of the frame, in percent, where the bar turns yellow and red
+const WARN_PERCENT: u32 = 50;
+const OVER_PERCENT: u32 = 85;
+
+/// Times each frame with timer 2 and shows the last one as a bar
+pub struct Profiler {
+    visible: bool,
+    ticks: u32,
+}
+
+impl Profiler {
+    /// Draw the bar tiles into sprite tile memory, the bar starts hidden
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let color = PaletteColor::new(PALETTE_INDEX);
+        for width in 1..=8 {
+            let mut tile = blank_tile();
+            Rectangle::new(Point::new(0, 5), Point::new(width - 1, 7))
+                .into_styled(PrimitiveStyle::with_fill(color))
+                .draw(&mut tile)?;
+            block.set_tile(FIRST_TILE + width as usize - 1, tile)?;
+        }
+        let profiler = Self {
+            visible: false,
+            ticks: 0,
+        };
+        profiler.write_oam();
+        Ok(profiler)
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.write_oam();
+    }
+
+    /// Show the last frame's time and start timing a new frame, call right
+    /// after waking up for vblank
+    pub fn start(&self) {
+        self.write_oam();
+        TM2CNT_H.write(TimerControlSetting::new());
+        TM2CNT_L.write(0); // reload value
+        TM2CNT_H.write(
+            TimerControlSetting::new()
+                .with_tick_rate(TimerTickRate::CPU64)
+                .with_enabled(true),
+        );
+    }
+
+    /// Record the time since `start`, call right before waiting for vblank
+    pub fn stop(&mut self) {
+        self.ticks = TM2CNT_L.read() as u32;
+    }
+
+    /// Share of the frame the last frame took, in percent
+    pub fn percent(&self) -> u32 {
+        self.ticks * 100 / FRAME_TICKS
+    }
+
+    fn write_oam(&self) {
+        let percent = self.percent();
+        let color = if percent < WARN_PERCENT {
+            Bgr555::GREEN
+        } else if percent < OVER_PERCENT {
+            Bgr555::YELLOW
+        } else {
+            Bgr555::RED
+        };
+        index_palram_obj_8bpp(PALETTE_INDEX).write(Color(color.into_storage()));
+        let filled = (self.ticks * 8 * SEGMENTS as u32 / FRAME_TICKS) as i32;
+        for i in 0..SEGMENTS {
+            let width = (filled - 8 * i as i32).max(0).min(8) as usize;
+            let segment = SpriteBuilder::new(FIRST_TILE + width.max(1) - 1)
+                .position(ORIGIN + Point::new(8 * i as i32, 0))
+                .hidden(!self.visible || width == 0)
+                .build();
+            write_obj_attributes(FIRST_SLOT + i, segment);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Snap every canvas pixel to the nearest palette color
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+use crate::{color, scheduler::Pass};
+
+/// Replaces each pixel with the nearest of `palette`, pixels already in it are left alone
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantize<const N: usize> {
+    palette: [Bgr555; N],
+}
+
+impl<const N: usize> Quantize<N> {
+    pub fn new(palette: [Bgr555; N]) -> Self {
+        Self { palette }
+    }
+}
+
+impl<const N: usize> Pass for Quantize<N> {
+    fn pixel(&mut self, _point: Point, color: Bgr555) -> Option<Bgr555> {
+        color::nearest(color, &self.palette).filter(|&nearest| nearest != color)
+    }
+}
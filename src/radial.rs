@@ -0,0 +1,149 @@
+//! Ring of tool icons around the cursor, picked with the d-pad
+//!
+//! The icons reuse the toolbar tiles, one wedge per tool going clockwise
+//! from straight up, so `Tool::ALL` fills all eight d-pad directions.
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use gba::{oam::write_obj_attributes, vram::bitmap::Mode3};
+
+use crate::{
+    geom,
+    paint::Tool,
+    sprite::SpriteBuilder,
+    toolbar::{icon_tile, FRAME_TILE},
+};
+
+/// OAM slot of the highlight, the icons follow it
+const FIRST_SLOT: usize = 118;
+
+/// Top left corner of each icon from the center, clockwise from up
+const OFFSETS: [Point; 8] = [
+    Point::new(0, -16),
+    Point::new(11, -11),
+    Point::new(16, 0),
+    Point::new(11, 11),
+    Point::new(0, 16),
+    Point::new(-11, 11),
+    Point::new(-16, 0),
+    Point::new(-11, -11),
+];
+
+/// Centers that keep the whole ring on screen, corners inclusive
+const CENTERS: Rectangle = Rectangle::new(
+    Point::new(20, 20),
+    Point::new(Mode3::WIDTH as i32 - 20, Mode3::HEIGHT as i32 - 20),
+);
+
+/// Wedge a d-pad `direction` points at, clockwise from up, `None` when centered
+pub fn wedge(direction: Point) -> Option<usize> {
+    match (direction.x.signum(), direction.y.signum()) {
+        (0, -1) => Some(0),
+        (1, -1) => Some(1),
+        (1, 0) => Some(2),
+        (1, 1) => Some(3),
+        (0, 1) => Some(4),
+        (-1, 1) => Some(5),
+        (-1, 0) => Some(6),
+        (-1, -1) => Some(7),
+        _ => None,
+    }
+}
+
+/// Pie menu of the tools, hidden until opened
+pub struct RadialMenu {
+    center: Option<Point>,
+    selected: Option<usize>,
+}
+
+impl RadialMenu {
+    pub fn new() -> Self {
+        let menu = Self {
+            center: None,
+            selected: None,
+        };
+        menu.write_oam();
+        menu
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.center.is_some()
+    }
+
+    /// Show the ring around `center`, moved in from the screen edges
+    pub fn open(&mut self, center: Point) {
+        self.center = Some(geom::clamp(&CENTERS, center));
+        self.selected = None;
+        self.write_oam();
+    }
+
+    /// Highlight the wedge `direction` points at, centering the d-pad keeps
+    /// the last one
+    pub fn update(&mut self, direction: Point) {
+        if let Some(wedge) = wedge(direction) {
+            if self.selected != Some(wedge) {
+                self.selected = Some(wedge);
+                self.write_oam();
+            }
+        }
+    }
+
+    /// Hide the ring, returns the highlighted tool if there is one
+    pub fn close(&mut self) -> Option<Tool> {
+        let tool = self.selected.map(|i| Tool::ALL[i]);
+        self.center = None;
+        self.selected = None;
+        self.write_oam();
+        tool
+    }
+
+    fn write_oam(&self) {
+        let center = self.center.unwrap_or_else(Point::zero);
+        let top_left = |i: usize| center + OFFSETS[i] - Point::new(4, 4);
+        let frame = SpriteBuilder::new(FRAME_TILE)
+            .position(top_left(self.selected.unwrap_or(0)))
+            .hidden(self.selected.is_none())
+            .build();
+        write_obj_attributes(FIRST_SLOT, frame);
+        for i in 0..Tool::ALL.len() {
+            let icon = SpriteBuilder::new(icon_tile(i))
+                .position(top_left(i))
+                .hidden(self.center.is_none())
+                .build();
+            write_obj_attributes(FIRST_SLOT + 1 + i, icon);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_direction_picks_its_wedge_clockwise_from_up() {
+        let directions = [
+            Point::new(0, -1),
+            Point::new(1, -1),
+            Point::new(1, 0),
+            Point::new(1, 1),
+            Point::new(0, 1),
+            Point::new(-1, 1),
+            Point::new(-1, 0),
+            Point::new(-1, -1),
+        ];
+        for (i, &direction) in directions.iter().enumerate() {
+            assert_eq!(wedge(direction), Some(i), "{:?}", direction);
+        }
+        assert_eq!(directions.len(), Tool::ALL.len());
+    }
+
+    #[test]
+    fn centered_d_pad_picks_nothing() {
+        assert_eq!(wedge(Point::zero()), None);
+    }
+
+    #[test]
+    fn only_the_sign_of_a_direction_counts() {
+        assert_eq!(wedge(Point::new(3, -7)), Some(1));
+        assert_eq!(wedge(Point::new(-2, 0)), Some(6));
+    }
+}
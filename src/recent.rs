@@ -0,0 +1,157 @@
+//! Colors recently picked with the eyedropper, shown as swatches under the toolbar
+
+use embedded_graphics::{
+    pixelcolor::Bgr555, prelude::*, primitives::Rectangle, style::PrimitiveStyleBuilder,
+};
+use gba::{oam::write_obj_attributes, palram::index_palram_obj_8bpp, Color};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::PaletteColor,
+    geom,
+    reticle::Reticle,
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Colors kept, the oldest is dropped for a new one
+pub const LEN: usize = 8;
+
+/// Registered palette slot, see `register_palette`
+const BLACK: PaletteColor = PaletteColor::new(1);
+
+/// Object palette slot of the first swatch color, after the reticle shades
+const FIRST_PALETTE_INDEX: u8 = 14;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// First swatch tile, after the animation frame
+const FIRST_TILE: usize = 134;
+
+/// OAM slot of the first swatch, after the animation
+const FIRST_SLOT: usize = 93;
+
+/// Below the toolbar icons
+const ORIGIN: Point = Point::new(80, 11);
+
+/// Distance between swatches, the same as the toolbar icons
+const SPACING: i32 = 10;
+
+/// Ring of the last `LEN` distinct picks, newest first
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RecentColors {
+    colors: [Bgr555; LEN],
+    len: usize,
+    /// Index of the newest color
+    head: usize,
+}
+
+impl RecentColors {
+    pub const fn new() -> Self {
+        Self {
+            colors: [Bgr555::BLACK; LEN],
+            len: 0,
+            head: 0,
+        }
+    }
+
+    /// Remember `color`, picking the newest color again changes nothing
+    pub fn push(&mut self, color: Bgr555) {
+        if self.get(0) == Some(color) {
+            return;
+        }
+        self.head = (self.head + LEN - 1) % LEN;
+        self.colors[self.head] = color;
+        self.len = (self.len + 1).min(LEN);
+    }
+
+    /// The `i`th newest color
+    pub fn get(&self, i: usize) -> Option<Bgr555> {
+        if i < self.len {
+            Some(self.colors[(self.head + i) % LEN])
+        } else {
+            None
+        }
+    }
+}
+
+/// Swatch sprites for `RecentColors`, each swatch tile uses its own palette slot
+pub struct RecentSwatches {
+    colors: RecentColors,
+    visible: bool,
+}
+
+impl RecentSwatches {
+    /// Draw the swatches into sprite tile memory, they start hidden
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        for i in 0..LEN {
+            let mut swatch = blank_tile();
+            let style = PrimitiveStyleBuilder::new()
+                .stroke_color(BLACK)
+                .stroke_width(1)
+                .fill_color(PaletteColor::new(FIRST_PALETTE_INDEX + i as u8))
+                .build();
+            Rectangle::new(Point::zero(), Point::new(7, 7))
+                .into_styled(style)
+                .draw(&mut swatch)?;
+            block.set_tile(FIRST_TILE + i, swatch)?;
+        }
+        let swatches = Self {
+            colors: RecentColors::new(),
+            visible: false,
+        };
+        swatches.write_oam();
+        Ok(swatches)
+    }
+
+    /// Show a swatch for each of `colors`
+    pub fn show(&mut self, colors: &RecentColors) {
+        self.colors = *colors;
+        self.visible = true;
+        self.write_oam();
+    }
+
+    /// Redraw if `colors` changed since the swatches were last drawn
+    pub fn update(&mut self, colors: &RecentColors) {
+        if self.colors != *colors {
+            self.colors = *colors;
+            self.write_oam();
+        }
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.write_oam();
+    }
+
+    /// Color of the swatch the reticle points at
+    pub fn color_at(&self, reticle: &Reticle) -> Option<Bgr555> {
+        if !self.visible {
+            return None;
+        }
+        (0..LEN)
+            .find(|&i| geom::hotspot_in_rect(reticle, &swatch_rect(i)))
+            .and_then(|i| self.colors.get(i))
+    }
+
+    fn write_oam(&self) {
+        for i in 0..LEN {
+            let color = self.colors.get(i);
+            if let Some(color) = color {
+                index_palram_obj_8bpp(FIRST_PALETTE_INDEX + i as u8)
+                    .write(Color(color.into_storage()));
+            }
+            let swatch = SpriteBuilder::new(FIRST_TILE + i)
+                .position(swatch_rect(i).top_left)
+                .hidden(!self.visible || color.is_none())
+                .build();
+            write_obj_attributes(FIRST_SLOT + i, swatch);
+        }
+    }
+}
+
+/// Screen area of swatch `i`
+fn swatch_rect(i: usize) -> Rectangle {
+    let top_left = ORIGIN + Point::new(SPACING * i as i32, 0);
+    Rectangle::new(top_left, top_left + Point::new(7, 7))
+}
@@ -0,0 +1,105 @@
+//! Deterministic playback of recorded keypad input
+//!
+//! Built with the `replay` feature, the main loop feeds a recorded log
+//! through `Input` instead of reading the keypad. Once the log runs out the
+//! canvas is compared against the expected pixels and the result is logged,
+//! so an emulator run can check that the input still paints the same picture.
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+use gba::{debug, fatal};
+
+use crate::{
+    gba_display::GbaDisplay,
+    input::{Input, Key, Keys},
+};
+
+/// Raw keypad state held for a number of frames
+pub type Entry = (Keys, u16);
+
+const A: Keys = Keys::NONE.with(Key::A);
+const B: Keys = Keys::NONE.with(Key::B);
+const A_RIGHT: Keys = A.with(Key::Right);
+
+/// Open the canvas, switch to red and brush a line right of center
+pub const EXAMPLE: [Entry; 7] = [
+    (Keys::NONE, 10),
+    (A, 4), // paint from the menu, the brush dabs black at center
+    (Keys::NONE, 10),
+    (B, 4),
+    (Keys::NONE, 10),
+    (A_RIGHT, 20),
+    (Keys::NONE, 10),
+];
+
+/// Pixels `EXAMPLE` must leave on the canvas, the cursor starts at center
+pub const EXAMPLE_EXPECTED: [(Point, Bgr555); 4] = [
+    (Point::new(120, 80), Bgr555::BLACK),
+    (Point::new(125, 80), Bgr555::RED),
+    (Point::new(130, 80), Bgr555::RED),
+    (Point::new(135, 80), Bgr555::RED),
+];
+
+/// Plays back a log one frame at a time
+pub struct Replay {
+    log: &'static [Entry],
+    expected: &'static [(Point, Bgr555)],
+    index: usize,
+    frame: u16,
+    checked: bool,
+}
+
+impl Replay {
+    pub const fn new(log: &'static [Entry], expected: &'static [(Point, Bgr555)]) -> Self {
+        Self {
+            log,
+            expected,
+            index: 0,
+            frame: 0,
+            checked: false,
+        }
+    }
+
+    /// Keys for the next frame, `None` once the log is finished
+    pub fn next_keys(&mut self) -> Option<Keys> {
+        while let Some(&(keys, frames)) = self.log.get(self.index) {
+            if self.frame < frames {
+                self.frame += 1;
+                return Some(keys);
+            }
+            self.index += 1;
+            self.frame = 0;
+        }
+        None
+    }
+
+    /// Feed the next frame into `input`, releasing all keys and checking the
+    /// canvas once the log is finished
+    pub fn update(&mut self, input: &mut Input, display: &GbaDisplay) {
+        match self.next_keys() {
+            Some(keys) => input.update_with(keys),
+            None => {
+                input.update_with(Keys::NONE);
+                if !self.checked {
+                    self.checked = true;
+                    assert_canvas(display, self.expected);
+                }
+            }
+        }
+    }
+}
+
+/// Log every pixel that doesn't match and stop if any failed
+pub fn assert_canvas(display: &GbaDisplay, expected: &[(Point, Bgr555)]) {
+    let mut failures = 0;
+    for &(point, color) in expected {
+        let actual = display.get_pixel(point);
+        if actual != Some(color) {
+            debug!("Replay: {:?} is {:?}, expected {:?}", point, actual, color);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        fatal!("Replay failed: {} of {} pixels", failures, expected.len());
+    }
+    debug!("Replay passed: {} pixels", expected.len());
+}
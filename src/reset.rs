@@ -0,0 +1,21 @@
+//! Restart the whole program through the BIOS
+//!
+//! `SoftReset` itself only clears the stacks at the top of IWRAM before
+//! jumping back to the cartridge entry point. The rest of RAM starts over
+//! because crt0 runs again: `RegisterRamReset` clears EWRAM, `.data` is
+//! copied back from ROM and `.bss` is zeroed. Unlike going back to the
+//! menu, which keeps the canvas, undo history and settings in memory, only
+//! what was written to SRAM is there after the restart.
+
+#![allow(unsafe_code)]
+
+use gba::io::irq::{IrqEnableSetting, IME};
+
+/// Restart through the BIOS, save anything worth keeping first
+pub fn soft_reset() -> ! {
+    // a handler firing mid reset would run with half cleared RAM
+    IME.write(IrqEnableSetting::IRQ_NO);
+    // SAFETY: interrupts are off and the call never returns, so no Rust
+    // code observes the reset state
+    unsafe { gba::bios::soft_reset() }
+}
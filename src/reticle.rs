@@ -0,0 +1,476 @@
+use core::{convert::Infallible, ops::Range};
+use embedded_graphics::{
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::{Circle, Line, Rectangle, Triangle},
+    style::{PrimitiveStyle, PrimitiveStyleBuilder},
+};
+use gba::{
+    oam::{write_affine_parameters, write_obj_attributes, ObjectAttributes},
+    palram::index_palram_obj_8bpp,
+    sync::Static,
+    vram::bitmap::Mode3,
+    Color,
+};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    color,
+    gba_display::{GbaDisplay, PaletteColor},
+    geom,
+    sprite::{self, ObjSize, SpriteBuilder, SpriteTiles},
+};
+
+/// Object palette slot holding the cursor color, after the registered colors
+pub const PALETTE_INDEX: u8 = 9;
+
+/// Object palette slots of the partly covered edge shades of `SmoothCircle`,
+/// after the guide color
+const SHADE_INDEX: u8 = 11;
+const SHADES: u8 = 3;
+
+/// Object palette slot of the outline around the shape, after the profiler
+const OUTLINE_INDEX: u8 = 23;
+
+/// Canvas pixels across and down each area the outline color is sampled over
+const OUTLINE_AREA: i32 = 8;
+
+/// Character block 5 is the only sprite tile memory available in bitmap modes
+const CHARACTER_BLOCK: usize = 5;
+
+/// First tile of the reticle shapes, tile 0 is left blank
+const FIRST_TILE: usize = 1;
+
+/// Affine parameter group used when the cursor is rotated
+const AFFINE_INDEX: u8 = 0;
+
+/// OAM slot of the cursor, drawn over every other sprite
+const SLOT: usize = 0;
+
+/// Cursor attributes waiting for the next `commit`
+static PENDING: Static<Option<ObjectAttributes>> = Static::new(None);
+
+/// Cursor attributes last written by `commit`
+static COMMITTED: Static<Option<ObjectAttributes>> = Static::new(None);
+
+/// Write deferred cursor attributes to OAM, register with `vblank::on_vblank`
+///
+/// Attributes that match the ones already in OAM aren't written again, so a
+/// still cursor costs nothing.
+pub fn commit() {
+    if let Some(attributes) = PENDING.read() {
+        PENDING.write(None);
+        if COMMITTED.read() != Some(attributes) {
+            write_obj_attributes(SLOT, attributes);
+            COMMITTED.write(Some(attributes));
+        }
+    }
+}
+
+/// Shape of the cursor sprite
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReticleStyle {
+    Arrow,
+    Crosshair,
+    Square,
+    Circle,
+    /// Anti-aliased circle, shaded with `SHADES` extra palette slots
+    SmoothCircle,
+}
+
+impl ReticleStyle {
+    pub const ALL: [Self; 5] = [
+        Self::Arrow,
+        Self::Crosshair,
+        Self::Square,
+        Self::Circle,
+        Self::SmoothCircle,
+    ];
+
+    /// Offset of the pixel the cursor points at from the sprite's top left corner
+    pub fn hotspot(self, size: ObjSize) -> Point {
+        match self {
+            Self::Arrow => Point::zero(),
+            Self::Crosshair | Self::Square | Self::Circle | Self::SmoothCircle => {
+                let center = center(size);
+                Point::new(center, center)
+            }
+        }
+    }
+
+    /// Index of the style's first tile in the character block
+    fn tile_index(self, size: ObjSize) -> usize {
+        FIRST_TILE + self as usize * size.tiles()
+    }
+}
+
+/// Where the cursor starts when the app boots
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StartPosition {
+    Center,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// Position saved with the canvas, or center if there is none
+    Saved,
+}
+
+impl StartPosition {
+    /// Screen point to start at, `saved` is clamped in case it is stale or corrupt
+    pub fn resolve(self, saved: Option<Point>) -> Point {
+        let screen = Rectangle::new(
+            Point::zero(),
+            Point::new(Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1),
+        );
+        let center = Point::new(Mode3::WIDTH as i32 / 2, Mode3::HEIGHT as i32 / 2);
+        match self {
+            Self::Center => center,
+            Self::TopLeft => screen.top_left,
+            Self::TopRight => Point::new(screen.bottom_right.x, 0),
+            Self::BottomLeft => Point::new(0, screen.bottom_right.y),
+            Self::BottomRight => screen.bottom_right,
+            Self::Saved => saved.map_or(center, |point| geom::clamp(&screen, point)),
+        }
+    }
+}
+
+/// The cursor sprite
+pub struct Reticle {
+    style: ReticleStyle,
+    size: ObjSize,
+    position: Point,
+    /// Added to the sprite position but not the hotspot, see `shake`
+    shake: Point,
+    hidden: bool,
+    smooth: bool,
+    /// Clockwise turn in 256ths of a turn, `None` draws the sprite unrotated
+    rotation: Option<u8>,
+    /// Leave OAM writes to `commit`
+    deferred: bool,
+    blink_period: Option<u16>,
+    frame: u16,
+    /// Shapes are drawn with a ring of `OUTLINE_INDEX` around them
+    outlined: bool,
+}
+
+impl Reticle {
+    /// Draw every style at `size` into sprite tile memory
+    pub fn new(style: ReticleStyle, size: ObjSize, color: Bgr555) -> Result<Self, TileError> {
+        draw_tiles(size, false)?;
+        let mut reticle = Self {
+            style,
+            size,
+            position: Point::zero(),
+            shake: Point::zero(),
+            hidden: false,
+            smooth: false,
+            rotation: None,
+            deferred: false,
+            blink_period: None,
+            frame: 0,
+            outlined: false,
+        };
+        reticle.set_color(color);
+        Ok(reticle)
+    }
+
+    /// Switch the cursor shape, updating OAM immediately
+    pub fn set_style(&mut self, style: ReticleStyle) {
+        self.style = style;
+        self.write_oam();
+    }
+
+    /// Show `Circle` as `SmoothCircle`, off by default for palettes with no slots to spare
+    pub fn set_smoothing(&mut self, smooth: bool) {
+        self.smooth = smooth;
+        self.write_oam();
+    }
+
+    /// Turn the cursor clockwise by `angle` in 256ths of a turn, keeping the
+    /// hotspot on the same screen pixel
+    pub fn set_rotation(&mut self, angle: Option<u8>) {
+        self.rotation = angle;
+        if let Some(angle) = angle {
+            write_affine_parameters(AFFINE_INDEX as usize, sprite::rotation(angle));
+        }
+        self.write_oam();
+    }
+
+    /// Only update OAM from `commit` during vblank, so a fast moving cursor
+    /// can't tear
+    ///
+    /// The main loop moves the cursor late in the frame, so a deferred move
+    /// shows from the top of the next frame instead of partway down this
+    /// one. Whole frames show the new position no later than before.
+    pub fn set_deferred(&mut self, deferred: bool) {
+        self.deferred = deferred;
+        self.write_oam();
+    }
+
+    /// Change the cursor color
+    pub fn set_color(&mut self, color: Bgr555) {
+        index_palram_obj_8bpp(PALETTE_INDEX).write(Color(color.into_storage()));
+        // darker toward less covered pixels
+        for shade in 1..=SHADES {
+            let color = color::blend(Bgr555::BLACK, color, shade * 16 / (SHADES + 1));
+            index_palram_obj_8bpp(SHADE_INDEX + shade - 1).write(Color(color.into_storage()));
+        }
+    }
+
+    /// Ring the cursor shape with `color` so it stands out from the canvas,
+    /// `None` draws the bare shape
+    ///
+    /// Turning the outline on or off redraws every shape, changing only the
+    /// color is a palette write.
+    pub fn set_outline(&mut self, color: Option<Bgr555>) -> Result<(), TileError> {
+        if color.is_some() != self.outlined {
+            draw_tiles(self.size, color.is_some())?;
+            self.outlined = color.is_some();
+        }
+        if let Some(color) = color {
+            index_palram_obj_8bpp(OUTLINE_INDEX).write(Color(color.into_storage()));
+        }
+        Ok(())
+    }
+
+    /// Blink the cursor every `period` frames so it can't get lost against busy art,
+    /// `None` keeps it steady
+    pub fn set_blink(&mut self, period: Option<u16>) {
+        self.blink_period = period.filter(|&period| period > 0);
+        self.frame = 0;
+        self.write_oam();
+    }
+
+    /// Advance the blink animation, call once per frame
+    pub fn tick(&mut self) {
+        if let Some(period) = self.blink_period {
+            let was_off = self.is_blinked_off();
+            self.frame = (self.frame + 1) % period;
+            if self.is_blinked_off() != was_off {
+                self.write_oam();
+            }
+        }
+    }
+
+    /// Draw the sprite `offset` away from the hotspot without moving the hotspot
+    pub fn set_shake(&mut self, offset: Point) {
+        if offset != self.shake {
+            self.shake = offset;
+            self.write_oam();
+        }
+    }
+
+    /// Stop drawing the cursor until the next `move_to`
+    pub fn hide(&mut self) {
+        self.hidden = true;
+        self.write_oam();
+    }
+
+    /// Character block tiles of the current shape
+    pub fn tiles(&self) -> Range<usize> {
+        let first = self.style.tile_index(self.size);
+        first..first + self.size.tiles()
+    }
+
+    /// Screen position of the pixel the cursor points at
+    pub fn hotspot(&self) -> Point {
+        self.position
+    }
+
+    /// Place the cursor hotspot at `point` and show it
+    pub fn move_to(&mut self, point: Point) {
+        self.position = point;
+        self.hidden = false;
+        self.write_oam();
+    }
+
+    /// Blinking hides the cursor for the last quarter of each period
+    fn is_blinked_off(&self) -> bool {
+        match self.blink_period {
+            Some(period) => self.frame >= period - period / 4,
+            None => false,
+        }
+    }
+
+    fn write_oam(&self) {
+        let style = match self.style {
+            ReticleStyle::Circle if self.smooth => ReticleStyle::SmoothCircle,
+            style => style,
+        };
+        let builder = SpriteBuilder::new(style.tile_index(self.size)).size(self.size);
+        let builder = match self.rotation {
+            Some(angle) => {
+                // turned around the sprite's center, which is the center of the doubled box
+                let half = self.size.pixels() as i32 / 2;
+                let offset = style.hotspot(self.size) - Point::new(half, half);
+                let top_left =
+                    self.position - sprite::rotate(offset, angle) - Point::new(2 * half, 2 * half);
+                builder
+                    .affine(AFFINE_INDEX, true)
+                    .position(top_left + self.shake)
+            }
+            None => builder.position(self.position - style.hotspot(self.size) + self.shake),
+        };
+        let attributes = builder.hidden(self.hidden || self.is_blinked_off()).build();
+        if self.deferred {
+            PENDING.write(Some(attributes));
+        } else {
+            write_obj_attributes(SLOT, attributes);
+            COMMITTED.write(Some(attributes));
+        }
+    }
+}
+
+/// Picks the outline color from the canvas under the cursor
+///
+/// Averaging the area reads 64 pixels, so it's only done when the cursor
+/// crosses into another area and not while it moves within one or the
+/// canvas under it changes.
+pub struct AutoOutline {
+    area: Option<Point>,
+}
+
+impl AutoOutline {
+    pub const fn new() -> Self {
+        Self { area: None }
+    }
+
+    /// Outline color for a cursor at `point`, `None` while it stays in the
+    /// area it was last sampled in
+    pub fn update(&mut self, display: &GbaDisplay, point: Point) -> Option<Bgr555> {
+        let area = Point::new(
+            point.x.div_euclid(OUTLINE_AREA),
+            point.y.div_euclid(OUTLINE_AREA),
+        );
+        if self.area == Some(area) {
+            return None;
+        }
+        self.area = Some(area);
+        let top_left = area * OUTLINE_AREA;
+        let pixels = (0..OUTLINE_AREA).flat_map(|y| {
+            (0..OUTLINE_AREA).filter_map(move |x| display.get_pixel(top_left + Point::new(x, y)))
+        });
+        color::average(pixels).map(color::contrasting)
+    }
+}
+
+/// Draw every style at `size` into sprite tile memory, ringed by the
+/// outline if `outline` is set
+fn draw_tiles(size: ObjSize, outline: bool) -> Result<(), TileError> {
+    let block = CharBlock::new(CHARACTER_BLOCK)?;
+    for &style in ReticleStyle::ALL.iter() {
+        let mut tiles = SpriteTiles::new(size);
+        if outline {
+            draw_reticle(style, size, &mut Neighbors(&mut tiles))?;
+        }
+        draw_reticle(style, size, &mut tiles)?;
+        for (i, &tile) in tiles.used().iter().enumerate() {
+            block.set_tile(style.tile_index(size) + i, tile)?;
+        }
+    }
+    Ok(())
+}
+
+/// Draws the outline color on the four neighbors of every pixel instead of
+/// the pixel, the shape drawn over it afterwards leaves just the ring
+struct Neighbors<'a>(&'a mut SpriteTiles);
+
+impl DrawTarget<PaletteColor> for Neighbors<'_> {
+    type Error = Infallible;
+
+    fn draw_pixel(&mut self, pixel: Pixel<PaletteColor>) -> Result<(), Self::Error> {
+        let color = PaletteColor::new(OUTLINE_INDEX);
+        for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            self.0
+                .draw_pixel(Pixel(pixel.0 + Point::new(dx, dy), color))?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        self.0.size()
+    }
+}
+
+/// Center pixel of a sprite, 3 for an 8x8 sprite
+fn center(size: ObjSize) -> i32 {
+    (size.pixels() as i32 - 2) / 2
+}
+
+/// Draw the shape of `style` scaled to fill a `size` sprite
+fn draw_reticle<T: DrawTarget<PaletteColor, Error = Infallible>>(
+    style: ReticleStyle,
+    size: ObjSize,
+    tiles: &mut T,
+) -> Result<(), Infallible> {
+    let color = PaletteColor::new(PALETTE_INDEX);
+    let stroke = PrimitiveStyle::with_stroke(color, 1);
+    let c = center(size);
+    let n = size.pixels() as i32 - 1; // far edge
+    match style {
+        ReticleStyle::Arrow => Triangle::new(
+            Point::zero(),
+            Point::new(n, n * 4 / 7),
+            Point::new(n * 4 / 7, n),
+        )
+        .into_styled(
+            PrimitiveStyleBuilder::new()
+                .stroke_color(color)
+                .stroke_width(1)
+                .fill_color(color)
+                .build(),
+        )
+        .draw(tiles),
+        ReticleStyle::Crosshair => {
+            Line::new(Point::new(c, 0), Point::new(c, 2 * c))
+                .into_styled(stroke)
+                .draw(tiles)?;
+            Line::new(Point::new(0, c), Point::new(2 * c, c))
+                .into_styled(stroke)
+                .draw(tiles)
+        }
+        ReticleStyle::Square => Rectangle::new(Point::zero(), Point::new(2 * c, 2 * c))
+            .into_styled(stroke)
+            .draw(tiles),
+        ReticleStyle::Circle => Circle::new(Point::new(c, c), c as u32)
+            .into_styled(stroke)
+            .draw(tiles),
+        ReticleStyle::SmoothCircle => draw_smooth_circle(c, tiles),
+    }
+}
+
+/// One pixel wide ring of radius `c` around (`c`, `c`), each pixel shaded by
+/// how many of its 4x4 sub-pixel samples land in the ring
+fn draw_smooth_circle<T: DrawTarget<PaletteColor, Error = Infallible>>(
+    c: i32,
+    tiles: &mut T,
+) -> Result<(), Infallible> {
+    // distances in 8ths of a pixel
+    let inner = (8 * c - 4) * (8 * c - 4);
+    let outer = (8 * c + 4) * (8 * c + 4);
+    for y in 0..=2 * c {
+        for x in 0..=2 * c {
+            let mut covered = 0;
+            for &dy in [-3, -1, 1, 3].iter() {
+                for &dx in [-3, -1, 1, 3].iter() {
+                    let sx = (x - c) * 8 + dx;
+                    let sy = (y - c) * 8 + dy;
+                    let distance = sx * sx + sy * sy;
+                    if inner <= distance && distance <= outer {
+                        covered += 1;
+                    }
+                }
+            }
+            // round 16 samples to full, one of the shades, or empty
+            let level = (covered * (SHADES as i32 + 1) + 8) / 16;
+            let index = match level {
+                0 => continue,
+                level if level > SHADES as i32 => PALETTE_INDEX,
+                level => SHADE_INDEX + level as u8 - 1,
+            };
+            Pixel(Point::new(x, y), PaletteColor::new(index)).draw(tiles)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Small pseudo random number generator for visual effects
+//!
+//! Effects don't seed their own generators, they fork one off the global
+//! generator when they start, so a single seed decides every random
+//! offset after it's set. With a fixed seed and the same button presses
+//! each run draws the same frames, which screenshot diffs rely on.
+
+use gba::sync::Static;
+
+/// Generator the others are forked from, see `set_seed`
+static GLOBAL: Static<Rng> = Static::new(Rng::new(0));
+
+/// Restart the global generator from `seed`
+pub fn set_seed(seed: u32) {
+    GLOBAL.write(Rng::new(seed));
+}
+
+/// A new generator seeded from the global one, which moves on
+pub fn fork() -> Rng {
+    let mut global = GLOBAL.read();
+    let seed = global.next_u32();
+    GLOBAL.write(global);
+    Rng::new(seed)
+}
+
+/// Xorshift generator, fast and tiny but not for anything that must be unpredictable
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    /// A zero seed would only ever produce zeros, so it is replaced
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x2545_f491 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform-ish value from `-max` to `max` inclusive
+    pub fn offset(&mut self, max: u8) -> i32 {
+        let span = 2 * max as u32 + 1;
+        (self.next_u32() % span) as i32 - max as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What an effect forked at the start of a run draws
+    fn run(seed: u32) -> Vec<i32> {
+        set_seed(seed);
+        let (mut sparkle, mut spray) = (fork(), fork());
+        (0..16)
+            .map(|i| {
+                if i % 2 == 0 {
+                    sparkle.offset(3)
+                } else {
+                    spray.offset(8)
+                }
+            })
+            .collect()
+    }
+
+    // one test, the global generator is shared between threads
+    #[test]
+    fn same_seed_gives_the_same_run() {
+        let first = run(42);
+        // draws since the last run don't leak into the next
+        fork();
+        fork();
+        assert_eq!(run(42), first);
+        assert_ne!(run(43), first);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_numbers() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn zero_seed_still_moves() {
+        let mut rng = Rng::new(0);
+        assert_ne!(rng.next_u32(), 0);
+    }
+
+    #[test]
+    fn offsets_stay_in_range() {
+        let mut rng = Rng::new(1);
+        let offsets: Vec<i32> = (0..1000).map(|_| rng.offset(2)).collect();
+        assert!(offsets.iter().all(|offset| (-2..=2).contains(offset)));
+        assert!((-2..=2).all(|offset| offsets.contains(&offset)));
+    }
+}
@@ -0,0 +1,89 @@
+//! Turn the whole canvas around its center by any angle
+//!
+//! Each pixel is mapped back to the canvas pixel that turns onto it, so
+//! there are no holes, and takes its color unfiltered. The pass overwrites
+//! pixels other pixels still read, so like `outline` it reads from the
+//! snapshot.
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+};
+use gba::vram::bitmap::Mode3;
+
+use crate::{canvas, scheduler::Pass, sprite};
+
+const CENTER: Point = Point::new(Mode3::WIDTH as i32 / 2, Mode3::HEIGHT as i32 / 2);
+
+/// Turns the canvas clockwise by `angle` in 256ths of a turn, take
+/// `canvas::snapshot_canvas` before submitting
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rotate {
+    angle: u8,
+    /// Shown where the turned canvas doesn't reach
+    background: Bgr555,
+}
+
+impl Rotate {
+    pub fn new(angle: u8, background: Bgr555) -> Self {
+        Self { angle, background }
+    }
+}
+
+/// Canvas pixel that lands on `point` once turned clockwise by `angle`
+pub fn source(point: Point, angle: u8) -> Point {
+    CENTER + sprite::rotate(point - CENTER, 0u8.wrapping_sub(angle))
+}
+
+impl Pass for Rotate {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        let source = source(point, self.angle);
+        let color = canvas::snapshot_pixel(source.x, source.y)
+            .map_or(self.background, |color| RawU16::new(color.0).into());
+        Some(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3x3 image around the center, numbered in row order
+    const IMAGE: [[u8; 3]; 3] = [[0, 1, 2], [3, 4, 5], [6, 7, 8]];
+
+    /// `IMAGE` turned by `angle`, mapping each pixel back with `source`
+    fn turn(angle: u8) -> [[u8; 3]; 3] {
+        let mut turned = [[0; 3]; 3];
+        for (y, row) in turned.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let offset = Point::new(x as i32 - 1, y as i32 - 1);
+                let from = source(CENTER + offset, angle) - CENTER + Point::new(1, 1);
+                *pixel = IMAGE[from.y as usize][from.x as usize];
+            }
+        }
+        turned
+    }
+
+    #[test]
+    fn no_angle_keeps_every_pixel() {
+        assert_eq!(turn(0), IMAGE);
+    }
+
+    #[test]
+    fn quarter_turn_is_clockwise() {
+        assert_eq!(turn(64), [[6, 3, 0], [7, 4, 1], [8, 5, 2]]);
+    }
+
+    #[test]
+    fn half_turn_flips_both_ways() {
+        assert_eq!(turn(128), [[8, 7, 6], [5, 4, 3], [2, 1, 0]]);
+    }
+
+    #[test]
+    fn corners_turn_in_from_off_the_canvas() {
+        // the bottom right corner comes from past the top right
+        let corner = Point::new(Mode3::WIDTH as i32 - 1, Mode3::HEIGHT as i32 - 1);
+        let from = source(corner, 64);
+        assert!(from.y < 0, "{:?}", from);
+    }
+}
@@ -0,0 +1,140 @@
+//! Measuring line from an anchor to the cursor, drawn with sprites
+//!
+//! Like the guides, the ruler never touches canvas pixels. The line is a
+//! row of dot sprites and the length is drawn into a label sprite's tiles,
+//! which are only rewritten when the length changes.
+
+use embedded_graphics::{
+    fonts::Font6x8, prelude::*, primitives::Rectangle, style::TextStyleBuilder,
+};
+use gba::{oam::write_obj_attributes, vram::bitmap::Mode3};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    fmt,
+    gba_display::PaletteColor,
+    geom, reticle,
+    sprite::{blank_tile, ObjSize, SpriteBuilder, SpriteTiles, Strip},
+};
+
+/// Registered palette slots, see `register_palette`
+const BLACK: PaletteColor = PaletteColor::new(1);
+const WHITE: PaletteColor = PaletteColor::new(8);
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Single pixel dot in the cursor color, after the guide tiles
+const DOT_TILE: usize = 121;
+/// 32x8 label with the length
+const LABEL_TILE: usize = DOT_TILE + 1;
+
+/// OAM slots after the guides, the label is drawn over the dots
+const LABEL_SLOT: usize = 42;
+const FIRST_DOT_SLOT: usize = LABEL_SLOT + 1;
+const DOTS: usize = 48;
+
+/// Pixels between dots on short lines, long lines spread `DOTS` evenly
+const DOT_SPACING: u32 = 4;
+
+/// Label offset from the cursor, above and to the right
+const LABEL_OFFSET: Point = Point::new(6, -10);
+
+/// Anchor of the measuring line, if one is set
+pub struct Ruler {
+    anchor: Option<Point>,
+    /// Cursor position and length of the line last written to OAM
+    shown: Option<(Point, u32)>,
+}
+
+impl Ruler {
+    /// Draw the dot tile, the ruler starts without an anchor
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let mut dot = blank_tile();
+        Pixel(Point::zero(), PaletteColor::new(reticle::PALETTE_INDEX)).draw(&mut dot)?;
+        block.set_tile(DOT_TILE, dot)?;
+
+        let ruler = Self {
+            anchor: None,
+            shown: None,
+        };
+        ruler.hide();
+        Ok(ruler)
+    }
+
+    /// Measure from `point`, or stop measuring if an anchor is already set
+    pub fn toggle(&mut self, point: Point) {
+        self.shown = None;
+        match self.anchor {
+            Some(_) => {
+                self.anchor = None;
+                self.hide();
+            }
+            None => {
+                self.anchor = Some(point);
+                self.update(point);
+            }
+        }
+    }
+
+    /// Redraw the line to the cursor at `point`, call once per frame
+    pub fn update(&mut self, point: Point) {
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        if matches!(self.shown, Some((shown, _)) if shown == point) {
+            return;
+        }
+        let length = geom::distance(anchor, point);
+        if self.shown.map(|(_, shown)| shown) != Some(length) {
+            draw_label(length).ok();
+        }
+        self.shown = Some((point, length));
+
+        let count = (length / DOT_SPACING + 1).min(DOTS as u32) as i32;
+        let delta = point - anchor;
+        for i in 0..DOTS as i32 {
+            // the first dot sits on the anchor and the last on the cursor
+            let position = anchor + delta * i / (count - 1).max(1);
+            let dot = SpriteBuilder::new(DOT_TILE)
+                .position(position)
+                .hidden(i >= count)
+                .build();
+            write_obj_attributes(FIRST_DOT_SLOT + i as usize, dot);
+        }
+
+        let screen = Rectangle::new(
+            Point::zero(),
+            Point::new(Mode3::WIDTH as i32 - 32, Mode3::HEIGHT as i32 - 8),
+        );
+        let label = SpriteBuilder::new(LABEL_TILE)
+            .strip(Strip::Horizontal)
+            .position(geom::clamp(&screen, point + LABEL_OFFSET))
+            .build();
+        write_obj_attributes(LABEL_SLOT, label);
+    }
+
+    fn hide(&self) {
+        let hidden = SpriteBuilder::new(0).hidden(true).build();
+        for slot in LABEL_SLOT..FIRST_DOT_SLOT + DOTS {
+            write_obj_attributes(slot, hidden);
+        }
+    }
+}
+
+/// Write `length` in white on black into the label tiles
+fn draw_label(length: u32) -> Result<(), TileError> {
+    let style = TextStyleBuilder::new(Font6x8)
+        .text_color(WHITE)
+        .background_color(BLACK)
+        .build();
+    // only the top row of tiles is shown by the 32x8 strip
+    let mut tiles = SpriteTiles::new(ObjSize::S32x32);
+    fmt::draw_number(&mut tiles, length, Point::zero(), style)?;
+    let block = CharBlock::new(CHARACTER_BLOCK)?;
+    for (i, &tile) in tiles.used()[..4].iter().enumerate() {
+        block.set_tile(LABEL_TILE + i, tile)?;
+    }
+    Ok(())
+}
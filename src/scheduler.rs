@@ -0,0 +1,226 @@
+//! Spread whole canvas operations over several frames
+//!
+//! Reading and writing all 38400 Mode3 pixels takes several frames worth of
+//! CPU time, so each call to `PassScheduler::step` only processes pixels
+//! until the frame's `Budget` is exhausted, resuming where it left off on
+//! the next frame.
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+use gba::vram::bitmap::Mode3;
+
+use crate::{budget::Budget, gba_display::Canvas};
+
+const TOTAL: usize = Mode3::WIDTH * Mode3::HEIGHT;
+
+/// Pixel-wise operation over the whole canvas
+pub trait Pass {
+    /// Called for every pixel in row order, return a color to overwrite the pixel with
+    fn pixel(&mut self, point: Point, color: Bgr555) -> Option<Bgr555>;
+
+    /// Called once after the last pixel
+    fn finish(&mut self, _canvas: &mut impl Canvas) {}
+}
+
+impl<F: FnMut(Point, Bgr555) -> Option<Bgr555>> Pass for F {
+    fn pixel(&mut self, point: Point, color: Bgr555) -> Option<Bgr555> {
+        self(point, color)
+    }
+}
+
+/// How far through the canvas a pass is
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn percent(&self) -> u32 {
+        (self.done * 100 / self.total) as u32
+    }
+}
+
+/// Result of running a pass for one frame
+pub enum Step<P> {
+    /// No pass was submitted
+    Idle,
+    Running(Progress),
+    /// The pass processed its last pixel and is handed back
+    Done(P),
+}
+
+/// Runs at most one `Pass` at a time, a frame's budget at a time
+pub struct PassScheduler<P> {
+    pass: Option<P>,
+    next: usize, // index of the next pixel in row order
+}
+
+impl<P: Pass> PassScheduler<P> {
+    pub fn new() -> Self {
+        Self {
+            pass: None,
+            next: 0,
+        }
+    }
+
+    /// Start `pass` from the first pixel, replacing any running pass
+    pub fn submit(&mut self, pass: P) {
+        self.pass = Some(pass);
+        self.next = 0;
+    }
+
+    /// Abandon the running pass, leaving processed pixels as they are
+    pub fn cancel(&mut self) -> Option<P> {
+        self.pass.take()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.pass.is_some()
+    }
+
+    /// The running pass, if any
+    pub fn pass(&self) -> Option<&P> {
+        self.pass.as_ref()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            done: self.next,
+            total: TOTAL,
+        }
+    }
+
+    /// Process pixels of the running pass until `budget` is exhausted
+    pub fn step(&mut self, canvas: &mut impl Canvas, budget: &mut Budget) -> Step<P> {
+        let pass = match self.pass.as_mut() {
+            Some(pass) => pass,
+            None => return Step::Idle,
+        };
+
+        while self.next < TOTAL && !budget.exhausted() {
+            let i = self.next;
+            let point = Point::new((i % Mode3::WIDTH) as i32, (i / Mode3::WIDTH) as i32);
+            if let Some(color) = canvas.read(point) {
+                if let Some(new) = pass.pixel(point, color) {
+                    canvas.write(point, new);
+                }
+            }
+            self.next += 1;
+            budget.spend(1);
+        }
+
+        if self.next < TOTAL {
+            return Step::Running(self.progress());
+        }
+        match self.pass.take() {
+            Some(mut pass) => {
+                pass.finish(canvas);
+                Step::Done(pass)
+            }
+            None => Step::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::BudgetLimit;
+
+    /// Mode3 sized canvas, all black to start
+    struct Screen(Vec<Bgr555>);
+
+    impl Canvas for Screen {
+        fn read(&self, point: Point) -> Option<Bgr555> {
+            let inside = (0..Mode3::WIDTH as i32).contains(&point.x)
+                && (0..Mode3::HEIGHT as i32).contains(&point.y);
+            if inside {
+                Some(self.0[point.y as usize * Mode3::WIDTH + point.x as usize])
+            } else {
+                None
+            }
+        }
+
+        fn write(&mut self, point: Point, color: Bgr555) {
+            self.0[point.y as usize * Mode3::WIDTH + point.x as usize] = color;
+        }
+    }
+
+    /// Paints every pixel white, counting visits in row order
+    struct Counter {
+        visits: Vec<u32>,
+        finished: u32,
+    }
+
+    impl Pass for Counter {
+        fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+            self.visits[point.y as usize * Mode3::WIDTH + point.x as usize] += 1;
+            Some(Bgr555::WHITE)
+        }
+
+        fn finish(&mut self, _canvas: &mut impl Canvas) {
+            self.finished += 1;
+        }
+    }
+
+    #[test]
+    fn pass_splits_across_ticks() {
+        const PER_FRAME: usize = 1000;
+        let mut screen = Screen(vec![Bgr555::BLACK; TOTAL]);
+        let mut scheduler = PassScheduler::new();
+        scheduler.submit(Counter {
+            visits: vec![0; TOTAL],
+            finished: 0,
+        });
+        let mut done = 0;
+        let counter = loop {
+            let mut budget = Budget::start(BudgetLimit::Pixels(PER_FRAME));
+            match scheduler.step(&mut screen, &mut budget) {
+                Step::Running(progress) => {
+                    done += PER_FRAME;
+                    assert_eq!(progress, Progress { done, total: TOTAL });
+                    assert_eq!(scheduler.pass().map(|pass| pass.finished), Some(0));
+                }
+                Step::Done(counter) => break counter,
+                Step::Idle => panic!("idle after {} pixels", done),
+            }
+        };
+        // the last frame only had the remainder left
+        assert_eq!(done, TOTAL / PER_FRAME * PER_FRAME);
+        assert_eq!(counter.finished, 1);
+        assert!(counter.visits.iter().all(|&visits| visits == 1));
+        assert!(screen.0.iter().all(|&color| color == Bgr555::WHITE));
+        assert!(!scheduler.is_running());
+    }
+
+    #[test]
+    fn nothing_submitted_is_idle() {
+        let mut screen = Screen(vec![Bgr555::BLACK; TOTAL]);
+        let mut scheduler: PassScheduler<Counter> = PassScheduler::new();
+        let mut budget = Budget::start(BudgetLimit::Pixels(10));
+        assert!(matches!(
+            scheduler.step(&mut screen, &mut budget),
+            Step::Idle
+        ));
+    }
+
+    #[test]
+    fn cancel_keeps_the_processed_pixels() {
+        let mut screen = Screen(vec![Bgr555::BLACK; TOTAL]);
+        let mut scheduler = PassScheduler::new();
+        scheduler.submit(Counter {
+            visits: vec![0; TOTAL],
+            finished: 0,
+        });
+        let mut budget = Budget::start(BudgetLimit::Pixels(Mode3::WIDTH));
+        scheduler.step(&mut screen, &mut budget);
+        let counter = scheduler.cancel().unwrap();
+        assert_eq!(counter.finished, 0);
+        assert!(screen.0[..Mode3::WIDTH]
+            .iter()
+            .all(|&color| color == Bgr555::WHITE));
+        assert!(screen.0[Mode3::WIDTH..]
+            .iter()
+            .all(|&color| color == Bgr555::BLACK));
+    }
+}
@@ -0,0 +1,169 @@
+//! Power on checks of the hardware the program relies on
+//!
+//! Each check writes known values, reads them back and puts back what was
+//! there, so running them never changes saves or the display. Failures
+//! point at a bad flashcart, a missing save chip or an emulator gap.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    style::TextStyle,
+};
+use gba::{
+    oam::{read_obj_attributes, write_obj_attributes},
+    palram::index_palram_obj_8bpp,
+    save::SaveAccess,
+    vram::{bitmap::Mode3, Tile8bpp},
+    Color,
+};
+
+use crate::{charblock::CharBlock, gba_display::GbaDisplay, sprite::SpriteBuilder};
+
+/// Object palette slot no feature uses
+const SPARE_PALETTE_INDEX: u8 = 255;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Test sprite tile, after the tile editor preview
+const TEST_TILE: usize = 144;
+
+/// Last OAM slot, drawn under every other sprite
+const TEST_SLOT: usize = 127;
+
+/// Where the test sprite shows on the results screen
+const TEST_SPRITE_POSITION: Point = Point::new(120, 8);
+
+/// Alternating bits, a valid Bgr555 color
+const PATTERN: u16 = 0x5AA5;
+
+/// A subsystem that can be checked on its own
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Check {
+    Palette,
+    Vram,
+    Sram,
+    Sprite,
+}
+
+impl Check {
+    pub const ALL: [Self; 4] = [Self::Palette, Self::Vram, Self::Sram, Self::Sprite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Palette => "Palette RAM",
+            Self::Vram => "VRAM",
+            Self::Sram => "SRAM",
+            Self::Sprite => "Sprites",
+        }
+    }
+
+    /// Returns true if the hardware read back what was written
+    pub fn run(self) -> bool {
+        match self {
+            Self::Palette => {
+                let slot = index_palram_obj_8bpp(SPARE_PALETTE_INDEX);
+                let old = slot.read();
+                slot.write(Color(PATTERN));
+                let ok = slot.read() == Color(PATTERN);
+                slot.write(old);
+                ok
+            }
+            Self::Vram => {
+                let (x, y) = (Mode3::WIDTH - 1, Mode3::HEIGHT - 1);
+                let old = Mode3::read(x, y).unwrap_or(Color(0));
+                Mode3::write(x, y, Color(PATTERN));
+                let ok = Mode3::read(x, y) == Some(Color(PATTERN));
+                Mode3::write(x, y, old);
+                ok
+            }
+            Self::Sram => check_sram().unwrap_or(false),
+            Self::Sprite => check_sprite(),
+        }
+    }
+}
+
+/// Result of every check in `Check::ALL` order
+pub fn run_all() -> [(Check, bool); Check::ALL.len()] {
+    let mut results = [(Check::Palette, false); Check::ALL.len()];
+    for (result, &check) in results.iter_mut().zip(Check::ALL.iter()) {
+        *result = (check, check.run());
+    }
+    results
+}
+
+/// Draw a pass or fail line per check over the whole display
+///
+/// The sprite check leaves its sprite showing at the top, so a missing
+/// sprite is visible even if the readback passed. `hide_test_sprite`
+/// removes it.
+pub fn draw(display: &mut GbaDisplay, results: &[(Check, bool)]) -> Result<(), Infallible> {
+    display.clear(Bgr555::BLACK)?;
+    let white = TextStyle::new(Font6x8, Bgr555::WHITE);
+    Text::new("Self test", Point::new(8, 8))
+        .into_styled(white)
+        .draw(display)?;
+    for (i, &(check, ok)) in results.iter().enumerate() {
+        let y = 24 + 10 * i as i32;
+        Text::new(check.name(), Point::new(8, y))
+            .into_styled(white)
+            .draw(display)?;
+        let (text, color) = if ok {
+            ("pass", Bgr555::GREEN)
+        } else {
+            ("FAIL", Bgr555::RED)
+        };
+        Text::new(text, Point::new(96, y))
+            .into_styled(TextStyle::new(Font6x8, color))
+            .draw(display)?;
+    }
+    Text::new("Any key to continue", Point::new(8, 140))
+        .into_styled(white)
+        .draw(display)
+}
+
+pub fn hide_test_sprite() {
+    write_obj_attributes(TEST_SLOT, SpriteBuilder::new(0).hidden(true).build());
+}
+
+/// Flip the last SRAM byte, which the settings leave unused, and flip it back
+fn check_sram() -> Result<bool, gba::save::Error> {
+    let access = SaveAccess::new()?;
+    let offset = access.len() - 1;
+    let mut old = [0];
+    access.read(offset, &mut old)?;
+    let prepared = access.prepare_write(offset..access.len())?;
+    prepared.write(offset, &[!old[0]])?;
+    let mut read = [0];
+    access.read(offset, &mut read)?;
+    prepared.write(offset, &old)?;
+    Ok(read[0] == !old[0])
+}
+
+/// Upload a solid tile, show it and read both back
+fn check_sprite() -> bool {
+    let block = match CharBlock::new(CHARACTER_BLOCK) {
+        Ok(block) => block,
+        Err(_) => return false,
+    };
+    // every pixel is palette slot 8, white
+    let tile = Tile8bpp([0x0808_0808; 16]);
+    if block.set_tile(TEST_TILE, tile).is_err() {
+        return false;
+    }
+    let tile_ok = block
+        .get_tile(TEST_TILE)
+        .map_or(false, |read| read.0 == tile.0);
+
+    let attributes = SpriteBuilder::new(TEST_TILE)
+        .position(TEST_SPRITE_POSITION)
+        .build();
+    write_obj_attributes(TEST_SLOT, attributes);
+    let oam_ok = read_obj_attributes(TEST_SLOT).map_or(false, |read| {
+        read.attr2.tile_id() == attributes.attr2.tile_id()
+            && read.attr1.col_coordinate() == TEST_SPRITE_POSITION.x as u16
+            && read.attr0.row_coordinate() == TEST_SPRITE_POSITION.y as u16
+    });
+    tile_ok && oam_ok
+}
@@ -0,0 +1,122 @@
+//! Preferences kept in SRAM between sessions
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+};
+
+use crate::{checksum, tile_editor::PACKED_LEN};
+
+const MAGIC: [u8; 4] = *b"AMS2";
+
+/// Bytes reserved for settings at the end of SRAM
+pub const LEN: usize = 64;
+
+/// Fletcher-16 of the bytes before it, u16 LE in the last two bytes
+const CHECKSUM: usize = LEN - 2;
+
+/// What the canvas shows when painting starts
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BootBehavior {
+    /// Fill the canvas with a color
+    FreshClear(Bgr555),
+    /// Load the canvas saved in SRAM, or the image if there is none
+    RestoreSaved,
+    /// Draw the background image
+    ShowImage,
+}
+
+/// Persistent preferences, defaults are used when SRAM holds none
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Settings {
+    /// Cursor position when the canvas was last saved
+    pub cursor: Option<Point>,
+    pub boot: BootBehavior,
+    /// Custom brush stamp made in the tile editor, see `TileCells::pack`
+    pub stamp_tile: Option<[u8; PACKED_LEN]>,
+    /// The first run tutorial was finished or skipped
+    pub tutorial_done: bool,
+    /// Index of the background tune, `None` is muted
+    pub music: Option<u8>,
+    /// Music volume from 0 to 7
+    pub music_volume: u8,
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Self {
+            cursor: None,
+            boot: BootBehavior::RestoreSaved,
+            stamp_tile: None,
+            tutorial_done: false,
+            music: None,
+            music_volume: 5,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; LEN] {
+        let mut bytes = [0; LEN];
+        bytes[..4].copy_from_slice(&MAGIC);
+        if let Some(cursor) = self.cursor {
+            bytes[4] = 1;
+            bytes[5] = cursor.x as u8;
+            bytes[6] = cursor.y as u8;
+        }
+        match self.boot {
+            BootBehavior::RestoreSaved => bytes[7] = 0,
+            BootBehavior::ShowImage => bytes[7] = 1,
+            BootBehavior::FreshClear(color) => {
+                bytes[7] = 2;
+                bytes[8..10].copy_from_slice(&color.into_storage().to_le_bytes());
+            }
+        }
+        if let Some(tile) = self.stamp_tile {
+            bytes[10] = 1;
+            bytes[16..16 + PACKED_LEN].copy_from_slice(&tile);
+        }
+        bytes[11] = self.tutorial_done as u8;
+        bytes[12] = self.music.map_or(0, |track| track + 1);
+        bytes[13] = self.music_volume;
+        let sum = checksum::fletcher16(&bytes[..CHECKSUM]);
+        bytes[CHECKSUM..].copy_from_slice(&sum.to_le_bytes());
+        bytes
+    }
+
+    /// Parse saved settings, `None` if the bytes weren't written by `encode`
+    /// or were corrupted since
+    pub fn decode(bytes: &[u8; LEN]) -> Option<Self> {
+        let sum = u16::from_le_bytes([bytes[CHECKSUM], bytes[CHECKSUM + 1]]);
+        if bytes[..4] != MAGIC || sum != checksum::fletcher16(&bytes[..CHECKSUM]) {
+            return None;
+        }
+        let cursor = match bytes[4] {
+            1 => Some(Point::new(bytes[5] as i32, bytes[6] as i32)),
+            _ => None,
+        };
+        let boot = match bytes[7] {
+            0 => BootBehavior::RestoreSaved,
+            1 => BootBehavior::ShowImage,
+            2 => {
+                let raw = RawU16::new(u16::from_le_bytes([bytes[8], bytes[9]]));
+                BootBehavior::FreshClear(raw.into())
+            }
+            _ => return None,
+        };
+        let stamp_tile = match bytes[10] {
+            1 => {
+                let mut tile = [0; PACKED_LEN];
+                tile.copy_from_slice(&bytes[16..16 + PACKED_LEN]);
+                Some(tile)
+            }
+            _ => None,
+        };
+        Some(Self {
+            cursor,
+            boot,
+            stamp_tile,
+            tutorial_done: bytes[11] == 1,
+            music: bytes[12].checked_sub(1),
+            music_volume: bytes[13],
+        })
+    }
+}
@@ -0,0 +1,56 @@
+//! Brief jolt of the cursor sprite for actions like clearing the canvas
+//!
+//! Bitmap modes show BG2 through its affine reference point, which ignores
+//! the scroll registers, so the canvas itself stays still and the offset
+//! moves the sprite layer instead.
+
+use embedded_graphics::prelude::*;
+
+use crate::rng::{self, Rng};
+
+/// How long and how far a shake moves
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shake {
+    pub frames: u8,
+    /// Largest offset in pixels, in the first frame
+    pub intensity: u8,
+}
+
+/// Random offsets that shrink to zero over the shake
+pub struct ScreenShake {
+    shake: Option<Shake>,
+    rng: Rng,
+    left: u8,
+}
+
+impl ScreenShake {
+    /// `None` never shakes, for players who'd rather not see it
+    pub const fn new(shake: Option<Shake>) -> Self {
+        Self {
+            shake,
+            rng: Rng::new(0),
+            left: 0,
+        }
+    }
+
+    /// Shake from full intensity, restarting any shake in progress
+    pub fn start(&mut self) {
+        if let Some(shake) = self.shake {
+            self.left = shake.frames;
+            self.rng = rng::fork();
+        }
+    }
+
+    /// Offset for this frame, call once per frame
+    ///
+    /// Always exactly zero once the shake is over.
+    pub fn tick(&mut self) -> Point {
+        let shake = match self.shake {
+            Some(shake) if self.left > 0 => shake,
+            _ => return Point::zero(),
+        };
+        let reach = (shake.intensity as u32 * self.left as u32 / shake.frames as u32) as u8;
+        self.left -= 1;
+        Point::new(self.rng.offset(reach), self.rng.offset(reach))
+    }
+}
@@ -0,0 +1,195 @@
+//! Every embedded-graphics primitive drawn on one screen
+//!
+//! Doubles as a visual check of the display's `DrawTarget`, several shapes
+//! hang off the screen edges to exercise clipping.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font12x16, Font6x12, Font6x8, Font8x16, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::{Circle, Line, Rectangle, Triangle},
+    style::{PrimitiveStyle, PrimitiveStyleBuilder, TextStyle},
+};
+
+use crate::gba_display::GbaDisplay;
+
+#[derive(Debug, Copy, Clone)]
+enum Font {
+    F6x8,
+    F6x12,
+    F8x16,
+    F12x16,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Shape {
+    Line(Point, Point),
+    Rectangle(Point, Point),
+    Circle(Point, u32),
+    Triangle(Point, Point, Point),
+    Text(&'static str, Point, Font),
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Fill {
+    Stroke,
+    Solid,
+    /// Filled with a white outline
+    Outlined,
+}
+
+/// Shape, color and fill for each item, add or remove rows freely
+const ITEMS: [(Shape, Bgr555, Fill); 18] = [
+    // lines in several directions from one point
+    (
+        Shape::Line(Point::new(40, 40), Point::new(70, 30)),
+        Bgr555::RED,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Line(Point::new(40, 40), Point::new(50, 10)),
+        Bgr555::GREEN,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Line(Point::new(40, 40), Point::new(10, 20)),
+        Bgr555::BLUE,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Line(Point::new(40, 40), Point::new(20, 70)),
+        Bgr555::YELLOW,
+        Fill::Stroke,
+    ),
+    // rectangles
+    (
+        Shape::Rectangle(Point::new(80, 10), Point::new(110, 30)),
+        Bgr555::CYAN,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Rectangle(Point::new(90, 20), Point::new(120, 40)),
+        Bgr555::MAGENTA,
+        Fill::Outlined,
+    ),
+    // circles
+    (
+        Shape::Circle(Point::new(150, 25), 15),
+        Bgr555::GREEN,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Circle(Point::new(190, 25), 12),
+        Bgr555::RED,
+        Fill::Solid,
+    ),
+    // triangles
+    (
+        Shape::Triangle(Point::new(80, 70), Point::new(110, 50), Point::new(120, 85)),
+        Bgr555::YELLOW,
+        Fill::Outlined,
+    ),
+    (
+        Shape::Triangle(
+            Point::new(140, 50),
+            Point::new(170, 85),
+            Point::new(130, 85),
+        ),
+        Bgr555::BLUE,
+        Fill::Stroke,
+    ),
+    // clipped at the screen edges
+    (
+        Shape::Circle(Point::new(0, 159), 20),
+        Bgr555::CYAN,
+        Fill::Solid,
+    ),
+    (
+        Shape::Rectangle(Point::new(220, -10), Point::new(260, 30)),
+        Bgr555::BLUE,
+        Fill::Outlined,
+    ),
+    (
+        Shape::Line(Point::new(200, 170), Point::new(250, 120)),
+        Bgr555::WHITE,
+        Fill::Stroke,
+    ),
+    // text in each font
+    (
+        Shape::Text("Font6x8", Point::new(10, 100), Font::F6x8),
+        Bgr555::WHITE,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Text("Font6x12", Point::new(10, 110), Font::F6x12),
+        Bgr555::WHITE,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Text("Font8x16", Point::new(10, 125), Font::F8x16),
+        Bgr555::WHITE,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Text("12x16", Point::new(100, 120), Font::F12x16),
+        Bgr555::YELLOW,
+        Fill::Stroke,
+    ),
+    (
+        Shape::Text("Clipped", Point::new(200, 140), Font::F8x16),
+        Bgr555::GREEN,
+        Fill::Stroke,
+    ),
+];
+
+/// Clear the display and draw every item
+pub fn draw(display: &mut GbaDisplay) -> Result<(), Infallible> {
+    display.clear(Bgr555::BLACK)?;
+    for &(shape, color, fill) in ITEMS.iter() {
+        draw_shape(display, shape, color, fill)?;
+    }
+    Ok(())
+}
+
+fn draw_shape(
+    display: &mut GbaDisplay,
+    shape: Shape,
+    color: Bgr555,
+    fill: Fill,
+) -> Result<(), Infallible> {
+    let style = match fill {
+        Fill::Stroke => PrimitiveStyle::with_stroke(color, 1),
+        Fill::Solid => PrimitiveStyle::with_fill(color),
+        Fill::Outlined => PrimitiveStyleBuilder::new()
+            .fill_color(color)
+            .stroke_color(Bgr555::WHITE)
+            .stroke_width(1)
+            .build(),
+    };
+    match shape {
+        Shape::Line(start, end) => Line::new(start, end).into_styled(style).draw(display),
+        Shape::Rectangle(a, b) => Rectangle::new(a, b).into_styled(style).draw(display),
+        Shape::Circle(center, radius) => {
+            Circle::new(center, radius).into_styled(style).draw(display)
+        }
+        Shape::Triangle(a, b, c) => Triangle::new(a, b, c).into_styled(style).draw(display),
+        Shape::Text(text, point, font) => {
+            let text = Text::new(text, point);
+            match font {
+                Font::F6x8 => text
+                    .into_styled(TextStyle::new(Font6x8, color))
+                    .draw(display),
+                Font::F6x12 => text
+                    .into_styled(TextStyle::new(Font6x12, color))
+                    .draw(display),
+                Font::F8x16 => text
+                    .into_styled(TextStyle::new(Font8x16, color))
+                    .draw(display),
+                Font::F12x16 => text
+                    .into_styled(TextStyle::new(Font12x16, color))
+                    .draw(display),
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Save slot screen, drawn over a snapshot of the canvas like the viewer
+//!
+//! Under the list, each slot's thumbnail is drawn in a row, framed in the
+//! list's colors.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::Rectangle,
+    style::{PrimitiveStyle, TextStyle},
+};
+
+use crate::{
+    fmt,
+    gba_display::GbaDisplay,
+    input::{Input, Key},
+    storage::{self, SlotInfo, AUTOSAVE_SLOT, SLOTS},
+    thumbnail,
+};
+
+const LINE_HEIGHT: i32 = 10;
+const ORIGIN: Point = Point::new(8, 8);
+const DIM: Bgr555 = Bgr555::new(16, 16, 16);
+
+/// Thumbnails are drawn at twice their size
+const THUMBNAIL_SCALE: i32 = 2;
+const THUMBNAIL_ORIGIN: Point = Point::new(8, 8 + LINE_HEIGHT * (SLOTS as i32 + 4));
+const THUMBNAIL_SPACING: i32 = thumbnail::WIDTH as i32 * THUMBNAIL_SCALE + 8;
+
+/// What to do with the selected slot
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SlotAction {
+    /// A replaces the canvas with the slot
+    Load(usize),
+    /// Start saves the canvas into the slot
+    Save(usize),
+    /// B goes back to painting
+    Close,
+}
+
+/// List of slots with the names and sizes of their canvases
+pub struct SlotScreen {
+    selected: usize,
+    slots: [Option<SlotInfo>; SLOTS],
+}
+
+impl SlotScreen {
+    pub const fn new() -> Self {
+        Self {
+            selected: 0,
+            slots: [None; SLOTS],
+        }
+    }
+
+    /// Read every slot header and draw the list over the whole display
+    pub fn open(&mut self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        self.slots = storage::list_slots().unwrap_or([None; SLOTS]);
+        display.clear(Bgr555::BLACK)?;
+        Text::new("Save slots", ORIGIN)
+            .into_styled(TextStyle::new(Font6x8, Bgr555::YELLOW))
+            .draw(display)?;
+        for i in 0..SLOTS {
+            self.draw_slot(display, i)?;
+        }
+        let help = ORIGIN + Point::new(0, LINE_HEIGHT * (SLOTS as i32 + 2));
+        Text::new("A: Load  Start: Save  B: Back", help)
+            .into_styled(TextStyle::new(Font6x8, DIM))
+            .draw(display)?;
+        for i in 0..SLOTS {
+            let thumbnail = match self.slots[i] {
+                Some(_) => storage::load_thumbnail(i).ok().flatten(),
+                None => None,
+            };
+            if let Some(thumbnail) = thumbnail {
+                thumbnail::draw(display, &thumbnail, thumbnail_top_left(i), THUMBNAIL_SCALE)?;
+            }
+            self.draw_frame(display, i)?;
+        }
+        Ok(())
+    }
+
+    /// Move the selection with up and down, returns the chosen action
+    pub fn update(
+        &mut self,
+        input: &Input,
+        display: &mut GbaDisplay,
+    ) -> Result<Option<SlotAction>, Infallible> {
+        let previous = self.selected;
+        if input.just_pressed(Key::Up) {
+            self.selected = (self.selected + SLOTS - 1) % SLOTS;
+        }
+        if input.just_pressed(Key::Down) {
+            self.selected = (self.selected + 1) % SLOTS;
+        }
+        if self.selected != previous {
+            self.draw_slot(display, previous)?;
+            self.draw_slot(display, self.selected)?;
+            self.draw_frame(display, previous)?;
+            self.draw_frame(display, self.selected)?;
+        }
+
+        Ok(if input.just_pressed(Key::A) {
+            Some(SlotAction::Load(self.selected))
+        } else if input.just_pressed(Key::Start) {
+            Some(SlotAction::Save(self.selected))
+        } else if input.just_pressed(Key::B) {
+            Some(SlotAction::Close)
+        } else {
+            None
+        })
+    }
+
+    /// "1 Autosave 3KB", or "1 empty"; the same length in either color so no erasing is needed
+    fn draw_slot(&self, display: &mut GbaDisplay, i: usize) -> Result<(), Infallible> {
+        let color = if i == self.selected {
+            Bgr555::WHITE
+        } else {
+            DIM
+        };
+        let style = TextStyle::new(Font6x8, color);
+        let point = ORIGIN + Point::new(0, LINE_HEIGHT * (i as i32 + 1) + 4);
+        fmt::draw_number(display, i as u32 + 1, point, style)?;
+        let point = point + Point::new(12, 0);
+        match self.slots[i] {
+            Some(info) => {
+                Text::new(info.name(), point)
+                    .into_styled(style)
+                    .draw(display)?;
+                let mut buf = [0; 12];
+                let kb = (info.len as u32 + 1023) / 1024;
+                let size = fmt::with_number(&mut buf, "", kb, "KB").unwrap_or_default();
+                Text::new(size, point + Point::new(60, 0))
+                    .into_styled(style)
+                    .draw(display)
+            }
+            None if i == AUTOSAVE_SLOT => Text::new("no autosave", point)
+                .into_styled(style)
+                .draw(display),
+            None => Text::new("empty", point).into_styled(style).draw(display),
+        }
+    }
+
+    /// Outline around thumbnail `i`, white when it's selected
+    fn draw_frame(&self, display: &mut GbaDisplay, i: usize) -> Result<(), Infallible> {
+        let color = if i == self.selected {
+            Bgr555::WHITE
+        } else {
+            DIM
+        };
+        let top_left = thumbnail_top_left(i);
+        let size = Point::new(thumbnail::WIDTH as i32, thumbnail::HEIGHT as i32) * THUMBNAIL_SCALE;
+        Rectangle::new(top_left - Point::new(1, 1), top_left + size)
+            .into_styled(PrimitiveStyle::with_stroke(color, 1))
+            .draw(display)
+    }
+}
+
+fn thumbnail_top_left(i: usize) -> Point {
+    THUMBNAIL_ORIGIN + Point::new(THUMBNAIL_SPACING * i as i32, 0)
+}
@@ -0,0 +1,189 @@
+//! Pull the cursor onto the loose ends of drawn strokes
+//!
+//! A loose end is a pixel in one of the palette colors with exactly one
+//! neighbor of the same color, the first or last pixel of a one pixel wide
+//! line. Limiting it to palette colors keeps the grain of a photo from
+//! counting as ends, at the cost of missing strokes in picked colors.
+//! Finding them reads the pixels around the cursor, about 750 reads at the
+//! default radius.
+
+use embedded_graphics::{
+    pixelcolor::Bgr555, prelude::*, primitives::Rectangle, style::PrimitiveStyle,
+};
+use gba::oam::write_obj_attributes;
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::{Canvas, PaletteColor},
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Yellow, see `register_palette`
+const PALETTE_INDEX: u8 = 5;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Marker ring, after the profiler bar
+const MARKER_TILE: usize = 153;
+
+/// Shared with the self test sprite, which is hidden before painting starts
+const MARKER_SLOT: usize = 127;
+
+/// Check if `point` is a loose end of a stroke in one of `colors`
+pub fn is_end(canvas: &impl Canvas, point: Point, colors: &[Bgr555]) -> bool {
+    let color = match canvas.read(point) {
+        Some(color) if colors.contains(&color) => color,
+        _ => return false,
+    };
+    let mut same = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let neighbor = point + Point::new(dx, dy);
+            if neighbor != point && canvas.read(neighbor) == Some(color) {
+                same += 1;
+            }
+        }
+    }
+    same == 1
+}
+
+/// Loose end closest to `center` within `radius` pixels along each axis,
+/// ties go to the first in row order
+pub fn nearest_end(
+    canvas: &impl Canvas,
+    center: Point,
+    radius: i32,
+    colors: &[Bgr555],
+) -> Option<Point> {
+    let mut best: Option<(i32, Point)> = None;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let point = center + Point::new(dx, dy);
+            let distance = dx * dx + dy * dy;
+            if best.map_or(true, |(closest, _)| distance < closest) && is_end(canvas, point, colors)
+            {
+                best = Some((distance, point));
+            }
+        }
+    }
+    best.map(|(_, point)| point)
+}
+
+/// Ring around the end the cursor snapped to
+pub struct SnapMarker {
+    target: Option<Point>,
+}
+
+impl SnapMarker {
+    /// Draw the ring into sprite tile memory, it starts hidden
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let mut ring = blank_tile();
+        Rectangle::new(Point::new(1, 1), Point::new(5, 5))
+            .into_styled(PrimitiveStyle::with_stroke(
+                PaletteColor::new(PALETTE_INDEX),
+                1,
+            ))
+            .draw(&mut ring)?;
+        block.set_tile(MARKER_TILE, ring)?;
+        Ok(Self { target: None })
+    }
+
+    /// Ring `target`, `None` hides the ring
+    pub fn show(&mut self, target: Option<Point>) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        let marker = SpriteBuilder::new(MARKER_TILE)
+            .position(target.unwrap_or_else(Point::zero) - Point::new(3, 3))
+            .hidden(target.is_none())
+            .build();
+        write_obj_attributes(MARKER_SLOT, marker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: [Bgr555; 2] = [Bgr555::BLACK, Bgr555::RED];
+
+    /// White 32x32 canvas with a few strokes on it
+    struct Sketch(Vec<(Point, Bgr555)>);
+
+    impl Canvas for Sketch {
+        fn read(&self, point: Point) -> Option<Bgr555> {
+            if !(0..32).contains(&point.x) || !(0..32).contains(&point.y) {
+                return None;
+            }
+            let stroke = self.0.iter().find(|&&(drawn, _)| drawn == point);
+            Some(stroke.map_or(Bgr555::WHITE, |&(_, color)| color))
+        }
+
+        fn write(&mut self, point: Point, color: Bgr555) {
+            self.0.push((point, color));
+        }
+    }
+
+    /// Horizontal stroke from `x` to `x + length - 1` on row `y`
+    fn stroke(sketch: &mut Sketch, x: i32, y: i32, length: i32, color: Bgr555) {
+        for i in 0..length {
+            sketch.write(Point::new(x + i, y), color);
+        }
+    }
+
+    #[test]
+    fn only_the_ends_of_a_stroke_are_loose() {
+        let mut sketch = Sketch(Vec::new());
+        stroke(&mut sketch, 10, 10, 5, Bgr555::BLACK);
+        let ends: Vec<i32> = (8..17)
+            .filter(|&x| is_end(&sketch, Point::new(x, 10), &COLORS))
+            .collect();
+        assert_eq!(ends, [10, 14]);
+    }
+
+    #[test]
+    fn strokes_in_other_colors_have_no_ends() {
+        let mut sketch = Sketch(Vec::new());
+        stroke(&mut sketch, 10, 10, 5, Bgr555::BLUE);
+        assert_eq!(nearest_end(&sketch, Point::new(12, 12), 4, &COLORS), None);
+    }
+
+    #[test]
+    fn snaps_to_the_closest_end() {
+        let mut sketch = Sketch(Vec::new());
+        stroke(&mut sketch, 10, 10, 5, Bgr555::BLACK);
+        stroke(&mut sketch, 10, 20, 3, Bgr555::RED);
+        assert_eq!(
+            nearest_end(&sketch, Point::new(15, 12), 8, &COLORS),
+            Some(Point::new(14, 10))
+        );
+        assert_eq!(
+            nearest_end(&sketch, Point::new(9, 18), 8, &COLORS),
+            Some(Point::new(10, 20))
+        );
+    }
+
+    #[test]
+    fn ends_outside_the_radius_are_ignored() {
+        let mut sketch = Sketch(Vec::new());
+        stroke(&mut sketch, 10, 10, 5, Bgr555::BLACK);
+        let center = Point::new(14, 14);
+        assert_eq!(nearest_end(&sketch, center, 3, &COLORS), None);
+        assert_eq!(
+            nearest_end(&sketch, center, 4, &COLORS),
+            Some(Point::new(14, 10))
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_first_end_in_row_order() {
+        let mut sketch = Sketch(Vec::new());
+        stroke(&mut sketch, 10, 10, 5, Bgr555::BLACK);
+        assert_eq!(
+            nearest_end(&sketch, Point::new(12, 10), 4, &COLORS),
+            Some(Point::new(10, 10))
+        );
+    }
+}
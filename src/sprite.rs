@@ -0,0 +1,346 @@
+//! Object attribute and sprite tile helpers
+
+use core::convert::{Infallible, TryInto};
+use embedded_graphics::{drawable::Pixel, geometry::Size, prelude::*};
+use gba::{
+    oam::{
+        AffineParameters, OBJAttr0, OBJAttr1, OBJAttr2, ObjectAttributes, ObjectRender, ObjectShape,
+    },
+    vram::Tile8bpp,
+};
+
+use crate::gba_display::PaletteColor;
+
+/// Square sprite sizes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ObjSize {
+    S8x8,
+    S16x16,
+    S32x32,
+}
+
+impl ObjSize {
+    /// Width and height in pixels
+    pub fn pixels(self) -> u32 {
+        8 << self as u32
+    }
+
+    /// Number of 8x8 tiles the sprite uses
+    pub fn tiles(self) -> usize {
+        let side = (self.pixels() / 8) as usize;
+        side * side
+    }
+
+    /// OBJAttr1 size bits for a square shape
+    fn size_bits(self) -> u16 {
+        self as u16
+    }
+}
+
+/// Thin sprite shapes, 4 tiles in a row or column
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Strip {
+    /// 32x8
+    Horizontal,
+    /// 8x32
+    Vertical,
+}
+
+/// OAM tile id of 8bpp tile `index` in character block 5, 8bpp tiles take two ids
+pub fn tile_id(index: usize) -> u16 {
+    512 + 2 * index as u16
+}
+
+/// Builds the attributes of an 8bpp sprite
+#[derive(Debug, Copy, Clone)]
+pub struct SpriteBuilder {
+    attr0: OBJAttr0,
+    attr1: OBJAttr1,
+    attr2: OBJAttr2,
+}
+
+impl SpriteBuilder {
+    /// An 8x8 sprite at the origin showing 8bpp tile `index` of character block 5
+    pub fn new(index: usize) -> Self {
+        Self {
+            attr0: OBJAttr0::new().with_is_8bpp(true),
+            attr1: OBJAttr1::new(),
+            attr2: OBJAttr2::new().with_tile_id(tile_id(index)),
+        }
+    }
+
+    /// Place the top left corner at `point`, wrapping off screen coordinates like hardware does
+    pub fn position(mut self, point: Point) -> Self {
+        self.attr0 = self.attr0.with_row_coordinate(point.y as u16 & 0xFF);
+        self.attr1 = self.attr1.with_col_coordinate(point.x as u16 & 0x1FF);
+        self
+    }
+
+    /// Set the shape and size bits, tiles are laid out row by row with 1D mapping
+    pub fn size(mut self, size: ObjSize) -> Self {
+        self.attr0 = self.attr0.with_obj_shape(ObjectShape::Square);
+        self.attr1 = self.attr1.with_obj_size(size.size_bits());
+        self
+    }
+
+    /// Use a 32x8 or 8x32 shape instead of a square
+    pub fn strip(mut self, strip: Strip) -> Self {
+        let shape = match strip {
+            Strip::Horizontal => ObjectShape::Horizontal,
+            Strip::Vertical => ObjectShape::Vertical,
+        };
+        self.attr0 = self.attr0.with_obj_shape(shape);
+        self.attr1 = self.attr1.with_obj_size(1);
+        self
+    }
+
+    /// Mirror the sprite, e.g. to draw four quadrants from one set of tiles
+    pub fn flip(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.attr1 = self.attr1.with_hflip(horizontal).with_vflip(vertical);
+        self
+    }
+
+    /// Transform the sprite with affine parameter group `index`
+    ///
+    /// `double_size` draws into a box twice as wide and tall, centered on the
+    /// same point, so rotated corners aren't clipped. Affine sprites can't flip,
+    /// the index shares the flip bits.
+    pub fn affine(mut self, index: u8, double_size: bool) -> Self {
+        let render = if double_size {
+            ObjectRender::DoubleAffine
+        } else {
+            ObjectRender::Affine
+        };
+        self.attr0 = self.attr0.with_obj_rendering(render);
+        self.attr1 = self.attr1.with_affine_index(index as u16 & 0x1F);
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        if hidden {
+            self.attr0 = self.attr0.with_obj_rendering(ObjectRender::Disabled);
+        }
+        self
+    }
+
+    pub fn build(self) -> ObjectAttributes {
+        ObjectAttributes {
+            attr0: self.attr0,
+            attr1: self.attr1,
+            attr2: self.attr2,
+        }
+    }
+}
+
+/// Quarter sine wave in 256ths, indexed by angle in 256ths of a turn
+#[rustfmt::skip]
+const SINE: [i16; 65] = [
+    0, 6, 13, 19, 25, 31, 38, 44, 50, 56, 62, 68, 74, 80, 86, 92,
+    98, 104, 109, 115, 121, 126, 132, 137, 142, 147, 152, 157, 162, 167, 172, 177,
+    181, 185, 190, 194, 198, 202, 206, 209, 213, 216, 220, 223, 226, 229, 231, 234,
+    237, 239, 241, 243, 245, 247, 248, 250, 251, 252, 253, 254, 255, 255, 256, 256,
+    256,
+];
+
+/// Sine of `angle` in 256ths of a turn, as 8.8 fixed point
+fn sin(angle: u8) -> i16 {
+    let quarter = (angle % 64) as usize;
+    match angle / 64 {
+        0 => SINE[quarter],
+        1 => SINE[64 - quarter],
+        2 => -SINE[quarter],
+        _ => -SINE[64 - quarter],
+    }
+}
+
+fn cos(angle: u8) -> i16 {
+    sin(angle.wrapping_add(64))
+}
+
+/// Affine parameters turning a sprite clockwise by `angle` in 256ths of a turn
+///
+/// The parameters map screen pixels back to texture pixels, so they hold the
+/// inverse rotation.
+pub fn rotation(angle: u8) -> AffineParameters {
+    let (sin, cos) = (sin(angle), cos(angle));
+    AffineParameters {
+        pa: cos,
+        pb: sin,
+        pc: -sin,
+        pd: cos,
+    }
+}
+
+/// Where `offset` from a sprite's center lands once turned by `angle`, see `rotation`
+pub fn rotate(offset: Point, angle: u8) -> Point {
+    let (sin, cos) = (sin(angle) as i32, cos(angle) as i32);
+    Point::new(
+        (offset.x * cos - offset.y * sin) / 256,
+        (offset.x * sin + offset.y * cos) / 256,
+    )
+}
+
+/// Tile with every pixel transparent
+pub fn blank_tile() -> Tile8bpp {
+    Tile8bpp([PaletteColor::TANSPARENT.into_storage().into(); 16])
+}
+
+/// Tiles in the largest supported sprite
+const MAX_TILES: usize = 16;
+
+/// Draw target covering the tiles of a square sprite in 1D mapping order
+pub struct SpriteTiles {
+    tiles: [Tile8bpp; MAX_TILES],
+    size: ObjSize,
+}
+
+impl SpriteTiles {
+    pub fn new(size: ObjSize) -> Self {
+        Self {
+            tiles: [blank_tile(); MAX_TILES],
+            size,
+        }
+    }
+
+    /// The tiles actually used by the sprite size
+    pub fn used(&self) -> &[Tile8bpp] {
+        &self.tiles[..self.size.tiles()]
+    }
+}
+
+impl DrawTarget<PaletteColor> for SpriteTiles {
+    type Error = Infallible;
+
+    /// Draw a `pixel` into whichever tile covers it
+    fn draw_pixel(&mut self, pixel: Pixel<PaletteColor>) -> Result<(), Self::Error> {
+        let side = self.size.pixels();
+        if let Ok((x, y)) = pixel.0.try_into() {
+            let (x, y): (u32, u32) = (x, y);
+            if x < side && y < side {
+                let index = (x / 8 + (y / 8) * (side / 8)) as usize;
+                let local = Point::new((x % 8) as i32, (y % 8) as i32);
+                self.tiles[index].draw_pixel(Pixel(local, pixel.1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> Size {
+        Size::new(self.size.pixels(), self.size.pixels())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Corners of a sprite `2 * half` pixels wide, as offsets from its center
+    fn corners(half: i32) -> [Point; 4] {
+        [
+            Point::new(-half, -half),
+            Point::new(half, -half),
+            Point::new(-half, half),
+            Point::new(half, half),
+        ]
+    }
+
+    #[test]
+    fn sine_table_hits_the_axes() {
+        assert_eq!((sin(0), cos(0)), (0, 256));
+        assert_eq!((sin(64), cos(64)), (256, 0));
+        assert_eq!((sin(128), cos(128)), (0, -256));
+        assert_eq!((sin(192), cos(192)), (-256, 0));
+    }
+
+    #[test]
+    fn quarter_turn_is_clockwise_on_screen() {
+        let right = Point::new(8, 0);
+        assert_eq!(rotate(right, 0), right);
+        assert_eq!(rotate(right, 64), Point::new(0, 8));
+        assert_eq!(rotate(right, 128), Point::new(-8, 0));
+        assert_eq!(rotate(right, 192), Point::new(0, -8));
+    }
+
+    #[test]
+    fn rotated_corners_stay_inside_the_double_size_box() {
+        for &half in &[4, 8, 16] {
+            for angle in 0..=255 {
+                for &corner in corners(half).iter() {
+                    let turned = rotate(corner, angle);
+                    assert!(
+                        turned.x.abs() <= 2 * half && turned.y.abs() <= 2 * half,
+                        "size {} angle {} corner {:?}",
+                        2 * half,
+                        angle,
+                        corner
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corners_at_45_degrees_need_the_double_size_box() {
+        let turned = rotate(Point::new(8, 8), 32);
+        assert_eq!(turned, Point::new(0, 11));
+        assert!(turned.y > 8);
+    }
+
+    #[test]
+    fn parameters_map_turned_pixels_back() {
+        for &angle in &[0, 32, 64, 100, 200] {
+            let params = rotation(angle);
+            for &corner in corners(8).iter() {
+                let turned = rotate(corner, angle);
+                let back = Point::new(
+                    (params.pa as i32 * turned.x + params.pb as i32 * turned.y) / 256,
+                    (params.pc as i32 * turned.x + params.pd as i32 * turned.y) / 256,
+                );
+                let error = back - corner;
+                assert!(error.x.abs() <= 1 && error.y.abs() <= 1, "angle {}", angle);
+            }
+        }
+    }
+
+    #[test]
+    fn square_sizes_set_shape_and_size_bits() {
+        let expected = [
+            (ObjSize::S8x8, 0, 1),
+            (ObjSize::S16x16, 1, 4),
+            (ObjSize::S32x32, 2, 16),
+        ];
+        for &(size, bits, tiles) in expected.iter() {
+            let sprite = SpriteBuilder::new(0).size(size).build();
+            assert!(matches!(sprite.attr0.obj_shape(), ObjectShape::Square));
+            assert_eq!(sprite.attr1.obj_size(), bits, "{:?}", size);
+            assert_eq!(size.tiles(), tiles, "{:?}", size);
+        }
+    }
+
+    #[test]
+    fn strips_are_four_tiles_long() {
+        // size 1 of the wide and tall shapes is 32x8 and 8x32
+        let horizontal = SpriteBuilder::new(0).strip(Strip::Horizontal).build();
+        assert!(matches!(
+            horizontal.attr0.obj_shape(),
+            ObjectShape::Horizontal
+        ));
+        assert_eq!(horizontal.attr1.obj_size(), 1);
+        let vertical = SpriteBuilder::new(0).strip(Strip::Vertical).build();
+        assert!(matches!(vertical.attr0.obj_shape(), ObjectShape::Vertical));
+        assert_eq!(vertical.attr1.obj_size(), 1);
+    }
+
+    #[test]
+    fn size_bits_leave_position_and_tile_alone() {
+        let point = Point::new(100, 60);
+        let sprite = SpriteBuilder::new(3)
+            .position(point)
+            .size(ObjSize::S32x32)
+            .build();
+        assert_eq!(sprite.attr0.row_coordinate(), 60);
+        assert_eq!(sprite.attr1.col_coordinate(), 100);
+        assert_eq!(sprite.attr2.tile_id(), tile_id(3));
+        assert!(sprite.attr0.is_8bpp());
+    }
+}
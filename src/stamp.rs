@@ -0,0 +1,84 @@
+//! Brush tips as 8x8 bitmasks
+
+use embedded_graphics::prelude::*;
+
+/// Shape painted by each brush dab, centered on row and column 3
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stamp {
+    pub name: &'static str,
+    /// Bit 7 of each row is the leftmost pixel
+    rows: [u8; 8],
+}
+
+impl Stamp {
+    /// Built in stamps in the order they are cycled, the single pixel first
+    pub const ALL: [Self; 5] = [
+        Self::new("Dot", [0, 0, 0, 0b0001_0000, 0, 0, 0, 0]),
+        Self::new(
+            "Square",
+            [0, 0, 0b0011_1000, 0b0011_1000, 0b0011_1000, 0, 0, 0],
+        ),
+        Self::new(
+            "Round",
+            [
+                0,
+                0b0011_1000,
+                0b0111_1100,
+                0b0111_1100,
+                0b0111_1100,
+                0b0011_1000,
+                0,
+                0,
+            ],
+        ),
+        Self::new(
+            "Cross",
+            [
+                0b0001_0000,
+                0b0001_0000,
+                0b0001_0000,
+                0b1111_1110,
+                0b0001_0000,
+                0b0001_0000,
+                0b0001_0000,
+                0,
+            ],
+        ),
+        Self::new(
+            "Speckle",
+            [
+                0b1000_1000,
+                0b0010_0010,
+                0b1000_1000,
+                0b0010_0010,
+                0b1000_1000,
+                0b0010_0010,
+                0b1000_1000,
+                0b0010_0010,
+            ],
+        ),
+    ];
+
+    pub const fn new(name: &'static str, rows: [u8; 8]) -> Self {
+        Self { name, rows }
+    }
+
+    /// The stamp after this one in `ALL`, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&stamp| stamp == self)
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Canvas points covered by the stamp centered on `center`
+    pub fn points(self, center: Point) -> impl Iterator<Item = Point> {
+        let rows = self.rows;
+        (0..8).flat_map(move |y| {
+            (0..8)
+                .filter(move |&x| rows[y as usize] & (0x80 >> x) != 0)
+                .map(move |x| center + Point::new(x - 3, y - 3))
+        })
+    }
+}
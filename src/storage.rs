@@ -0,0 +1,562 @@
+//! Save and load canvases in battery backed SRAM
+//!
+//! Each canvas is stored as the XOR difference from the background image,
+//! run length encoded one row at a time, so a lightly edited canvas only
+//! takes a few KB of the 32KB SRAM.
+//!
+//! SRAM up to the settings is split into `SLOTS` equal slots, slot 0 is the
+//! autosave. Slot layout: `MAGIC`, payload length (u32 LE), Fletcher-16
+//! checksum of the thumbnail and payload (u32 LE), name (`NAME_LEN` bytes,
+//! zero padded), thumbnail (`thumbnail::WIDTH` by `thumbnail::HEIGHT`
+//! pixels in row order), payload. Each payload packet starts with a control
+//! byte: `0x80 | (n - 1)` is a run of `n` copies of the following pixel,
+//! `n - 1` is `n` literal pixels. Pixels are u16 LE. The last
+//! `settings::LEN` bytes of SRAM hold the settings instead, which carry
+//! their own checksum. A slot that fails its checksum is never drawn.
+
+use core::{ops::Range, str};
+use gba::{
+    save::{self, sram::BatteryBackedAccess, Error, PreparedSave, SaveAccess},
+    vram::bitmap::Mode3,
+    Color,
+};
+
+use crate::{
+    background,
+    checksum::Fletcher16,
+    settings::{self, Settings},
+    thumbnail::{self, Thumbnail},
+};
+
+#[repr(C, align(4))]
+struct Marker([u8; 12]);
+
+/// Lets emulators and flashcarts detect the save media type
+#[used]
+static SRAM_MARKER: Marker = Marker(*b"SRAM_Vnnn\0\0\0");
+
+const MAGIC: [u8; 4] = *b"AMY3";
+const HEADER_LEN: usize = 20;
+
+/// Bytes of the thumbnail after the header
+const THUMBNAIL_LEN: usize = thumbnail::WIDTH * thumbnail::HEIGHT * 2;
+
+/// Canvases that can be saved at once, each gets an equal share of SRAM
+pub const SLOTS: usize = 4;
+
+/// Slot the autosave uses
+pub const AUTOSAVE_SLOT: usize = 0;
+
+/// Longest slot name in bytes
+pub const NAME_LEN: usize = 8;
+
+/// Longest run or literal packet
+const MAX_PACKET: usize = 128;
+
+/// Select SRAM as the save media
+pub fn init() {
+    save::set_save_implementation(Some(&BatteryBackedAccess));
+}
+
+/// A slot with a saved canvas
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SlotInfo {
+    name: [u8; NAME_LEN],
+    /// Payload bytes
+    pub len: usize,
+    checksum: u32,
+}
+
+impl SlotInfo {
+    /// Parse a slot header, `None` if the slot was never written
+    fn decode(header: &[u8; HEADER_LEN]) -> Option<Self> {
+        if header[..4] != MAGIC {
+            return None;
+        }
+        let word =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let mut name = [0; NAME_LEN];
+        name.copy_from_slice(&header[12..]);
+        Some(Self {
+            name,
+            len: word(4) as usize,
+            checksum: word(8),
+        })
+    }
+
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..8].copy_from_slice(&(self.len as u32).to_le_bytes());
+        header[8..12].copy_from_slice(&self.checksum.to_le_bytes());
+        header[12..].copy_from_slice(&self.name);
+        header
+    }
+
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|&b| b == 0).unwrap_or(NAME_LEN);
+        str::from_utf8(&self.name[..len]).unwrap_or_default()
+    }
+
+    /// Check a checksum of the thumbnail and payload against the header
+    fn matches(&self, checksum: &Fletcher16) -> bool {
+        checksum.value() as u32 == self.checksum
+    }
+}
+
+/// Write the current canvas to the autosave slot
+pub fn save_canvas() -> Result<(), Error> {
+    save_slot(AUTOSAVE_SLOT, "Autosave")
+}
+
+/// What loading a slot found
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Loaded {
+    /// The canvas was drawn
+    Canvas,
+    /// Nothing was ever saved in the slot
+    Empty,
+    /// The saved bytes don't match their checksum, nothing was drawn
+    Corrupt,
+}
+
+/// Draw the autosaved canvas
+pub fn load_canvas() -> Result<Loaded, Error> {
+    load_slot(AUTOSAVE_SLOT)
+}
+
+/// Write the current canvas to slot `n` named `name`, cut to `NAME_LEN` bytes
+///
+/// A canvas too detailed to fit the slot fails with `Error::OutOfBounds`
+/// and leaves the slot empty.
+pub fn save_slot(n: usize, name: &str) -> Result<(), Error> {
+    let access = SaveAccess::new()?;
+    let slot = slot_range(&access, n)?;
+    let prepared = access.prepare_write(slot.clone())?;
+
+    // invalidate the old save first so a power loss mid-write isn't loaded
+    prepared.write(slot.start, &[0; HEADER_LEN])?;
+
+    let mut writer = Writer::new(&prepared, slot.start + HEADER_LEN, slot.end);
+    for &pixel in thumbnail::from_canvas().iter() {
+        writer.push_pixel(pixel)?;
+    }
+    let mut row = [0u16; Mode3::WIDTH];
+    for y in 0..Mode3::HEIGHT {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let color = Mode3::read(x, y).unwrap_or(Color(0));
+            *pixel = color.0 ^ background::pixel(x, y);
+        }
+        encode_row(&row, &mut writer)?;
+    }
+    let checksum = writer.checksum.value() as u32;
+    let len = writer.finish()? - slot.start - HEADER_LEN - THUMBNAIL_LEN;
+
+    let mut info = SlotInfo {
+        name: [0; NAME_LEN],
+        len,
+        checksum,
+    };
+    let name = &name.as_bytes()[..name.len().min(NAME_LEN)];
+    info.name[..name.len()].copy_from_slice(name);
+    prepared.write(slot.start, &info.encode())
+}
+
+/// Draw the canvas saved in slot `n`, only once the whole payload matches its checksum
+pub fn load_slot(n: usize) -> Result<Loaded, Error> {
+    let access = SaveAccess::new()?;
+    let slot = slot_range(&access, n)?;
+    let info = match check_slot(&access, &slot)? {
+        Checked::Valid(info) => info,
+        Checked::Empty => return Ok(Loaded::Empty),
+        Checked::Corrupt => return Ok(Loaded::Corrupt),
+    };
+
+    let payload = slot.start + HEADER_LEN + THUMBNAIL_LEN;
+    let mut reader = Reader::new(&access, payload, payload + info.len);
+    let mut i = 0;
+    let mut write = |xor: u16| {
+        let (x, y) = (i % Mode3::WIDTH, i / Mode3::WIDTH);
+        if y < Mode3::HEIGHT {
+            Mode3::write(x, y, Color(xor ^ background::pixel(x, y)));
+        }
+        i += 1;
+    };
+    while i < Mode3::WIDTH * Mode3::HEIGHT {
+        let control = reader.pop()?;
+        let n = (control & 0x7F) as usize + 1;
+        if control & 0x80 != 0 {
+            let pixel = reader.pop_pixel()?;
+            for _ in 0..n {
+                write(pixel);
+            }
+        } else {
+            for _ in 0..n {
+                write(reader.pop_pixel()?);
+            }
+        }
+    }
+    Ok(Loaded::Canvas)
+}
+
+/// Header of each slot holding a valid canvas
+pub fn list_slots() -> Result<[Option<SlotInfo>; SLOTS], Error> {
+    let access = SaveAccess::new()?;
+    let mut slots = [None; SLOTS];
+    for (n, info) in slots.iter_mut().enumerate() {
+        let slot = slot_range(&access, n)?;
+        *info = match check_slot(&access, &slot)? {
+            Checked::Valid(header) => Some(header),
+            _ => None,
+        };
+    }
+    Ok(slots)
+}
+
+/// Thumbnail of the canvas saved in slot `n`, `None` if the slot is empty
+///
+/// Only the header is checked, use slots `list_slots` verified.
+pub fn load_thumbnail(n: usize) -> Result<Option<Thumbnail>, Error> {
+    let access = SaveAccess::new()?;
+    let slot = slot_range(&access, n)?;
+    if read_header(&access, &slot)?.is_none() {
+        return Ok(None);
+    }
+    let start = slot.start + HEADER_LEN;
+    let mut reader = Reader::new(&access, start, start + THUMBNAIL_LEN);
+    let mut thumbnail = [0; thumbnail::WIDTH * thumbnail::HEIGHT];
+    for pixel in thumbnail.iter_mut() {
+        *pixel = reader.pop_pixel()?;
+    }
+    Ok(Some(thumbnail))
+}
+
+/// What `check_slot` found in a slot
+enum Checked {
+    Valid(SlotInfo),
+    Empty,
+    Corrupt,
+}
+
+/// Read the header of `slot` and check its thumbnail and payload against it
+fn check_slot(access: &impl Sram, slot: &Range<usize>) -> Result<Checked, Error> {
+    Ok(match read_header(access, slot)? {
+        Some(info) if verify(access, slot, &info)? => Checked::Valid(info),
+        Some(_) => Checked::Corrupt,
+        None => Checked::Empty,
+    })
+}
+
+/// Header of the canvas in `slot`, `None` if the slot was never written
+fn read_header(access: &impl Sram, slot: &Range<usize>) -> Result<Option<SlotInfo>, Error> {
+    let mut header = [0; HEADER_LEN];
+    access.read(slot.start, &mut header)?;
+    Ok(SlotInfo::decode(&header)
+        .filter(|info| slot.start + HEADER_LEN + THUMBNAIL_LEN + info.len <= slot.end))
+}
+
+/// Check the thumbnail and payload in `slot` against the checksum in its header
+fn verify(access: &impl Sram, slot: &Range<usize>, info: &SlotInfo) -> Result<bool, Error> {
+    let start = slot.start + HEADER_LEN;
+    let len = THUMBNAIL_LEN + info.len;
+    let mut reader = Reader::new(access, start, start + len);
+    for _ in 0..len {
+        reader.pop()?;
+    }
+    Ok(info.matches(&reader.checksum))
+}
+
+/// Bytes of slot `n`, header included
+fn slot_range(access: &SaveAccess, n: usize) -> Result<Range<usize>, Error> {
+    if n >= SLOTS {
+        return Err(Error::OutOfBounds);
+    }
+    let len = canvas_end(access) / SLOTS;
+    Ok(n * len..(n + 1) * len)
+}
+
+/// Read the settings, defaults if none were saved
+pub fn load_settings() -> Settings {
+    let mut bytes = [0; settings::LEN];
+    let read = SaveAccess::new().and_then(|access| {
+        access.read(canvas_end(&access), &mut bytes)?;
+        Ok(())
+    });
+    match read {
+        Ok(()) => Settings::decode(&bytes).unwrap_or_else(Settings::new),
+        Err(_) => Settings::new(),
+    }
+}
+
+/// Write the settings to the end of SRAM
+pub fn save_settings(settings: &Settings) -> Result<(), Error> {
+    let access = SaveAccess::new()?;
+    let start = canvas_end(&access);
+    let prepared = access.prepare_write(start..access.len())?;
+    prepared.write(start, &settings.encode())
+}
+
+/// Canvas slots may use SRAM up to the settings
+fn canvas_end(access: &SaveAccess) -> usize {
+    access.len() - settings::LEN
+}
+
+fn encode_row(row: &[u16], writer: &mut Writer) -> Result<(), Error> {
+    let mut i = 0;
+    while i < row.len() {
+        let run = row[i..]
+            .iter()
+            .take(MAX_PACKET)
+            .take_while(|&&pixel| pixel == row[i])
+            .count();
+        if run > 1 {
+            writer.push(0x80 | (run - 1) as u8)?;
+            writer.push_pixel(row[i])?;
+            i += run;
+        } else {
+            // extend the literal until the next run starts
+            let start = i;
+            while i < row.len() && i - start < MAX_PACKET {
+                if i + 1 < row.len() && row[i] == row[i + 1] {
+                    break;
+                }
+                i += 1;
+            }
+            writer.push((i - start - 1) as u8)?;
+            for &pixel in &row[start..i] {
+                writer.push_pixel(pixel)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Save media `Reader` reads, SRAM outside of tests
+trait Sram {
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<(), Error>;
+}
+
+impl Sram for SaveAccess {
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<(), Error> {
+        SaveAccess::read(self, offset, buffer)
+    }
+}
+
+/// Reads SRAM from `offset` to `end` in chunks and returns it a byte at a time
+struct Reader<'a, S = SaveAccess> {
+    access: &'a S,
+    offset: usize,
+    end: usize,
+    buf: [u8; 64],
+    pos: usize,
+    len: usize,
+    /// Of the bytes popped so far
+    checksum: Fletcher16,
+}
+
+impl<'a, S: Sram> Reader<'a, S> {
+    fn new(access: &'a S, offset: usize, end: usize) -> Self {
+        Self {
+            access,
+            offset,
+            end,
+            buf: [0; 64],
+            pos: 0,
+            len: 0,
+            checksum: Fletcher16::new(),
+        }
+    }
+
+    fn pop(&mut self) -> Result<u8, Error> {
+        if self.pos == self.len {
+            let len = self.buf.len().min(self.end - self.offset);
+            if len == 0 {
+                return Err(Error::OutOfBounds);
+            }
+            self.access.read(self.offset, &mut self.buf[..len])?;
+            self.offset += len;
+            self.pos = 0;
+            self.len = len;
+        }
+        self.pos += 1;
+        let byte = self.buf[self.pos - 1];
+        self.checksum.push(byte);
+        Ok(byte)
+    }
+
+    fn pop_pixel(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes([self.pop()?, self.pop()?]))
+    }
+}
+
+/// Buffers bytes and writes them to SRAM from `offset` to `end` in chunks
+struct Writer<'a> {
+    save: &'a PreparedSave,
+    offset: usize,
+    end: usize,
+    buf: [u8; 64],
+    len: usize,
+    /// Of the bytes pushed so far
+    checksum: Fletcher16,
+}
+
+impl<'a> Writer<'a> {
+    fn new(save: &'a PreparedSave, offset: usize, end: usize) -> Self {
+        Self {
+            save,
+            offset,
+            end,
+            buf: [0; 64],
+            len: 0,
+            checksum: Fletcher16::new(),
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), Error> {
+        if self.offset + self.len >= self.end {
+            return Err(Error::OutOfBounds);
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self.checksum.push(byte);
+        if self.len == self.buf.len() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn push_pixel(&mut self, pixel: u16) -> Result<(), Error> {
+        let [lo, hi] = pixel.to_le_bytes();
+        self.push(lo)?;
+        self.push(hi)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.save.write(self.offset, &self.buf[..self.len])?;
+        self.offset += self.len;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Flush remaining bytes and return the end offset
+    fn finish(mut self) -> Result<usize, Error> {
+        self.flush()?;
+        Ok(self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::fletcher16;
+
+    fn info(name: &[u8], payload: &[u8]) -> SlotInfo {
+        let mut info = SlotInfo {
+            name: [0; NAME_LEN],
+            len: payload.len(),
+            checksum: fletcher16(payload) as u32,
+        };
+        info.name[..name.len()].copy_from_slice(name);
+        info
+    }
+
+    fn sum(bytes: &[u8]) -> Fletcher16 {
+        let mut sum = Fletcher16::new();
+        bytes.iter().for_each(|&byte| sum.push(byte));
+        sum
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let info = info(b"Autosave", &[1, 2, 3]);
+        assert_eq!(SlotInfo::decode(&info.encode()), Some(info));
+    }
+
+    #[test]
+    fn header_layout() {
+        let header = info(b"Sky", &[7; 300]).encode();
+        assert_eq!(header[..4], *b"AMY3");
+        assert_eq!(header[4..8], 300u32.to_le_bytes());
+        assert_eq!(header[8..12], (fletcher16(&[7; 300]) as u32).to_le_bytes());
+        assert_eq!(header[12..], *b"Sky\0\0\0\0\0");
+    }
+
+    #[test]
+    fn unwritten_or_old_headers_are_empty() {
+        assert_eq!(SlotInfo::decode(&[0; HEADER_LEN]), None);
+        assert_eq!(SlotInfo::decode(&[0xFF; HEADER_LEN]), None);
+        let mut old = info(b"Autosave", &[]).encode();
+        old[..4].copy_from_slice(b"AMY2");
+        assert_eq!(SlotInfo::decode(&old), None);
+    }
+
+    #[test]
+    fn name_stops_at_padding() {
+        assert_eq!(info(b"Sky", &[]).name(), "Sky");
+        assert_eq!(info(b"Autosave", &[]).name(), "Autosave");
+    }
+
+    #[test]
+    fn checksum_catches_a_changed_byte() {
+        let mut payload = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        let info = info(b"Sky", &payload);
+        assert!(info.matches(&sum(&payload)));
+        payload[2] ^= 0x01;
+        assert!(!info.matches(&sum(&payload)));
+    }
+
+    /// SRAM contents held in memory
+    struct Fake(Vec<u8>);
+
+    impl Sram for Fake {
+        fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<(), Error> {
+            let bytes = self.0.get(offset..offset + buffer.len());
+            buffer.copy_from_slice(bytes.ok_or(Error::OutOfBounds)?);
+            Ok(())
+        }
+    }
+
+    /// A slot holding `payload` behind a thumbnail, as `save_slot` writes it
+    fn saved(payload: &[u8]) -> (Fake, Range<usize>) {
+        let mut body = vec![0x5A; THUMBNAIL_LEN];
+        body.extend_from_slice(payload);
+        let mut info = info(b"Sky", payload);
+        info.checksum = fletcher16(&body) as u32;
+        let mut bytes = info.encode().to_vec();
+        bytes.extend_from_slice(&body);
+        bytes.resize(bytes.len() + 16, 0);
+        let len = bytes.len();
+        (Fake(bytes), 0..len)
+    }
+
+    #[test]
+    fn intact_slot_is_valid() {
+        let (sram, slot) = saved(&[0x81, 0x34, 0x12]);
+        match check_slot(&sram, &slot) {
+            Ok(Checked::Valid(info)) => assert_eq!(info.len, 3),
+            _ => panic!("slot not valid"),
+        }
+    }
+
+    #[test]
+    fn flipped_byte_is_corrupt() {
+        let payload = [0x81, 0x34, 0x12];
+        let body = HEADER_LEN..HEADER_LEN + THUMBNAIL_LEN + payload.len();
+        for i in body {
+            let (mut sram, slot) = saved(&payload);
+            sram.0[i] ^= 0x10;
+            assert!(
+                matches!(check_slot(&sram, &slot), Ok(Checked::Corrupt)),
+                "byte {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn erased_slot_is_empty() {
+        let (mut sram, slot) = saved(&[0x81, 0x34, 0x12]);
+        sram.0[..HEADER_LEN].iter_mut().for_each(|byte| *byte = 0);
+        assert!(matches!(check_slot(&sram, &slot), Ok(Checked::Empty)));
+    }
+}
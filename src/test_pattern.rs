@@ -0,0 +1,76 @@
+//! Calibration screen for checking the display pipeline
+//!
+//! Every pixel depends only on its position, so emulator screenshots of the
+//! finished pattern can be compared byte for byte.
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+use gba::vram::bitmap::Mode3;
+
+use crate::scheduler::Pass;
+
+const WIDTH: i32 = Mode3::WIDTH as i32;
+const HEIGHT: i32 = Mode3::HEIGHT as i32;
+
+/// Full intensity bars across the top, in order of decreasing brightness
+const BARS: [Bgr555; 8] = [
+    Bgr555::WHITE,
+    Bgr555::YELLOW,
+    Bgr555::CYAN,
+    Bgr555::GREEN,
+    Bgr555::MAGENTA,
+    Bgr555::RED,
+    Bgr555::BLUE,
+    Bgr555::BLACK,
+];
+const BARS_BOTTOM: i32 = 48;
+
+/// Red, green, blue and gray ramps through all 32 levels
+const RAMP_HEIGHT: i32 = 12;
+const RAMPS_BOTTOM: i32 = BARS_BOTTOM + 4 * RAMP_HEIGHT;
+
+const CHECKER: i32 = 8;
+
+/// Side of the square marking each corner, an outline around the screen joins them
+const MARKER: i32 = 8;
+
+/// Color bars, ramps, a checkerboard and corner markers
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TestPattern;
+
+impl TestPattern {
+    /// Color the pattern has at `point`
+    fn color(point: Point) -> Bgr555 {
+        let Point { x, y } = point;
+        let near = |value: i32, end: i32| value < MARKER || value >= end - MARKER;
+        if near(x, WIDTH) && near(y, HEIGHT) {
+            return Bgr555::RED;
+        }
+        if x == 0 || y == 0 || x == WIDTH - 1 || y == HEIGHT - 1 {
+            return Bgr555::WHITE;
+        }
+
+        if y < BARS_BOTTOM {
+            return BARS[(x * BARS.len() as i32 / WIDTH) as usize];
+        }
+        if y < RAMPS_BOTTOM {
+            let level = (x * 32 / WIDTH) as u8;
+            return match (y - BARS_BOTTOM) / RAMP_HEIGHT {
+                0 => Bgr555::new(level, 0, 0),
+                1 => Bgr555::new(0, level, 0),
+                2 => Bgr555::new(0, 0, level),
+                _ => Bgr555::new(level, level, level),
+            };
+        }
+        if (x / CHECKER + y / CHECKER) % 2 == 0 {
+            Bgr555::WHITE
+        } else {
+            Bgr555::BLACK
+        }
+    }
+}
+
+impl Pass for TestPattern {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        Some(Self::color(point))
+    }
+}
@@ -0,0 +1,112 @@
+//! Small previews of saved canvases
+//!
+//! A thumbnail averages each 10x10 block of the canvas into one pixel,
+//! 24x16 pixels or 768 bytes, about a tenth of a save slot. The slot screen
+//! draws it at twice the size.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+    primitives::Rectangle,
+    style::PrimitiveStyle,
+};
+use gba::{vram::bitmap::Mode3, Color};
+
+use crate::{color, gba_display::GbaDisplay};
+
+/// Canvas pixels across and down each thumbnail pixel
+const BLOCK: usize = 10;
+
+pub const WIDTH: usize = Mode3::WIDTH / BLOCK;
+pub const HEIGHT: usize = Mode3::HEIGHT / BLOCK;
+
+/// Thumbnail pixels in row order
+pub type Thumbnail = [u16; WIDTH * HEIGHT];
+
+/// Average the blocks of the canvas on screen
+pub fn from_canvas() -> Thumbnail {
+    downscale(|x, y| RawU16::new(Mode3::read(x, y).unwrap_or(Color(0)).0).into())
+}
+
+/// Average the blocks of the canvas pixels `read` gives for `x`, `y`
+fn downscale(read: impl Fn(usize, usize) -> Bgr555) -> Thumbnail {
+    let mut thumbnail = [0; WIDTH * HEIGHT];
+    for (i, pixel) in thumbnail.iter_mut().enumerate() {
+        let (left, top) = (i % WIDTH * BLOCK, i / WIDTH * BLOCK);
+        let block = (top..top + BLOCK).flat_map(|y| (left..left + BLOCK).map(move |x| (x, y)));
+        *pixel = color::average(block.map(|(x, y)| read(x, y)))
+            .unwrap_or(Bgr555::BLACK)
+            .into_storage();
+    }
+    thumbnail
+}
+
+/// Draw `thumbnail` with its top left corner at `point`, each pixel as a
+/// `scale` by `scale` square
+pub fn draw(
+    display: &mut GbaDisplay,
+    thumbnail: &Thumbnail,
+    point: Point,
+    scale: i32,
+) -> Result<(), Infallible> {
+    for (i, &pixel) in thumbnail.iter().enumerate() {
+        let top_left = point + Point::new((i % WIDTH) as i32, (i / WIDTH) as i32) * scale;
+        Rectangle::new(top_left, top_left + Point::new(scale - 1, scale - 1))
+            .into_styled(PrimitiveStyle::with_fill(Bgr555::from(RawU16::new(pixel))))
+            .draw(display)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(thumbnail: &Thumbnail, x: usize, y: usize) -> Bgr555 {
+        RawU16::new(thumbnail[y * WIDTH + x]).into()
+    }
+
+    #[test]
+    fn each_block_lands_on_its_own_pixel() {
+        let thumbnail = downscale(|x, y| Bgr555::new((x / BLOCK) as u8, (y / BLOCK) as u8, 7));
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                assert_eq!(pixel(&thumbnail, x, y), Bgr555::new(x as u8, y as u8, 7));
+            }
+        }
+    }
+
+    #[test]
+    fn stripes_average_to_gray() {
+        let white_columns = |x: usize, _: usize| {
+            if x % 2 == 0 {
+                Bgr555::WHITE
+            } else {
+                Bgr555::BLACK
+            }
+        };
+        let thumbnail = downscale(white_columns);
+        assert!(thumbnail
+            .iter()
+            .all(|&pixel| pixel == Bgr555::new(16, 16, 16).into_storage()));
+    }
+
+    #[test]
+    fn averages_round_to_nearest() {
+        // a few red pixels in the top left corner of the first block
+        let corner = |red: usize| {
+            downscale(move |x, y| {
+                if x < BLOCK && y < BLOCK && y * BLOCK + x < red {
+                    Bgr555::RED
+                } else {
+                    Bgr555::BLACK
+                }
+            })
+        };
+        assert_eq!(pixel(&corner(1), 0, 0), Bgr555::BLACK);
+        assert_eq!(pixel(&corner(2), 0, 0), Bgr555::new(1, 0, 0));
+        assert_eq!(pixel(&corner(100), 0, 0), Bgr555::RED);
+        assert_eq!(pixel(&corner(100), 1, 0), Bgr555::BLACK);
+    }
+}
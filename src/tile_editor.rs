@@ -0,0 +1,263 @@
+//! Edit one 8x8 tile on a magnified grid, for a custom brush stamp
+//!
+//! Each cell holds a registered palette index, 0 being transparent. The
+//! tile is kept in character block 5 as it is edited and shown at 1x as a
+//! sprite next to the grid.
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::Rectangle,
+    style::{PrimitiveStyle, TextStyle},
+};
+use gba::{oam::write_obj_attributes, vram::Tile8bpp};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    gba_display::{GbaDisplay, PaletteColor},
+    input::{Input, Key},
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Cells per side
+pub const SIZE: usize = 8;
+
+/// Bytes of `TileCells::pack`, two cells per byte
+pub const PACKED_LEN: usize = SIZE * SIZE / 2;
+
+const CHARACTER_BLOCK: usize = 5;
+
+/// Preview tile, after the lock corner mark
+const PREVIEW_TILE: usize = 143;
+
+/// OAM slot of the preview, after the lock marks
+const PREVIEW_SLOT: usize = 117;
+
+/// Screen pixels per cell, including the line to its right and below
+const PITCH: i32 = 12;
+const GRID_ORIGIN: Point = Point::new(16, 24);
+const PREVIEW_POSITION: Point = Point::new(128, 24);
+
+const GRID_LINE: Bgr555 = Bgr555::new(8, 8, 8);
+/// Shown for transparent cells
+const EMPTY: Bgr555 = Bgr555::new(20, 20, 20);
+const CURSOR: Bgr555 = Bgr555::YELLOW;
+
+/// Palette index of each pixel, row by row
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TileCells(pub [[u8; SIZE]; SIZE]);
+
+impl TileCells {
+    pub const fn new() -> Self {
+        Self([[0; SIZE]; SIZE])
+    }
+
+    /// Four bits per cell, the left cell of each pair in the low bits
+    pub fn pack(&self) -> [u8; PACKED_LEN] {
+        let mut bytes = [0; PACKED_LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let (x, y) = (i * 2 % SIZE, i * 2 / SIZE);
+            *byte = (self.0[y][x] & 0xF) | self.0[y][x + 1] << 4;
+        }
+        bytes
+    }
+
+    pub fn unpack(bytes: &[u8; PACKED_LEN]) -> Self {
+        let mut cells = Self::new();
+        for (i, &byte) in bytes.iter().enumerate() {
+            let (x, y) = (i * 2 % SIZE, i * 2 / SIZE);
+            cells.0[y][x] = byte & 0xF;
+            cells.0[y][x + 1] = byte >> 4;
+        }
+        cells
+    }
+
+    /// One bit per opaque cell, bit 7 is the leftmost, like `stamp::Stamp` rows
+    pub fn mask_rows(&self) -> [u8; SIZE] {
+        let mut rows = [0; SIZE];
+        for (row, cells) in rows.iter_mut().zip(self.0.iter()) {
+            for (x, &index) in cells.iter().enumerate() {
+                if index != 0 {
+                    *row |= 0x80 >> x;
+                }
+            }
+        }
+        rows
+    }
+
+    fn tile(&self) -> Result<Tile8bpp, Infallible> {
+        let mut tile = blank_tile();
+        for (y, cells) in self.0.iter().enumerate() {
+            for (x, &index) in cells.iter().enumerate() {
+                Pixel(Point::new(x as i32, y as i32), PaletteColor::new(index)).draw(&mut tile)?;
+            }
+        }
+        Ok(tile)
+    }
+}
+
+/// Leaving the editor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EditorAction {
+    /// Start keeps the edited tile
+    Done(TileCells),
+    /// B throws the edits away
+    Cancel,
+}
+
+/// Grid editor state, `palette[i]` is the color of palette index `i + 1`
+pub struct TileEditor {
+    cells: TileCells,
+    /// Cells as last kept, restored on cancel
+    kept: TileCells,
+    cursor: Point,
+    /// Palette index painted with A, 0 erases
+    index: u8,
+    palette: &'static [Bgr555],
+}
+
+impl TileEditor {
+    pub const fn new(cells: TileCells, palette: &'static [Bgr555]) -> Self {
+        Self {
+            cells,
+            kept: cells,
+            cursor: Point::zero(),
+            index: 1,
+            palette,
+        }
+    }
+
+    /// Draw the editor over the whole display and show the preview
+    pub fn open(&mut self, display: &mut GbaDisplay) -> Result<(), TileError> {
+        display.clear(Bgr555::BLACK)?;
+        let style = TextStyle::new(Font6x8, Bgr555::WHITE);
+        Text::new("Tile editor", Point::new(16, 8))
+            .into_styled(style)
+            .draw(display)?;
+        Text::new("A: Paint  L/R: Color", Point::new(16, 128))
+            .into_styled(style)
+            .draw(display)?;
+        Text::new("Start: Keep  B: Cancel", Point::new(16, 140))
+            .into_styled(style)
+            .draw(display)?;
+        let side = PITCH * SIZE as i32;
+        Rectangle::new(GRID_ORIGIN, GRID_ORIGIN + Point::new(side, side))
+            .into_styled(PrimitiveStyle::with_fill(GRID_LINE))
+            .draw(display)?;
+        for y in 0..SIZE as i32 {
+            for x in 0..SIZE as i32 {
+                self.draw_cell(display, Point::new(x, y))?;
+            }
+        }
+        self.draw_cursor(display, CURSOR)?;
+        self.draw_swatch(display)?;
+        self.upload()?;
+        self.show_preview(true);
+        Ok(())
+    }
+
+    /// Hide the preview sprite, the display is left for the caller to redraw
+    pub fn close(&mut self) {
+        self.show_preview(false);
+    }
+
+    /// Move between cells with the d-pad and paint them, returns how the editor was left
+    pub fn update(
+        &mut self,
+        input: &Input,
+        display: &mut GbaDisplay,
+    ) -> Result<Option<EditorAction>, TileError> {
+        let mut step = Point::zero();
+        for &(key, direction) in [
+            (Key::Left, Point::new(-1, 0)),
+            (Key::Right, Point::new(1, 0)),
+            (Key::Up, Point::new(0, -1)),
+            (Key::Down, Point::new(0, 1)),
+        ]
+        .iter()
+        {
+            if input.just_pressed(key) {
+                step += direction;
+            }
+        }
+        if step != Point::zero() {
+            self.draw_cursor(display, GRID_LINE)?;
+            let wrap = |v: i32| (v + SIZE as i32) % SIZE as i32;
+            self.cursor = Point::new(wrap(self.cursor.x + step.x), wrap(self.cursor.y + step.y));
+            self.draw_cursor(display, CURSOR)?;
+        }
+
+        // index 0, transparent, is one of the choices
+        let choices = self.palette.len() as u8 + 1;
+        if input.just_pressed(Key::L) {
+            self.index = (self.index + choices - 1) % choices;
+            self.draw_swatch(display)?;
+        }
+        if input.just_pressed(Key::R) {
+            self.index = (self.index + 1) % choices;
+            self.draw_swatch(display)?;
+        }
+
+        if input.just_pressed(Key::A) {
+            self.cells.0[self.cursor.y as usize][self.cursor.x as usize] = self.index;
+            self.draw_cell(display, self.cursor)?;
+            self.upload()?;
+        }
+
+        Ok(if input.just_pressed(Key::Start) {
+            self.kept = self.cells;
+            Some(EditorAction::Done(self.cells))
+        } else if input.just_pressed(Key::B) {
+            self.cells = self.kept;
+            Some(EditorAction::Cancel)
+        } else {
+            None
+        })
+    }
+
+    fn color(&self, index: u8) -> Bgr555 {
+        match index {
+            0 => EMPTY,
+            i => self.palette.get(i as usize - 1).copied().unwrap_or(EMPTY),
+        }
+    }
+
+    fn draw_cell(&self, display: &mut GbaDisplay, cell: Point) -> Result<(), Infallible> {
+        let top_left = GRID_ORIGIN + cell * PITCH + Point::new(1, 1);
+        let index = self.cells.0[cell.y as usize][cell.x as usize];
+        Rectangle::new(top_left, top_left + Point::new(PITCH - 2, PITCH - 2))
+            .into_styled(PrimitiveStyle::with_fill(self.color(index)))
+            .draw(display)
+    }
+
+    /// Outline the cursor cell on the grid lines around it
+    fn draw_cursor(&self, display: &mut GbaDisplay, color: Bgr555) -> Result<(), Infallible> {
+        let top_left = GRID_ORIGIN + self.cursor * PITCH;
+        Rectangle::new(top_left, top_left + Point::new(PITCH, PITCH))
+            .into_styled(PrimitiveStyle::with_stroke(color, 1))
+            .draw(display)
+    }
+
+    /// Color painted with A, below the preview
+    fn draw_swatch(&self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        let top_left = PREVIEW_POSITION + Point::new(0, 16);
+        Rectangle::new(top_left, top_left + Point::new(15, 15))
+            .into_styled(PrimitiveStyle::with_fill(self.color(self.index)))
+            .draw(display)
+    }
+
+    fn upload(&self) -> Result<(), TileError> {
+        CharBlock::new(CHARACTER_BLOCK)?.set_tile(PREVIEW_TILE, self.cells.tile()?)?;
+        Ok(())
+    }
+
+    fn show_preview(&self, visible: bool) {
+        let sprite = SpriteBuilder::new(PREVIEW_TILE)
+            .position(PREVIEW_POSITION)
+            .hidden(!visible)
+            .build();
+        write_obj_attributes(PREVIEW_SLOT, sprite);
+    }
+}
@@ -0,0 +1,41 @@
+use gba::{
+    io::timers::{TimerControlSetting, TimerTickRate, TM0CNT_H, TM0CNT_L},
+    sync::Static,
+};
+
+/// Timer 0 ticks at 16.78 MHz / 1024
+const TICKS_PER_SECOND: u16 = 16384;
+
+static SECONDS: Static<u32> = Static::new(0);
+
+/// Start timer 0, overflowing (and raising an interrupt) once per second
+pub fn init() {
+    TM0CNT_L.write(0u16.wrapping_sub(TICKS_PER_SECOND)); // reload value
+    TM0CNT_H.write(
+        TimerControlSetting::new()
+            .with_tick_rate(TimerTickRate::CPU1024)
+            .with_overflow_irq(true)
+            .with_enabled(true),
+    );
+}
+
+/// Call from the irq handler when timer 0 overflows
+pub fn on_overflow() {
+    SECONDS.write(SECONDS.read().wrapping_add(1));
+}
+
+/// Seconds elapsed since `init`
+pub fn seconds() -> u32 {
+    SECONDS.read()
+}
+
+/// Timer ticks elapsed since `init`, wrapping after about three days
+pub fn ticks() -> u32 {
+    let into_second = TM0CNT_L
+        .read()
+        .wrapping_sub(0u16.wrapping_sub(TICKS_PER_SECOND));
+    SECONDS
+        .read()
+        .wrapping_mul(TICKS_PER_SECOND as u32)
+        .wrapping_add(into_second as u32)
+}
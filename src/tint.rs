@@ -0,0 +1,24 @@
+//! Warm or cool the whole canvas
+
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+use crate::{color, scheduler::Pass};
+
+/// Shifts every pixel with `color::adjust_tint`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tint {
+    delta: i8,
+}
+
+impl Tint {
+    /// Positive `delta` warms, negative cools
+    pub fn new(delta: i8) -> Self {
+        Self { delta }
+    }
+}
+
+impl Pass for Tint {
+    fn pixel(&mut self, _point: Point, color: Bgr555) -> Option<Bgr555> {
+        Some(color::adjust_tint(color, self.delta))
+    }
+}
@@ -0,0 +1,92 @@
+use core::convert::Infallible;
+use embedded_graphics::{
+    fonts::{Font6x8, Text},
+    pixelcolor::Bgr555,
+    prelude::*,
+    primitives::Rectangle,
+    style::{PrimitiveStyle, TextStyle},
+};
+use gba::vram::bitmap::Mode3;
+
+use crate::{gba_display::GbaDisplay, overlay::Obscured};
+
+/// Longest message, longer text is truncated
+const MAX_CHARS: usize = 20;
+const WIDTH: usize = MAX_CHARS * 6 + 2;
+const HEIGHT: usize = 10;
+const ORIGIN: (usize, usize) = (0, Mode3::HEIGHT - HEIGHT);
+
+/// Number of frames a toast stays on screen
+const DURATION: u16 = 90;
+
+/// Brief message drawn over the bottom left corner of the canvas
+///
+/// The pixels underneath are saved and restored when the toast expires.
+pub struct Toast {
+    under: Obscured<{ WIDTH * HEIGHT }>,
+    frames: u16,
+}
+
+impl Toast {
+    pub const fn new() -> Self {
+        Self {
+            under: Obscured::new(),
+            frames: 0,
+        }
+    }
+
+    /// Show `text`, replacing any toast already on screen
+    pub fn show(&mut self, display: &mut GbaDisplay, text: &str) -> Result<(), Infallible> {
+        self.hide(display)?;
+
+        let end = text
+            .char_indices()
+            .nth(MAX_CHARS)
+            .map_or(text.len(), |(i, _)| i);
+        let text = &text[..end];
+        let origin = Point::new(ORIGIN.0 as i32, ORIGIN.1 as i32);
+        let area = Rectangle::new(
+            origin,
+            origin + Point::new(WIDTH as i32 - 1, HEIGHT as i32 - 1),
+        );
+        if self.under.obscure(display, area).is_err() {
+            return Ok(());
+        }
+        area.into_styled(PrimitiveStyle::with_fill(Bgr555::WHITE))
+            .draw(display)?;
+        Text::new(text, origin + Point::new(1, 1))
+            .into_styled(TextStyle::new(Font6x8, Bgr555::BLACK))
+            .draw(display)?;
+
+        self.frames = DURATION;
+        Ok(())
+    }
+
+    /// Check if a toast is on screen
+    pub fn is_visible(&self) -> bool {
+        self.frames > 0
+    }
+
+    /// Count down one frame, restoring the canvas once the toast expires
+    pub fn tick(&mut self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        if self.frames == 1 {
+            self.hide(display)?;
+        } else if self.frames > 1 {
+            self.frames -= 1;
+        }
+        Ok(())
+    }
+
+    /// Run `f` on the saved canvas pixels, e.g. so a pass that ran under the
+    /// toast isn't undone when it hides
+    pub fn map_backup(&mut self, f: impl FnMut(Point, Bgr555) -> Option<Bgr555>) {
+        self.under.map(f);
+    }
+
+    /// Restore the canvas under the toast immediately
+    pub fn hide(&mut self, display: &mut GbaDisplay) -> Result<(), Infallible> {
+        self.under.reveal(display);
+        self.frames = 0;
+        Ok(())
+    }
+}
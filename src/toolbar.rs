@@ -0,0 +1,198 @@
+//! Row of tool icons drawn with sprites over the top of the canvas
+
+use core::convert::Infallible;
+use embedded_graphics::{
+    prelude::*,
+    primitives::{Circle, Line, Rectangle, Triangle},
+    style::PrimitiveStyle,
+};
+use gba::{oam::write_obj_attributes, vram::Tile8bpp};
+
+use crate::{
+    charblock::{CharBlock, TileError},
+    dim,
+    gba_display::PaletteColor,
+    geom,
+    paint::Tool,
+    reticle::Reticle,
+    sprite::{blank_tile, SpriteBuilder},
+};
+
+/// Registered palette slots, see `register_palette`
+const BLACK: PaletteColor = PaletteColor::new(1);
+const YELLOW: PaletteColor = PaletteColor::new(5);
+const WHITE: PaletteColor = PaletteColor::new(8);
+
+/// Character block 5, like the reticle
+const CHARACTER_BLOCK: usize = 5;
+
+/// First icon tile, after the largest reticle shapes, it holds the highlight frame
+pub const FRAME_TILE: usize = 81;
+
+/// OAM slot of the highlight, icons follow it and the reticle stays in slot 0 on top
+const FIRST_SLOT: usize = 1;
+
+const ORIGIN: Point = Point::new(80, 1);
+
+/// Distance between icons
+const SPACING: i32 = 10;
+
+/// Tool icons, one sprite each, with a frame sprite around the current tool
+pub struct Toolbar {
+    selected: Tool,
+    visible: bool,
+    /// Sixteenths the canvas behind the visible toolbar is darkened by
+    dim: Option<u8>,
+}
+
+impl Toolbar {
+    /// Draw the icons into sprite tile memory, the toolbar starts hidden
+    pub fn new() -> Result<Self, TileError> {
+        let block = CharBlock::new(CHARACTER_BLOCK)?;
+        let mut frame = blank_tile();
+        Rectangle::new(Point::zero(), Point::new(7, 7))
+            .into_styled(PrimitiveStyle::with_stroke(YELLOW, 1))
+            .draw(&mut frame)?;
+        block.set_tile(FRAME_TILE, frame)?;
+        for (i, &tool) in Tool::ALL.iter().enumerate() {
+            let mut icon = blank_tile();
+            draw_icon(tool, &mut icon)?;
+            block.set_tile(icon_tile(i), icon)?;
+        }
+
+        let mut toolbar = Self {
+            selected: Tool::ALL[0],
+            visible: false,
+            dim: None,
+        };
+        toolbar.write_oam();
+        Ok(toolbar)
+    }
+
+    /// Screen area covered by the icons, corners inclusive
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(
+            ORIGIN,
+            ORIGIN + Point::new(SPACING * (Tool::ALL.len() as i32 - 1) + 7, 7),
+        )
+    }
+
+    /// Show the toolbar with `tool` highlighted
+    pub fn show(&mut self, tool: Tool) {
+        self.selected = tool;
+        self.visible = true;
+        self.write_oam();
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.write_oam();
+    }
+
+    /// Darken the canvas behind the icons while they're shown, `None` leaves it
+    pub fn set_dim(&mut self, amount: Option<u8>) {
+        self.dim = amount;
+        self.write_oam();
+    }
+
+    /// Tool whose icon the reticle points at
+    pub fn tool_at(&self, reticle: &Reticle) -> Option<Tool> {
+        if !self.visible {
+            return None;
+        }
+        Tool::ALL
+            .iter()
+            .enumerate()
+            .find(|&(i, _)| geom::hotspot_in_rect(reticle, &icon_rect(i)))
+            .map(|(_, &tool)| tool)
+    }
+
+    fn write_oam(&self) {
+        let selected = Tool::ALL
+            .iter()
+            .position(|&tool| tool == self.selected)
+            .unwrap_or(0);
+        let frame = SpriteBuilder::new(FRAME_TILE)
+            .position(icon_rect(selected).top_left)
+            .hidden(!self.visible)
+            .build();
+        write_obj_attributes(FIRST_SLOT, frame);
+        for i in 0..Tool::ALL.len() {
+            let icon = SpriteBuilder::new(icon_tile(i))
+                .position(icon_rect(i).top_left)
+                .hidden(!self.visible)
+                .build();
+            write_obj_attributes(FIRST_SLOT + 1 + i, icon);
+        }
+        match self.dim {
+            Some(amount) if self.visible => dim::dim_region(&self.bounds(), amount),
+            _ => dim::clear(),
+        }
+    }
+}
+
+/// Tile of the icon of `Tool::ALL[i]`
+pub fn icon_tile(i: usize) -> usize {
+    FRAME_TILE + 1 + i
+}
+
+/// Screen area of icon `i`
+fn icon_rect(i: usize) -> Rectangle {
+    let top_left = ORIGIN + Point::new(SPACING * i as i32, 0);
+    Rectangle::new(top_left, top_left + Point::new(7, 7))
+}
+
+/// White glyph on a black square so the icon shows over any art
+fn draw_icon(tool: Tool, tile: &mut Tile8bpp) -> Result<(), Infallible> {
+    Rectangle::new(Point::zero(), Point::new(7, 7))
+        .into_styled(PrimitiveStyle::with_fill(BLACK))
+        .draw(tile)?;
+    let stroke = PrimitiveStyle::with_stroke(WHITE, 1);
+    let fill = PrimitiveStyle::with_fill(WHITE);
+    match tool {
+        Tool::Brush => {
+            Line::new(Point::new(2, 5), Point::new(5, 2))
+                .into_styled(stroke)
+                .draw(tile)?;
+            Rectangle::new(Point::new(1, 5), Point::new(2, 6))
+                .into_styled(fill)
+                .draw(tile)
+        }
+        Tool::Line => Line::new(Point::new(1, 6), Point::new(6, 1))
+            .into_styled(stroke)
+            .draw(tile),
+        Tool::Fill => Rectangle::new(Point::new(1, 3), Point::new(5, 6))
+            .into_styled(fill)
+            .draw(tile),
+        Tool::Eyedropper => {
+            Line::new(Point::new(1, 6), Point::new(4, 3))
+                .into_styled(stroke)
+                .draw(tile)?;
+            Circle::new(Point::new(5, 2), 1)
+                .into_styled(fill)
+                .draw(tile)
+        }
+        Tool::Stamp => {
+            Rectangle::new(Point::new(1, 1), Point::new(6, 6))
+                .into_styled(stroke)
+                .draw(tile)?;
+            Rectangle::new(Point::new(3, 3), Point::new(4, 4))
+                .into_styled(fill)
+                .draw(tile)
+        }
+        Tool::Polygon => Triangle::new(Point::new(1, 6), Point::new(3, 1), Point::new(6, 5))
+            .into_styled(stroke)
+            .draw(tile),
+        Tool::Gradient => Triangle::new(Point::new(1, 6), Point::new(6, 6), Point::new(6, 1))
+            .into_styled(fill)
+            .draw(tile),
+        Tool::Move => {
+            Line::new(Point::new(1, 3), Point::new(6, 3))
+                .into_styled(stroke)
+                .draw(tile)?;
+            Line::new(Point::new(3, 1), Point::new(3, 6))
+                .into_styled(stroke)
+                .draw(tile)
+        }
+    }
+}
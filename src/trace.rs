@@ -0,0 +1,65 @@
+//! Trace the background image and score how closely the canvas matches it
+//!
+//! Mode3 has a single bitmap layer, so the reference can't sit behind the
+//! canvas on its own background. `Faded` paints it into the canvas washed
+//! out toward white instead, which reads as a guide, and `Score` compares
+//! against the full color image read straight from the ROM.
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Bgr555},
+    prelude::*,
+};
+
+use crate::{background, color, scheduler::Pass};
+
+/// Sixteenths of the reference color kept in the guide
+const GUIDE_OPACITY: u8 = 5;
+
+/// Sum of channel differences within which a pixel counts as matching
+const TOLERANCE: u32 = 6;
+
+/// Background image pixel at `point`
+fn reference(point: Point) -> Bgr555 {
+    RawU16::new(background::pixel(point.x as usize, point.y as usize)).into()
+}
+
+/// Replaces the canvas with a washed out copy of the reference
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Faded;
+
+impl Pass for Faded {
+    fn pixel(&mut self, point: Point, _color: Bgr555) -> Option<Bgr555> {
+        Some(color::blend(Bgr555::WHITE, reference(point), GUIDE_OPACITY))
+    }
+}
+
+/// Counts canvas pixels that match the reference, leaving the canvas alone
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Score {
+    matched: u32,
+    total: u32,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Self {
+            matched: 0,
+            total: 0,
+        }
+    }
+
+    /// Share of matching pixels seen so far
+    pub fn percent(&self) -> u32 {
+        self.matched * 100 / self.total.max(1)
+    }
+}
+
+impl Pass for Score {
+    fn pixel(&mut self, point: Point, color: Bgr555) -> Option<Bgr555> {
+        if color::difference(color, reference(point)) <= TOLERANCE {
+            self.matched += 1;
+        }
+        self.total += 1;
+        None
+    }
+}
@@ -0,0 +1,80 @@
+//! First run walkthrough of the basic controls
+
+use embedded_graphics::prelude::*;
+
+use crate::input::{Input, Key};
+
+/// Actions the tutorial asks for, in order
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Step {
+    Move,
+    Paint,
+    Undo,
+}
+
+impl Step {
+    /// The step after this one, `None` after the last
+    fn next(self) -> Option<Self> {
+        match self {
+            Self::Move => Some(Self::Paint),
+            Self::Paint => Some(Self::Undo),
+            Self::Undo => None,
+        }
+    }
+
+    fn prompt(self) -> &'static str {
+        match self {
+            Self::Move => "Move with d-pad",
+            Self::Paint => "Press A to paint",
+            Self::Undo => "Press L to undo",
+        }
+    }
+
+    /// Check if `input` performs this step's action
+    fn done_by(self, input: &Input) -> bool {
+        match self {
+            Self::Move => input.held().direction() != Point::zero(),
+            Self::Paint => input.just_pressed(Key::A),
+            Self::Undo => input.just_pressed(Key::L) && !input.is_held(Key::R),
+        }
+    }
+}
+
+/// Prompt for each step, moving on when the user performs it
+pub struct Tutorial {
+    step: Option<Step>,
+}
+
+impl Tutorial {
+    /// Start at the first step if `active`
+    pub const fn new(active: bool) -> Self {
+        Self {
+            step: if active { Some(Step::Move) } else { None },
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// Text asking for the current step, `None` once finished
+    pub fn prompt(&self) -> Option<&'static str> {
+        self.step.map(Step::prompt)
+    }
+
+    /// Move on if `input` performs the current step, returns true if it did
+    pub fn update(&mut self, input: &Input) -> bool {
+        match self.step {
+            Some(step) if step.done_by(input) => {
+                self.step = step.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Stop before the last step
+    pub fn skip(&mut self) {
+        self.step = None;
+    }
+}
@@ -0,0 +1,160 @@
+use embedded_graphics::{pixelcolor::Bgr555, prelude::*};
+
+/// Previous color of a painted pixel
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PixelChange {
+    pub x: u8,
+    pub y: u8,
+    pub color: Bgr555,
+    /// Start of an undo step, later changes up to the next start are undone with it
+    pub first: bool,
+}
+
+impl PixelChange {
+    /// Location of the changed pixel
+    pub fn point(&self) -> Point {
+        Point::new(self.x as i32, self.y as i32)
+    }
+}
+
+/// Fixed capacity undo history backed by a ring buffer
+///
+/// Pushing onto a full stack drops the oldest entry.
+pub struct UndoStack<T, const N: usize> {
+    entries: [Option<T>; N],
+    head: usize, // next slot to write
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> UndoStack<T, N> {
+    pub fn new() -> Self {
+        Self {
+            entries: [None; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Push an entry, overwriting the oldest entry if full
+    pub fn push(&mut self, entry: T) {
+        self.entries[self.head] = Some(entry);
+        self.head = (self.head + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Pop the most recent entry
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.head = (self.head + N - 1) % N;
+        self.len -= 1;
+        self.entries[self.head].take()
+    }
+
+    /// Entries from the most recent to the oldest, without popping them
+    pub fn iter_newest(&self) -> impl Iterator<Item = &T> {
+        (1..=self.len).filter_map(move |back| self.entries[(self.head + N - back) % N].as_ref())
+    }
+
+    /// Drop all entries
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Number of entries in the stack
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Maximum number of entries before the oldest is dropped
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_reverse_push_order() {
+        let mut stack: UndoStack<u32, 4> = UndoStack::new();
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.pop(), Some(1));
+    }
+
+    #[test]
+    fn pop_on_empty_is_none() {
+        let mut stack: UndoStack<u32, 4> = UndoStack::new();
+        assert_eq!(stack.pop(), None);
+        stack.push(1);
+        stack.pop();
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn full_stack_drops_oldest() {
+        let mut stack: UndoStack<u32, 3> = UndoStack::new();
+        for entry in 1..=5 {
+            stack.push(entry);
+        }
+        assert!(stack.is_full());
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.pop(), Some(5));
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn wraps_around_the_ring() {
+        let mut stack: UndoStack<u32, 3> = UndoStack::new();
+        // push and pop across the end of the buffer several times
+        for round in 0..4 {
+            stack.push(round * 10);
+            stack.push(round * 10 + 1);
+            assert_eq!(stack.pop(), Some(round * 10 + 1));
+        }
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.pop(), Some(30));
+        assert_eq!(stack.pop(), Some(20));
+        assert_eq!(stack.pop(), Some(10));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn iter_newest_goes_back_in_time() {
+        let mut stack: UndoStack<u32, 3> = UndoStack::new();
+        for entry in 1..=4 {
+            stack.push(entry);
+        }
+        let mut newest = stack.iter_newest();
+        assert_eq!(newest.next(), Some(&4));
+        assert_eq!(newest.next(), Some(&3));
+        assert_eq!(newest.next(), Some(&2));
+        assert_eq!(newest.next(), None);
+    }
+
+    #[test]
+    fn clear_empties() {
+        let mut stack: UndoStack<u32, 3> = UndoStack::new();
+        stack.push(1);
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+    }
+}
@@ -0,0 +1,41 @@
+//! Callbacks run once per frame by the main loop
+//!
+//! Callbacks run from the main loop right after it wakes from vblank, not
+//! from the interrupt handler, so they may freely touch VRAM, OAM and
+//! state shared with the main loop. They share the vblank period with the
+//! rest of the frame's work, so keep them short or they push drawing into
+//! the visible period and tear. Registering from inside a callback is
+//! allowed and takes effect the next frame.
+
+use gba::sync::Static;
+
+pub type Callback = fn();
+
+/// Most callbacks that can be registered at once
+pub const MAX_CALLBACKS: usize = 8;
+
+static CALLBACKS: Static<[Option<Callback>; MAX_CALLBACKS]> = Static::new([None; MAX_CALLBACKS]);
+
+/// All callback slots are taken
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CallbacksFull;
+
+/// Run `callback` every frame, in registration order
+pub fn on_vblank(callback: Callback) -> Result<(), CallbacksFull> {
+    let mut callbacks = CALLBACKS.read();
+    let slot = callbacks
+        .iter_mut()
+        .find(|slot| slot.is_none())
+        .ok_or(CallbacksFull)?;
+    *slot = Some(callback);
+    CALLBACKS.write(callbacks);
+    Ok(())
+}
+
+/// Call from the main loop once per frame after waking from vblank
+pub fn run_callbacks() {
+    // copy out so callbacks can register more callbacks
+    for callback in CALLBACKS.read().iter().flatten() {
+        callback();
+    }
+}
@@ -0,0 +1,131 @@
+//! Magnified view of the canvas around the cursor
+//!
+//! The canvas waits in the snapshot while zoomed and every screen pixel is
+//! sampled from it. The screen doubles as the cache of the visible region:
+//! panning shifts the pixels already on screen and only samples the strip
+//! that comes into view.
+//!
+//! A pan still reads and writes every screen pixel, which takes a few
+//! frames whatever the level. Higher levels show fewer canvas pixels, so
+//! the view pans more often as the cursor moves and feels slower.
+
+use embedded_graphics::prelude::*;
+use gba::{vram::bitmap::Mode3, Color};
+
+use crate::canvas;
+
+const WIDTH: i32 = Mode3::WIDTH as i32;
+const HEIGHT: i32 = Mode3::HEIGHT as i32;
+
+/// Canvas pixels kept between the cursor and the view edge before panning
+const MARGIN: i32 = 8;
+
+/// Screen filling view of part of the canvas at a whole number magnification
+pub struct Zoom {
+    level: i32,
+    /// Canvas pixel in the top left corner of the screen
+    origin: Point,
+}
+
+impl Zoom {
+    /// `level` must divide the screen width and height
+    pub const fn new(level: u8) -> Self {
+        Self {
+            level: level as i32,
+            origin: Point::new(0, 0),
+        }
+    }
+
+    /// Snapshot the canvas and fill the screen with the view centered on
+    /// `point`, returns the screen position of `point`
+    pub fn open(&mut self, point: Point) -> Point {
+        canvas::snapshot_canvas();
+        let (width, height) = self.view_size();
+        self.origin = clamp_origin(point - Point::new(width / 2, height / 2), width, height);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                Mode3::write(x as usize, y as usize, self.sample(x, y));
+            }
+        }
+        self.screen_position(point)
+    }
+
+    /// Pan to keep canvas `point` away from the view edges, returns its
+    /// screen position
+    pub fn update(&mut self, point: Point) -> Point {
+        let origin = self.follow(point);
+        if origin != self.origin {
+            self.scroll(origin);
+        }
+        self.screen_position(point)
+    }
+
+    /// Put the canvas back on the screen
+    pub fn close(&self) {
+        canvas::restore_canvas();
+    }
+
+    /// Canvas pixels shown across and down
+    fn view_size(&self) -> (i32, i32) {
+        (WIDTH / self.level, HEIGHT / self.level)
+    }
+
+    /// Center of the block showing canvas `point`
+    fn screen_position(&self, point: Point) -> Point {
+        (point - self.origin) * self.level + Point::new(self.level / 2, self.level / 2)
+    }
+
+    /// Nearest origin that keeps `point` `MARGIN` from the view edges
+    fn follow(&self, point: Point) -> Point {
+        let (width, height) = self.view_size();
+        let axis = |origin: i32, p: i32, len: i32| {
+            let margin = MARGIN.min(len / 2 - 1);
+            origin.max(p + margin + 1 - len).min(p - margin)
+        };
+        let origin = Point::new(
+            axis(self.origin.x, point.x, width),
+            axis(self.origin.y, point.y, height),
+        );
+        clamp_origin(origin, width, height)
+    }
+
+    /// Move the view to `origin`, reusing the screen pixels that stay in view
+    fn scroll(&mut self, origin: Point) {
+        let shift = (origin - self.origin) * self.level;
+        self.origin = origin;
+        // walk away from the pixels still to be copied so none are overwritten first
+        for y in ordered(HEIGHT, shift.y < 0) {
+            for x in ordered(WIDTH, shift.x < 0) {
+                let source = Point::new(x, y) + shift;
+                let color = if (0..WIDTH).contains(&source.x) && (0..HEIGHT).contains(&source.y) {
+                    Mode3::read(source.x as usize, source.y as usize).unwrap_or(Color(0))
+                } else {
+                    self.sample(x, y)
+                };
+                Mode3::write(x as usize, y as usize, color);
+            }
+        }
+    }
+
+    /// Snapshot color under screen pixel `x`, `y`
+    fn sample(&self, x: i32, y: i32) -> Color {
+        canvas::snapshot_pixel(
+            self.origin.x + x / self.level,
+            self.origin.y + y / self.level,
+        )
+        .unwrap_or(Color(0))
+    }
+}
+
+/// Keep a `width` by `height` view inside the canvas
+fn clamp_origin(origin: Point, width: i32, height: i32) -> Point {
+    Point::new(
+        origin.x.max(0).min(WIDTH - width),
+        origin.y.max(0).min(HEIGHT - height),
+    )
+}
+
+/// `0..len`, backwards if `reverse`
+fn ordered(len: i32, reverse: bool) -> impl Iterator<Item = i32> {
+    (0..len).map(move |i| if reverse { len - 1 - i } else { i })
+}